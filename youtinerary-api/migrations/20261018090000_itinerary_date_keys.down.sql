@@ -0,0 +1,6 @@
+-- Add down migration script here
+alter table itinerary_end_date
+drop constraint itinerary_end_date_pk;
+
+alter table itinerary_start_date
+drop constraint itinerary_start_date_pk;
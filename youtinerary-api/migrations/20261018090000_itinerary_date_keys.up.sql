@@ -0,0 +1,8 @@
+-- Add up migration script here
+alter table itinerary_start_date
+add constraint itinerary_start_date_pk
+primary key (itinerary_id);
+
+alter table itinerary_end_date
+add constraint itinerary_end_date_pk
+primary key (itinerary_id);
@@ -0,0 +1,3 @@
+-- Add up migration script here
+alter table itineraries
+add column status itinerary_status default 'draft' not null;
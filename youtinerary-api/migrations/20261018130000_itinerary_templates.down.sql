@@ -0,0 +1,6 @@
+-- Add down migration script here
+alter table itinerary_shares
+drop constraint itinerary_shares_pk;
+
+alter table itineraries
+drop column is_template;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+alter table itineraries
+add column is_template boolean default false not null;
+
+alter table itinerary_shares
+add constraint itinerary_shares_pk
+primary key (itinerary_id, user_id);
@@ -0,0 +1,14 @@
+-- Add up migration script here
+create table itinerary_feeds
+(
+    token uuid default gen_random_uuid() not null
+    constraint itinerary_feeds_pk
+    primary key,
+    itinerary_id integer not null
+    constraint itinerary_feeds_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade
+    constraint itinerary_feeds_unique_itineraries
+    unique,
+    created_at timestamp default now() not null
+);
@@ -0,0 +1,18 @@
+-- Add down migration script here
+drop table if exists itinerary_calendar_events;
+
+drop type calendar_event_item_type;
+
+update activities
+set location = point(0, 0)
+where location is null;
+
+alter table activities
+alter column location set not null;
+
+update stays
+set location = point(0, 0)
+where location is null;
+
+alter table stays
+alter column location set not null;
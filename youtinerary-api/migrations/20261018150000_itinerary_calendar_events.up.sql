@@ -0,0 +1,22 @@
+-- Add up migration script here
+alter table stays
+alter column location drop not null;
+
+alter table activities
+alter column location drop not null;
+
+create type calendar_event_item_type as enum ('flight', 'stay', 'activity');
+
+create table itinerary_calendar_events
+(
+    itinerary_id integer not null
+    constraint itinerary_calendar_events_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    uid varchar(255) not null,
+    item_type calendar_event_item_type not null,
+    item_id integer not null,
+    imported_at timestamp default now() not null,
+    constraint itinerary_calendar_events_pk
+    primary key (itinerary_id, uid)
+);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+alter table travel_legs
+drop column start_timezone,
+drop column end_timezone;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+alter table travel_legs
+add column start_timezone varchar(64),
+add column end_timezone varchar(64);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+alter table flights
+drop column departure_airport,
+drop column arrival_airport,
+drop column departure_location,
+drop column arrival_location;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+alter table flights
+add column departure_airport varchar(3),
+add column arrival_airport varchar(3),
+add column departure_location point,
+add column arrival_location point;
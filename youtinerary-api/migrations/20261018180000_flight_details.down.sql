@@ -0,0 +1,14 @@
+-- Add down migration script here
+drop table flight_passengers;
+
+alter table flights
+drop column flight_number,
+drop column operating_carrier,
+drop column departure_terminal,
+drop column departure_gate,
+drop column arrival_terminal,
+drop column arrival_gate,
+drop column cabin_class,
+drop column aircraft_type;
+
+drop type cabin_class;
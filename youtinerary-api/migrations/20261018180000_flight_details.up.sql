@@ -0,0 +1,27 @@
+-- Add up migration script here
+create type cabin_class as enum ('economy', 'premium_economy', 'business', 'first');
+
+alter table flights
+add column flight_number varchar(8),
+add column operating_carrier varchar(255),
+add column departure_terminal varchar(16),
+add column departure_gate varchar(16),
+add column arrival_terminal varchar(16),
+add column arrival_gate varchar(16),
+add column cabin_class cabin_class,
+add column aircraft_type varchar(64);
+
+create table flight_passengers
+(
+    flight_id integer not null
+    constraint flight_passengers_flights_id_fk references flights
+    on update cascade
+    on delete cascade,
+    position integer not null,
+
+    name varchar(255) not null,
+    booking_reference varchar(16),
+    seat varchar(4),
+
+    constraint flight_passengers_pk primary key (flight_id, position)
+);
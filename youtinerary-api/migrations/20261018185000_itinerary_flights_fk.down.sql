@@ -0,0 +1,10 @@
+-- Add down migration script here
+alter table itinerary_flights
+drop constraint itinerary_flights_flights_id_fk;
+
+alter table itinerary_flights
+add constraint flight_flights_itineraries_id_fk
+    foreign key (flight_id) references itineraries
+    on update cascade
+    on delete cascade
+    not valid;
@@ -0,0 +1,13 @@
+-- Add up migration script here
+-- flight_id pointed at itineraries, so links could name any itinerary id instead of a flight.
+alter table itinerary_flights
+drop constraint flight_flights_itineraries_id_fk;
+
+delete from itinerary_flights x
+where not exists (select 1 from flights f where f.id = x.flight_id);
+
+alter table itinerary_flights
+add constraint itinerary_flights_flights_id_fk
+    foreign key (flight_id) references flights
+    on update cascade
+    on delete cascade;
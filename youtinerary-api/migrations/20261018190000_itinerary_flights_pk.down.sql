@@ -0,0 +1,3 @@
+-- Add down migration script here
+alter table itinerary_flights
+drop constraint itinerary_flights_pk;
@@ -0,0 +1,10 @@
+-- Add up migration script here
+-- A flight is linked to an itinerary once; repeated links keep the oldest row.
+delete from itinerary_flights x
+using itinerary_flights y
+where x.itinerary_id = y.itinerary_id
+    and x.flight_id = y.flight_id
+    and x.ctid > y.ctid;
+
+alter table itinerary_flights
+add constraint itinerary_flights_pk primary key (itinerary_id, flight_id);
@@ -0,0 +1,35 @@
+-- Add down migration script here
+create or replace view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(items.start_date) as start_date,
+            max(items.end_date) as end_date
+        from (
+            select
+                (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+                (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date
+            from itinerary_flights x
+                join flights f on f.id = x.flight_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select t.start_date, t.end_date
+            from itinerary_items it
+                join travel_legs t on t.itinerary_item_id = it.id
+            where it.itinerary_id = i.itinerary_id
+        ) items
+    ) derived on true;
+
+drop table itinerary_stays;
+
+alter table stays
+drop column confirmation_number,
+drop column address,
+drop column check_out_time,
+drop column check_in_time;
@@ -0,0 +1,53 @@
+-- Add up migration script here
+alter table stays
+add column check_in_time time,
+add column check_out_time time,
+add column address varchar(255),
+add column confirmation_number varchar(50);
+
+create table itinerary_stays
+(
+    itinerary_id integer not null
+    constraint itinerary_stays_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    stay_id integer not null
+    constraint itinerary_stays_stays_id_fk
+    references stays
+    on update cascade on delete cascade,
+    constraint itinerary_stays_pk
+    primary key (itinerary_id, stay_id)
+);
+
+-- Stays count towards the dates derived from the trip's items.
+create or replace view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(items.start_date) as start_date,
+            max(items.end_date) as end_date
+        from (
+            select
+                (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+                (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date
+            from itinerary_flights x
+                join flights f on f.id = x.flight_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select st.start_date, st.end_date
+            from itinerary_stays x
+                join stays st on st.id = x.stay_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select t.start_date, t.end_date
+            from itinerary_items it
+                join travel_legs t on t.itinerary_item_id = it.id
+            where it.itinerary_id = i.itinerary_id
+        ) items
+    ) derived on true;
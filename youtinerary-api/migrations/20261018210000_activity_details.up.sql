@@ -0,0 +1,74 @@
+-- Add up migration script here
+create type activity_category as enum (
+    'sightseeing',
+    'dining',
+    'event',
+    'tour',
+    'outdoors',
+    'shopping',
+    'nightlife',
+    'wellness',
+    'other'
+);
+
+alter table activities
+add column category activity_category not null default 'other',
+add column start_time time,
+add column end_time time,
+add column address varchar(255),
+add column booking_reference varchar(50),
+add column cost numeric(12, 2),
+add column cost_currency char(3),
+add constraint activities_cost_currency_check check ((cost is null) = (cost_currency is null));
+
+create table itinerary_activities
+(
+    itinerary_id integer not null
+    constraint itinerary_activities_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    activity_id integer not null
+    constraint itinerary_activities_activities_id_fk
+    references activities
+    on update cascade on delete cascade,
+    constraint itinerary_activities_pk
+    primary key (itinerary_id, activity_id)
+);
+
+-- Activities count towards the dates derived from the trip's items.
+create or replace view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(items.start_date) as start_date,
+            max(items.end_date) as end_date
+        from (
+            select
+                (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+                (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date
+            from itinerary_flights x
+                join flights f on f.id = x.flight_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select st.start_date, st.end_date
+            from itinerary_stays x
+                join stays st on st.id = x.stay_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select a.start_date, a.end_date
+            from itinerary_activities x
+                join activities a on a.id = x.activity_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select t.start_date, t.end_date
+            from itinerary_items it
+                join travel_legs t on t.itinerary_item_id = it.id
+            where it.itinerary_id = i.itinerary_id
+        ) items
+    ) derived on true;
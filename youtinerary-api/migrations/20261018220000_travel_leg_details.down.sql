@@ -0,0 +1,26 @@
+-- Add down migration script here
+alter table travel_legs
+drop column cabin,
+drop column vehicle,
+drop column platform,
+drop column seat,
+drop column coach,
+drop column service_number,
+drop column booking_reference,
+drop column operator,
+drop column end_place,
+drop column start_place,
+drop column end_time,
+drop column start_time;
+
+update travel_legs
+set start_location = point(0, 0)
+where start_location is null;
+
+update travel_legs
+set end_location = point(0, 0)
+where end_location is null;
+
+alter table travel_legs
+alter column start_location set not null,
+alter column end_location set not null;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+alter table travel_legs
+alter column start_location drop not null,
+alter column end_location drop not null,
+add column start_time timestamptz,
+add column end_time timestamptz,
+add column start_place varchar(255),
+add column end_place varchar(255),
+add column operator varchar(255),
+add column booking_reference varchar(50),
+add column service_number varchar(16),
+add column coach varchar(8),
+add column seat varchar(8),
+add column platform varchar(8),
+add column vehicle varchar(64),
+add column cabin varchar(16);
@@ -0,0 +1,129 @@
+-- Add down migration script here
+drop view itinerary_dates;
+drop view itinerary_item_summaries;
+
+create table itinerary_flights (
+    itinerary_id integer not null
+    constraint itinerary_flights_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    flight_id integer not null
+    constraint itinerary_flights_flights_id_fk
+    references flights
+    on update cascade on delete cascade,
+    constraint itinerary_flights_pk
+    primary key (itinerary_id, flight_id)
+);
+
+create table itinerary_stays
+(
+    itinerary_id integer not null
+    constraint itinerary_stays_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    stay_id integer not null
+    constraint itinerary_stays_stays_id_fk
+    references stays
+    on update cascade on delete cascade,
+    constraint itinerary_stays_pk
+    primary key (itinerary_id, stay_id)
+);
+
+create table itinerary_activities
+(
+    itinerary_id integer not null
+    constraint itinerary_activities_itineraries_id_fk
+    references itineraries
+    on update cascade on delete cascade,
+    activity_id integer not null
+    constraint itinerary_activities_activities_id_fk
+    references activities
+    on update cascade on delete cascade,
+    constraint itinerary_activities_pk
+    primary key (itinerary_id, activity_id)
+);
+
+insert into itinerary_flights (itinerary_id, flight_id)
+select i.itinerary_id, f.id
+from flights f
+    join itinerary_items i on i.id = f.itinerary_item_id;
+
+insert into itinerary_stays (itinerary_id, stay_id)
+select i.itinerary_id, s.id
+from stays s
+    join itinerary_items i on i.id = s.itinerary_item_id;
+
+insert into itinerary_activities (itinerary_id, activity_id)
+select i.itinerary_id, a.id
+from activities a
+    join itinerary_items i on i.id = a.itinerary_item_id;
+
+alter table flights
+drop column itinerary_item_id;
+
+alter table stays
+drop column itinerary_item_id;
+
+alter table activities
+drop column itinerary_item_id;
+
+delete from itinerary_items
+where item_type <> 'travel_leg';
+
+alter table itinerary_items
+add column name varchar(255);
+
+update itinerary_items i
+set name = t.name
+from travel_legs t
+where t.itinerary_item_id = i.id;
+
+alter table itinerary_items
+alter column name set not null,
+drop column visibility,
+drop column position,
+drop column item_type;
+
+alter table travel_legs
+drop constraint travel_legs_itinerary_item_id_key,
+drop column name;
+
+drop type itinerary_item_visibility;
+drop type itinerary_item_type;
+
+create view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(items.start_date) as start_date,
+            max(items.end_date) as end_date
+        from (
+            select
+                (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+                (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date
+            from itinerary_flights x
+                join flights f on f.id = x.flight_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select st.start_date, st.end_date
+            from itinerary_stays x
+                join stays st on st.id = x.stay_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select a.start_date, a.end_date
+            from itinerary_activities x
+                join activities a on a.id = x.activity_id
+            where x.itinerary_id = i.itinerary_id
+            union all
+            select t.start_date, t.end_date
+            from itinerary_items it
+                join travel_legs t on t.itinerary_item_id = it.id
+            where it.itinerary_id = i.itinerary_id
+        ) items
+    ) derived on true;
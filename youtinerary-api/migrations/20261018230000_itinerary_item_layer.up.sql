@@ -0,0 +1,181 @@
+-- Add up migration script here
+-- Every kind of booking joins its itinerary through an item, which carries what is common to
+-- all of them. What an item shows is still stored on its kind's own table.
+create type itinerary_item_type as enum ('flight', 'stay', 'activity', 'travel_leg');
+
+-- Items only editors see are left out for viewers and the public calendar feed.
+create type itinerary_item_visibility as enum ('everyone', 'editors');
+
+alter table travel_legs
+add column name varchar(255);
+
+update travel_legs t
+set name = i.name
+from itinerary_items i
+where i.id = t.itinerary_item_id;
+
+alter table travel_legs
+alter column name set not null,
+add constraint travel_legs_itinerary_item_id_key unique (itinerary_item_id);
+
+-- `position` orders items that start at the same time, unpositioned ones go last.
+alter table itinerary_items
+drop column name,
+add column item_type itinerary_item_type not null default 'travel_leg',
+add column position integer,
+add column visibility itinerary_item_visibility not null default 'everyone',
+add column source_id integer;
+
+alter table itinerary_items
+alter column item_type drop default;
+
+-- A booking linked to several itineraries keeps the first one, which is how the app treats it.
+insert into itinerary_items (itinerary_id, item_type, source_id)
+select distinct on (flight_id) itinerary_id, 'flight', flight_id
+from itinerary_flights
+order by flight_id, itinerary_id;
+
+insert into itinerary_items (itinerary_id, item_type, source_id)
+select distinct on (stay_id) itinerary_id, 'stay', stay_id
+from itinerary_stays
+order by stay_id, itinerary_id;
+
+insert into itinerary_items (itinerary_id, item_type, source_id)
+select distinct on (activity_id) itinerary_id, 'activity', activity_id
+from itinerary_activities
+order by activity_id, itinerary_id;
+
+alter table flights
+add column itinerary_item_id integer
+    constraint flights_itinerary_items_id_fk
+    references itinerary_items
+    on update cascade on delete cascade;
+
+alter table stays
+add column itinerary_item_id integer
+    constraint stays_itinerary_items_id_fk
+    references itinerary_items
+    on update cascade on delete cascade;
+
+alter table activities
+add column itinerary_item_id integer
+    constraint activities_itinerary_items_id_fk
+    references itinerary_items
+    on update cascade on delete cascade;
+
+update flights f
+set itinerary_item_id = i.id
+from itinerary_items i
+where i.item_type = 'flight'
+    and i.source_id = f.id;
+
+update stays s
+set itinerary_item_id = i.id
+from itinerary_items i
+where i.item_type = 'stay'
+    and i.source_id = s.id;
+
+update activities a
+set itinerary_item_id = i.id
+from itinerary_items i
+where i.item_type = 'activity'
+    and i.source_id = a.id;
+
+-- Bookings outside of any itinerary couldn't be reached by anyone.
+delete from flights where itinerary_item_id is null;
+delete from stays where itinerary_item_id is null;
+delete from activities where itinerary_item_id is null;
+
+alter table flights
+alter column itinerary_item_id set not null,
+add constraint flights_itinerary_item_id_key unique (itinerary_item_id);
+
+alter table stays
+alter column itinerary_item_id set not null,
+add constraint stays_itinerary_item_id_key unique (itinerary_item_id);
+
+alter table activities
+alter column itinerary_item_id set not null,
+add constraint activities_itinerary_item_id_key unique (itinerary_item_id);
+
+alter table itinerary_items
+drop column source_id;
+
+drop view itinerary_dates;
+drop table itinerary_flights;
+drop table itinerary_stays;
+drop table itinerary_activities;
+
+-- The fields every item has, whatever its kind. `kind_id` is the row of the kind's own table,
+-- dates are local to the item and `starts_at` is the instant the timeline orders it by.
+create view itinerary_item_summaries as
+select
+    i.id,
+    i.itinerary_id,
+    i.item_type,
+    i.position,
+    i.visibility,
+    k.kind_id,
+    k.title,
+    k.start_date,
+    k.end_date,
+    k.starts_at,
+    k.notes
+from itinerary_items i
+    join lateral (
+        select
+            f.id as kind_id,
+            concat_ws(' ', f.airline, f.flight_number) as title,
+            (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+            (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date,
+            f.departure_time as starts_at,
+            f.notes
+        from flights f
+        where f.itinerary_item_id = i.id
+        union all
+        select
+            s.id,
+            s.summary,
+            s.start_date,
+            s.end_date,
+            s.start_date::timestamp at time zone 'UTC',
+            s.notes
+        from stays s
+        where s.itinerary_item_id = i.id
+        union all
+        select
+            a.id,
+            a.summary,
+            a.start_date,
+            a.end_date,
+            (a.start_date + coalesce(a.start_time, time '00:00')) at time zone 'UTC',
+            a.notes
+        from activities a
+        where a.itinerary_item_id = i.id
+        union all
+        select
+            t.id,
+            t.name,
+            t.start_date,
+            t.end_date,
+            coalesce(t.start_time, t.start_date::timestamp at time zone 'UTC'),
+            t.notes
+        from travel_legs t
+        where t.itinerary_item_id = i.id
+    ) k on true;
+
+create view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(x.start_date) as start_date,
+            max(x.end_date) as end_date
+        from itinerary_item_summaries x
+        where x.itinerary_id = i.itinerary_id
+    ) derived on true;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+drop table inbox_bookings;
+drop table inbox_messages;
+drop type inbox_message_status;
+
+alter table users
+drop column inbox_token;
@@ -0,0 +1,44 @@
+-- Add up migration script here
+-- Confirmations forwarded to plans+<inbox_token>@<domain> land in the user's inbox.
+alter table users
+add column inbox_token uuid default gen_random_uuid() not null,
+add constraint users_unique_inbox_tokens unique (inbox_token);
+
+create type inbox_message_status as enum ('queued', 'parsed', 'failed');
+
+-- Messages are kept as received, so they can be parsed again once the parsers get better.
+create table inbox_messages
+(
+    id serial not null
+    constraint inbox_messages_pk
+    primary key,
+    user_id integer not null
+    constraint inbox_messages_users_id_fk
+    references users
+    on update cascade on delete cascade,
+    sender varchar(255) not null,
+    subject text,
+    raw text not null,
+    status inbox_message_status default 'queued' not null,
+    parser text,
+    error text,
+    received_at timestamp default now() not null,
+    parsed_at timestamp
+);
+
+create index inbox_messages_queued_idx on inbox_messages (received_at) where status = 'queued';
+
+-- A booking read from a message that isn't in any itinerary yet. `proposal` is the body of the
+-- create endpoint for its kind, tagged with the kind.
+create table inbox_bookings
+(
+    id serial not null
+    constraint inbox_bookings_pk
+    primary key,
+    message_id integer not null
+    constraint inbox_bookings_inbox_messages_id_fk
+    references inbox_messages
+    on update cascade on delete cascade,
+    proposal jsonb not null,
+    created_at timestamp default now() not null
+);
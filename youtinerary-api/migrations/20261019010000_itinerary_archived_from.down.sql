@@ -0,0 +1,3 @@
+-- Add down migration script here
+alter table itineraries
+drop column archived_from;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- The status an archived itinerary had, so restoring it brings it back as it was. Trips archived
+-- before this was recorded come back as drafts.
+alter table itineraries
+add column archived_from itinerary_status;
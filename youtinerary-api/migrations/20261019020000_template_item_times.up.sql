@@ -0,0 +1,57 @@
+-- Add up migration script here
+-- Where each item of a template falls: days after the template's first day, and the local wall
+-- clock times of flights and travel legs, which are resolved in their own timezone on the day a
+-- template is instantiated. Stays and activities already keep local times of their own.
+create table template_item_times (
+    itinerary_item_id integer primary key references itinerary_items (id) on delete cascade,
+    start_day integer not null,
+    start_time time,
+    end_day integer not null,
+    end_time time
+);
+
+insert into template_item_times (itinerary_item_id, start_day, start_time, end_day, end_time)
+select
+    f.itinerary_item_id,
+    (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date - d.start_date,
+    (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::time,
+    (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date - d.start_date,
+    (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::time
+from flights f
+    join itinerary_items x on x.id = f.itinerary_item_id
+    join itineraries i on i.itinerary_id = x.itinerary_id
+    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+where i.is_template
+    and d.start_date is not null;
+
+insert into template_item_times (itinerary_item_id, start_day, end_day)
+select s.itinerary_item_id, s.start_date - d.start_date, s.end_date - d.start_date
+from stays s
+    join itinerary_items x on x.id = s.itinerary_item_id
+    join itineraries i on i.itinerary_id = x.itinerary_id
+    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+where i.is_template
+    and d.start_date is not null;
+
+insert into template_item_times (itinerary_item_id, start_day, end_day)
+select a.itinerary_item_id, a.start_date - d.start_date, a.end_date - d.start_date
+from activities a
+    join itinerary_items x on x.id = a.itinerary_item_id
+    join itineraries i on i.itinerary_id = x.itinerary_id
+    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+where i.is_template
+    and d.start_date is not null;
+
+insert into template_item_times (itinerary_item_id, start_day, start_time, end_day, end_time)
+select
+    t.itinerary_item_id,
+    t.start_date - d.start_date,
+    (t.start_time at time zone coalesce(t.start_timezone, 'UTC'))::time,
+    t.end_date - d.start_date,
+    (t.end_time at time zone coalesce(t.end_timezone, 'UTC'))::time
+from travel_legs t
+    join itinerary_items x on x.id = t.itinerary_item_id
+    join itineraries i on i.itinerary_id = x.itinerary_id
+    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+where i.is_template
+    and d.start_date is not null;
@@ -0,0 +1,12 @@
+-- Add down migration script here
+alter table activities
+drop constraint activities_itinerary_item_id_check,
+alter column itinerary_item_id set not null;
+
+alter table stays
+drop constraint stays_itinerary_item_id_check,
+alter column itinerary_item_id set not null;
+
+alter table flights
+drop constraint flights_itinerary_item_id_check,
+alter column itinerary_item_id set not null;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Bookings that were never linked to an itinerary may exist without an item. The checks are not
+-- validated against them, but every booking written from now on needs an item.
+alter table flights
+alter column itinerary_item_id drop not null,
+add constraint flights_itinerary_item_id_check check (itinerary_item_id is not null) not valid;
+
+alter table stays
+alter column itinerary_item_id drop not null,
+add constraint stays_itinerary_item_id_check check (itinerary_item_id is not null) not valid;
+
+alter table activities
+alter column itinerary_item_id drop not null,
+add constraint activities_itinerary_item_id_check check (itinerary_item_id is not null) not valid;
@@ -0,0 +1,75 @@
+-- Add down migration script here
+drop view itinerary_dates;
+drop view itinerary_item_summaries;
+
+create view itinerary_item_summaries as
+select
+    i.id,
+    i.itinerary_id,
+    i.item_type,
+    i.position,
+    i.visibility,
+    k.kind_id,
+    k.title,
+    k.start_date,
+    k.end_date,
+    k.starts_at,
+    k.notes
+from itinerary_items i
+    join lateral (
+        select
+            f.id as kind_id,
+            concat_ws(' ', f.airline, f.flight_number) as title,
+            (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date as start_date,
+            (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date as end_date,
+            f.departure_time as starts_at,
+            f.notes
+        from flights f
+        where f.itinerary_item_id = i.id
+        union all
+        select
+            s.id,
+            s.summary,
+            s.start_date,
+            s.end_date,
+            s.start_date::timestamp at time zone 'UTC',
+            s.notes
+        from stays s
+        where s.itinerary_item_id = i.id
+        union all
+        select
+            a.id,
+            a.summary,
+            a.start_date,
+            a.end_date,
+            (a.start_date + coalesce(a.start_time, time '00:00')) at time zone 'UTC',
+            a.notes
+        from activities a
+        where a.itinerary_item_id = i.id
+        union all
+        select
+            t.id,
+            t.name,
+            t.start_date,
+            t.end_date,
+            coalesce(t.start_time, t.start_date::timestamp at time zone 'UTC'),
+            t.notes
+        from travel_legs t
+        where t.itinerary_item_id = i.id
+    ) k on true;
+
+create view itinerary_dates as
+select
+    i.itinerary_id,
+    coalesce(s.start_date, derived.start_date) as start_date,
+    coalesce(e.end_date, derived.end_date) as end_date
+from itineraries i
+    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+    left join lateral (
+        select
+            min(x.start_date) as start_date,
+            max(x.end_date) as end_date
+        from itinerary_item_summaries x
+        where x.itinerary_id = i.itinerary_id
+    ) derived on true;
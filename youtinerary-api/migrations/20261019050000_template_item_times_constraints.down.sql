@@ -0,0 +1,7 @@
+-- Add down migration script here
+alter table template_item_times
+    rename constraint template_item_times_itinerary_items_id_fk
+        to template_item_times_itinerary_item_id_fkey;
+
+alter table template_item_times
+    rename constraint template_item_times_pk to template_item_times_pkey;
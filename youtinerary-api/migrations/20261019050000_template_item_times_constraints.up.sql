@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Give the keys of template_item_times names like those of the other tables.
+alter table template_item_times
+    rename constraint template_item_times_pkey to template_item_times_pk;
+
+alter table template_item_times
+    rename constraint template_item_times_itinerary_item_id_fkey
+        to template_item_times_itinerary_items_id_fk;
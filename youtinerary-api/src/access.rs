@@ -0,0 +1,150 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use sqlx::PgConnection;
+
+use crate::{ItemVisibility, ItineraryShareType, ItineraryStatus};
+
+/// Why a user may not read or change an itinerary's items.
+#[derive(Debug)]
+pub enum AccessError {
+    /// The itinerary doesn't exist or isn't shared with the user, which look the same to them.
+    NotFound,
+    ReadOnly,
+    Archived,
+    Unexpected(sqlx::Error),
+}
+
+impl From<sqlx::Error> for AccessError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for AccessError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::ReadOnly => (
+                StatusCode::FORBIDDEN,
+                "The itinerary is shared with you as a viewer",
+            )
+                .into_response(),
+            Self::Archived => (
+                StatusCode::CONFLICT,
+                "Restore the itinerary before editing it",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Checks that the user owns the itinerary or is one of its editors, and that it isn't archived.
+///
+/// The itinerary row stays share-locked until the transaction ends, so it can't be archived or
+/// deleted while its items are being written.
+pub async fn check_can_edit(
+    connection: &mut PgConnection,
+    user_id: i32,
+    itinerary_id: i32,
+) -> Result<(), AccessError> {
+    let itinerary = sqlx::query!(
+        r#"
+            select
+                i.user_id = $1 as "is_owner!",
+                i.status as "status: ItineraryStatus",
+                s.share_type as "share_type?: ItineraryShareType"
+            from itineraries i
+                left join itinerary_shares s
+                    on s.itinerary_id = i.itinerary_id
+                    and s.user_id = $1
+            where i.itinerary_id = $2
+                and (i.user_id = $1 or s.user_id is not null)
+            for share of i
+        "#,
+        user_id,
+        itinerary_id
+    )
+    .fetch_optional(connection)
+    .await?
+    .ok_or(AccessError::NotFound)?;
+
+    if !itinerary.is_owner && itinerary.share_type != Some(ItineraryShareType::Editor) {
+        return Err(AccessError::ReadOnly);
+    }
+    if itinerary.status == ItineraryStatus::Archived {
+        return Err(AccessError::Archived);
+    }
+
+    Ok(())
+}
+
+/// How a user came to see an itinerary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItineraryRole {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+impl ItineraryRole {
+    pub fn new(is_owner: bool, share_type: Option<ItineraryShareType>) -> Self {
+        match (is_owner, share_type) {
+            (true, _) => Self::Owner,
+            (false, Some(ItineraryShareType::Editor)) => Self::Editor,
+            (false, _) => Self::Viewer,
+        }
+    }
+
+    pub fn can_edit(self) -> bool {
+        self != Self::Viewer
+    }
+
+    /// Items only editors see are hidden from viewers, the owner sees everything.
+    pub fn can_see(self, visibility: ItemVisibility) -> bool {
+        visibility == ItemVisibility::Everyone || self != Self::Viewer
+    }
+}
+
+/// Checks that the user owns the itinerary or that it is shared with them in any role, and
+/// tells which.
+pub async fn check_can_view(
+    connection: &mut PgConnection,
+    user_id: i32,
+    itinerary_id: i32,
+) -> Result<ItineraryRole, AccessError> {
+    find_role(connection, user_id, itinerary_id)
+        .await?
+        .ok_or(AccessError::NotFound)
+}
+
+/// The user's role on the itinerary, or `None` when it doesn't exist or isn't shared with them.
+pub async fn find_role(
+    connection: &mut PgConnection,
+    user_id: i32,
+    itinerary_id: i32,
+) -> Result<Option<ItineraryRole>, sqlx::Error> {
+    let itinerary = sqlx::query!(
+        r#"
+            select
+                i.user_id = $1 as "is_owner!",
+                s.share_type as "share_type?: ItineraryShareType"
+            from itineraries i
+                left join itinerary_shares s
+                    on s.itinerary_id = i.itinerary_id
+                    and s.user_id = $1
+            where i.itinerary_id = $2
+                and (i.user_id = $1 or s.user_id is not null)
+        "#,
+        user_id,
+        itinerary_id
+    )
+    .fetch_optional(connection)
+    .await?;
+
+    Ok(itinerary.map(|x| ItineraryRole::new(x.is_owner, x.share_type)))
+}
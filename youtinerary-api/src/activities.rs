@@ -0,0 +1,190 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::fields::{coordinates, invalid, optional_text, required_text, InvalidField};
+use crate::items::Item;
+use crate::timeline::{ActivityEntry, Coordinates};
+use crate::{ActivityCategory, ItemVisibility};
+
+/// A price as printed on a booking. The amount is decimal text, such as `"12.50"`, so that it
+/// never goes through floating point on its way to the `numeric` column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cost {
+    pub amount: String,
+    /// ISO 4217 code, such as `EUR`.
+    pub currency: String,
+}
+
+impl Cost {
+    /// Reads a cost back from its columns, which are either both set or both empty.
+    pub fn from_columns(amount: Option<String>, currency: Option<String>) -> Option<Self> {
+        Some(Self {
+            amount: amount?,
+            currency: currency?,
+        })
+    }
+
+    /// Normalizes the amount to two decimals and the currency to upper case.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        let amount = self.amount.trim();
+        let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let whole = whole.trim_start_matches('0');
+        if whole.len() > 10
+            || fraction.len() > 2
+            || (whole.is_empty() && fraction.is_empty() && !amount.starts_with('0'))
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid(
+                "cost.amount",
+                "must be an amount like 12.50, with at most two decimals",
+            ));
+        }
+
+        let currency = self.currency.trim().to_uppercase();
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(invalid("cost.currency", "must be a currency code like EUR"));
+        }
+
+        Ok(Self {
+            amount: format!(
+                "{}.{:0<2}",
+                if whole.is_empty() { "0" } else { whole },
+                fraction
+            ),
+            currency,
+        })
+    }
+}
+
+/// An activity as clients write it. Dates and times are local to where it takes place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityFields {
+    pub summary: String,
+    #[serde(default)]
+    pub category: ActivityCategory,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Local times on the first and last day, such as `09:30`. Activities without them take
+    /// the whole day.
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub booking_reference: Option<String>,
+    pub cost: Option<Cost>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl ActivityFields {
+    /// Trims the text fields and checks the activity ends after it starts.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        if self.end_date < self.start_date {
+            return Err(invalid("end_date", "must not be before start_date"));
+        }
+        if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+            if self.end_date == self.start_date && end_time < start_time {
+                return Err(invalid("end_time", "must not be before start_time"));
+            }
+        }
+
+        Ok(ActivityFields {
+            summary: required_text("summary", self.summary, 255)?,
+            category: self.category,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            address: optional_text("address", self.address, 255)?,
+            location: coordinates("location", self.location)?,
+            booking_reference: optional_text("booking_reference", self.booking_reference, 50)?,
+            cost: self.cost.map(Cost::validated).transpose()?,
+            notes: optional_text("notes", Some(self.notes), 255)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<ActivityEntry> for ActivityFields {
+    fn from(value: ActivityEntry) -> Self {
+        Self {
+            summary: value.summary,
+            category: value.category,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            address: value.address,
+            location: value.location,
+            booking_reference: value.booking_reference,
+            cost: value.cost,
+            notes: value.notes,
+        }
+    }
+}
+
+/// Loads the activities of an itinerary in the order they start, or only `activity_id` when
+/// given.
+pub async fn get_activities(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    activity_id: Option<i32>,
+) -> sqlx::Result<Vec<ActivityEntry>> {
+    let activities = sqlx::query!(
+        r#"
+            select
+                a.id,
+                a.summary,
+                a.category as "category: ActivityCategory",
+                a.start_date,
+                a.end_date,
+                a.start_time,
+                a.end_time,
+                a.address,
+                a.location[0] as longitude,
+                a.location[1] as latitude,
+                a.booking_reference,
+                a.cost::text as cost,
+                a.cost_currency,
+                a.notes,
+                i.id as item_id,
+                i.position,
+                i.visibility as "visibility: ItemVisibility"
+            from activities a
+                join itinerary_items i on i.id = a.itinerary_item_id
+            where i.itinerary_id = $1
+                and ($2::integer is null or a.id = $2)
+            order by a.start_date, a.start_time nulls first, i.position nulls last, a.id
+        "#,
+        itinerary_id,
+        activity_id
+    )
+    .fetch_all(connection)
+    .await?;
+
+    Ok(activities
+        .into_iter()
+        .map(|activity| ActivityEntry {
+            id: activity.id,
+            summary: activity.summary,
+            category: activity.category,
+            start_date: activity.start_date,
+            end_date: activity.end_date,
+            start_time: activity.start_time,
+            end_time: activity.end_time,
+            address: activity.address,
+            location: Coordinates::from_point(activity.longitude, activity.latitude),
+            booking_reference: activity.booking_reference,
+            cost: Cost::from_columns(activity.cost, activity.cost_currency),
+            item: Item {
+                id: activity.item_id,
+                position: activity.position,
+                visibility: activity.visibility,
+            },
+            notes: activity.notes,
+        })
+        .collect())
+}
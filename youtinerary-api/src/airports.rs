@@ -0,0 +1,153 @@
+use std::sync::LazyLock;
+
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::timeline::Coordinates;
+
+/// Airports bundled with the service, so that lookups work without network access. Each row
+/// holds the IATA and ICAO codes, the airport and city names, the ISO country code, the
+/// position and the IANA timezone.
+const AIRPORTS_CSV: &str = include_str!("../data/airports.csv");
+
+static AIRPORTS: LazyLock<Vec<Airport>> = LazyLock::new(|| {
+    AIRPORTS_CSV
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            parse_airport(line)
+                .unwrap_or_else(|| panic!("Invalid airport on line {} of airports.csv", index + 2))
+        })
+        .collect()
+});
+
+#[derive(Debug, Serialize)]
+pub struct Airport {
+    pub iata: &'static str,
+    pub icao: &'static str,
+    pub name: &'static str,
+    pub city: &'static str,
+    pub country: &'static str,
+    pub location: Coordinates,
+    pub timezone: Tz,
+}
+
+fn parse_airport(line: &'static str) -> Option<Airport> {
+    let mut fields = line.split(',');
+    let mut field = || fields.next().map(str::trim);
+
+    let airport = Airport {
+        iata: field()?,
+        icao: field()?,
+        name: field()?,
+        city: field()?,
+        country: field()?,
+        location: Coordinates {
+            latitude: field()?.parse().ok()?,
+            longitude: field()?.parse().ok()?,
+        },
+        timezone: field()?.parse().ok()?,
+    };
+
+    field().is_none().then_some(airport)
+}
+
+/// Finds an airport by its IATA or ICAO code, ignoring case.
+pub fn find_airport(code: &str) -> Option<&'static Airport> {
+    let code = code.trim();
+    AIRPORTS.iter().find(|airport| {
+        airport.iata.eq_ignore_ascii_case(code) || airport.icao.eq_ignore_ascii_case(code)
+    })
+}
+
+/// Airports matching `query`, best matches first: exact codes, then airports whose city or name
+/// starts with the query, then those with a word starting with it, then any other substring.
+pub fn search_airports(query: &str, limit: usize) -> Vec<&'static Airport> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let rank = |airport: &Airport| -> Option<u8> {
+        if airport.iata.eq_ignore_ascii_case(&query) || airport.icao.eq_ignore_ascii_case(&query) {
+            return Some(0);
+        }
+
+        let texts = [airport.city.to_lowercase(), airport.name.to_lowercase()];
+        if texts.iter().any(|text| text.starts_with(&query)) {
+            Some(1)
+        } else if texts.iter().any(|text| {
+            text.split(|c: char| !c.is_alphanumeric())
+                .any(|word| word.starts_with(&query))
+        }) {
+            Some(2)
+        } else if texts.iter().any(|text| text.contains(&query)) {
+            Some(3)
+        } else {
+            None
+        }
+    };
+
+    let mut matches: Vec<(u8, &'static Airport)> = AIRPORTS
+        .iter()
+        .filter_map(|airport| Some((rank(airport)?, airport)))
+        .collect();
+    matches.sort_by_key(|(rank, airport)| (*rank, airport.iata));
+
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, airport)| airport)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn every_bundled_airport_parses() {
+        let rows = AIRPORTS_CSV
+            .lines()
+            .skip(1)
+            .filter(|line| !line.trim().is_empty());
+        for (index, line) in rows.enumerate() {
+            assert!(
+                parse_airport(line).is_some(),
+                "Invalid airport on line {} of airports.csv: {}",
+                index + 2,
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn bundled_airport_codes_are_unique_and_well_formed() {
+        let mut codes = HashSet::new();
+        for airport in AIRPORTS.iter() {
+            assert_eq!(airport.iata.len(), 3, "{:?}", airport);
+            assert_eq!(airport.icao.len(), 4, "{:?}", airport);
+            assert!(
+                codes.insert(airport.iata),
+                "{} is listed twice",
+                airport.iata
+            );
+            assert!(
+                codes.insert(airport.icao),
+                "{} is listed twice",
+                airport.icao
+            );
+        }
+    }
+
+    #[test]
+    fn airports_are_found_by_either_code() {
+        assert_eq!(find_airport("ams").map(|x| x.icao), Some("EHAM"));
+        assert_eq!(find_airport(" EHAM ").map(|x| x.iata), Some("AMS"));
+        assert!(find_airport("XXX").is_none());
+    }
+}
@@ -5,10 +5,17 @@ use youtinerary_auth::AuthSettings;
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
+    // The connections are made from DATABASE_URL and REDIS_URL.
+    #[allow(dead_code)]
     pub database_url: String,
     pub auth_settings: AuthSettings,
     pub app_settings: ApplicationSettings,
+    #[allow(dead_code)]
     pub redis_url: String,
+    /// Receives forwarded confirmation emails when set, e.g. with `APP_SMTP_SETTINGS__PORT=2525`
+    /// and `APP_SMTP_SETTINGS__DOMAIN=localhost`.
+    #[serde(default)]
+    pub smtp_settings: Option<SmtpSettings>,
 }
 
 impl Settings {
@@ -46,6 +53,24 @@ impl Settings {
 pub struct ApplicationSettings {
     pub addr: [u8; 4],
     pub port: u16,
+    /// Public address of the API, e.g. `https://api.youtinerary.com`. Links in responses start
+    /// with it.
+    pub base_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    #[serde(default = "SmtpSettings::default_addr")]
+    pub addr: [u8; 4],
+    pub port: u16,
+    /// Domain of the inbox addresses, mail sent to any other domain is refused.
+    pub domain: String,
+}
+
+impl SmtpSettings {
+    fn default_addr() -> [u8; 4] {
+        [127, 0, 0, 1]
+    }
 }
 
 pub enum Environment {
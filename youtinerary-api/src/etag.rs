@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use headers::{ETag, IfMatch};
+
+/// Builds the strong entity tag for a row from its `updated_at` column.
+///
+/// Postgres stores `timestamp` with microsecond precision, so the microseconds since the epoch
+/// uniquely identify a version of the row.
+pub fn etag_from_updated_at(updated_at: NaiveDateTime) -> ETag {
+    format!("\"{:x}\"", updated_at.and_utc().timestamp_micros())
+        .parse()
+        .expect("hex digits are always a valid entity tag")
+}
+
+/// Returns true when the request may be applied to the row last written at `updated_at`.
+///
+/// A missing `If-Match` header is treated as an unconditional write.
+pub fn precondition_passes(if_match: Option<&IfMatch>, updated_at: NaiveDateTime) -> bool {
+    match if_match {
+        Some(if_match) => if_match.precondition_passes(&etag_from_updated_at(updated_at)),
+        None => true,
+    }
+}
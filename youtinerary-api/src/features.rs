@@ -1,80 +1,197 @@
+mod assign_inbox_booking;
+mod clone_itinerary;
+mod create_activity;
 mod create_flight;
 mod create_itinerary;
+mod create_itinerary_feed;
 mod create_stay;
+mod create_travel_leg;
+#[allow(dead_code)]
 mod create_user;
+mod delete_activity;
+mod delete_flight;
+mod delete_item;
+mod delete_itinerary;
+mod delete_stay;
+mod delete_travel_leg;
+mod dismiss_inbox_booking;
+mod export_itinerary;
+mod export_itinerary_calendar;
+mod export_itinerary_geojson;
+mod export_itinerary_gpx;
+mod get_activities;
+mod get_airports;
+mod get_feed_calendar;
+mod get_flights;
+mod get_inbox;
+mod get_items;
 mod get_itineraries;
 mod get_itinerary;
+mod get_itinerary_days;
+mod get_itinerary_feed;
+mod get_itinerary_issues;
+mod get_stays;
+mod get_templates;
+mod get_travel_legs;
+mod import_itinerary;
+mod import_itinerary_calendar;
+mod ingest_itinerary;
+mod instantiate_template;
+mod process_inbox;
+mod purge_itinerary;
+mod restore_itinerary;
+mod revoke_itinerary_feed;
+mod save_template;
+mod share_template;
+mod unshare_template;
+mod update_activity;
+mod update_flight;
+mod update_item;
+mod update_itinerary;
+mod update_itinerary_status;
+mod update_stay;
+mod update_travel_leg;
 
+use assign_inbox_booking::assign_inbox_booking;
+use clone_itinerary::clone_itinerary;
+use create_activity::create_activity;
 use create_flight::create_flight;
 use create_itinerary::create_itinerary;
+use create_itinerary_feed::create_itinerary_feed;
+use create_stay::create_stay;
+use create_travel_leg::create_travel_leg;
+use delete_activity::delete_activity;
+use delete_flight::delete_flight;
+use delete_item::delete_item;
+use delete_itinerary::delete_itinerary;
+use delete_stay::delete_stay;
+use delete_travel_leg::delete_travel_leg;
+use dismiss_inbox_booking::dismiss_inbox_booking;
+use export_itinerary::export_itinerary;
+use export_itinerary_calendar::export_itinerary_calendar;
+use export_itinerary_geojson::export_itinerary_geojson;
+use export_itinerary_gpx::export_itinerary_gpx;
+use get_activities::{get_activities, get_activity};
+use get_airports::{get_airport, get_airports};
+use get_feed_calendar::get_feed_calendar;
+use get_flights::{get_flight, get_flights};
+use get_inbox::get_inbox;
+use get_items::{get_item, get_items};
 use get_itineraries::get_itineraries;
 use get_itinerary::get_itinerary;
+use get_itinerary_days::get_itinerary_days;
+use get_itinerary_feed::get_itinerary_feed;
+use get_itinerary_issues::get_itinerary_issues;
+use get_stays::{get_stay, get_stays};
+use get_templates::get_templates;
+use get_travel_legs::{get_travel_leg, get_travel_legs};
+use import_itinerary::import_itinerary;
+use import_itinerary_calendar::import_itinerary_calendar;
+use ingest_itinerary::ingest_itinerary;
+use instantiate_template::instantiate_template;
+pub use process_inbox::process_inbox;
+use purge_itinerary::purge_itinerary;
+use restore_itinerary::restore_itinerary;
+use revoke_itinerary_feed::revoke_itinerary_feed;
+use save_template::save_template;
+use share_template::share_template;
+use unshare_template::unshare_template;
+use update_activity::update_activity;
+use update_flight::update_flight;
+use update_item::update_item;
+use update_itinerary::update_itinerary;
+use update_itinerary_status::update_itinerary_status;
+use update_stay::update_stay;
+use update_travel_leg::update_travel_leg;
 
-use axum::extract::{Path, State};
-
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::routing::post;
+use axum::routing::{delete, post, put};
 use axum::{routing::get, Router};
-use serde::{Deserialize, Serialize};
-use sqlx::{types::chrono::NaiveDate, PgPool};
 
 use crate::AppState;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateItineraryRequest {
-    pub name: String,
-    pub user_id: i32,
-    pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateItineraryRequest {
-    pub name: Option<String>,
-    pub start_date: Option<NaiveDate>,
-    pub end_date: Option<NaiveDate>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DeleteItineraryRequest {
-    pub id: i32,
-}
-
-#[tracing::instrument(name = "Put Itinerary", skip(db))]
-pub async fn put_itinerary(State(db): State<PgPool>, Path(id): Path<i32>) -> impl IntoResponse {
-    (StatusCode::NOT_IMPLEMENTED, "put_itinerary")
-}
-
-#[tracing::instrument(name = "Delete Itineraries", skip(db))]
-pub fn delete_itinerary(State(db): State<PgPool>, Path(id): Path<i32>) -> impl IntoResponse {
-    (StatusCode::NOT_IMPLEMENTED, "delete_itinerary")
-}
-
-#[tracing::instrument(name = "Get Itinerary Stays", skip(db))]
-
-pub async fn get_itinerary_stays(
-    State(db): State<PgPool>,
-    Path(id): Path<i32>,
-) -> impl IntoResponse {
-    (StatusCode::NOT_IMPLEMENTED, "get_itinerary_stays")
-}
-
-#[tracing::instrument(name = "Post Itinerary Stay", skip(db))]
-pub async fn post_itinerary_stay(
-    State(db): State<PgPool>,
-    Path(id): Path<i32>,
-) -> impl IntoResponse {
-    (StatusCode::NOT_IMPLEMENTED, "get_itinerary_stays")
-}
-
 pub fn itineraries_router() -> Router<AppState> {
     Router::new()
         .route("/itineraries", get(get_itineraries).post(create_itinerary))
-        .route("/itineraries/:id", get(get_itinerary).put(put_itinerary))
-        .route("/itineraries/:id/flights", post(create_flight))
         .route(
-            "/itineraries/:id/stays",
-            get(get_itinerary_stays).post(post_itinerary_stay),
+            "/itineraries/:id",
+            get(get_itinerary)
+                .put(update_itinerary)
+                .delete(delete_itinerary),
+        )
+        .route("/itineraries/import", post(import_itinerary))
+        .route("/itineraries/:id/clone", post(clone_itinerary))
+        .route("/itineraries/:id/export", get(export_itinerary))
+        .route(
+            "/itineraries/:id/map.geojson",
+            get(export_itinerary_geojson),
+        )
+        .route("/itineraries/:id/map.gpx", get(export_itinerary_gpx))
+        .route(
+            "/itineraries/:id/calendar.ics",
+            get(export_itinerary_calendar).post(import_itinerary_calendar),
+        )
+        .route(
+            "/itineraries/:id/feed",
+            get(get_itinerary_feed)
+                .post(create_itinerary_feed)
+                .delete(revoke_itinerary_feed),
+        )
+        .route("/itineraries/:id/ingest", post(ingest_itinerary))
+        .route("/itineraries/:id/days", get(get_itinerary_days))
+        .route("/itineraries/:id/issues", get(get_itinerary_issues))
+        .route("/itineraries/:id/template", post(save_template))
+        .route("/itineraries/:id/status", put(update_itinerary_status))
+        .route("/itineraries/:id/restore", post(restore_itinerary))
+        .route("/itineraries/:id/purge", delete(purge_itinerary))
+        .route("/itineraries/:id/items", get(get_items))
+        .route(
+            "/itineraries/:id/items/:item_id",
+            get(get_item).patch(update_item).delete(delete_item),
+        )
+        .route(
+            "/itineraries/:id/flights",
+            get(get_flights).post(create_flight),
+        )
+        .route(
+            "/itineraries/:id/flights/:flight_id",
+            get(get_flight).patch(update_flight).delete(delete_flight),
+        )
+        .route("/itineraries/:id/stays", get(get_stays).post(create_stay))
+        .route(
+            "/itineraries/:id/stays/:stay_id",
+            get(get_stay).patch(update_stay).delete(delete_stay),
+        )
+        .route(
+            "/itineraries/:id/activities",
+            get(get_activities).post(create_activity),
+        )
+        .route(
+            "/itineraries/:id/activities/:activity_id",
+            get(get_activity)
+                .patch(update_activity)
+                .delete(delete_activity),
+        )
+        .route(
+            "/itineraries/:id/travel_legs",
+            get(get_travel_legs).post(create_travel_leg),
+        )
+        .route(
+            "/itineraries/:id/travel_legs/:leg_id",
+            get(get_travel_leg)
+                .patch(update_travel_leg)
+                .delete(delete_travel_leg),
+        )
+        .route("/airports", get(get_airports))
+        .route("/airports/:code", get(get_airport))
+        .route("/templates", get(get_templates))
+        .route("/inbox", get(get_inbox))
+        .route("/inbox/:booking_id", delete(dismiss_inbox_booking))
+        .route("/inbox/:booking_id/assign", post(assign_inbox_booking))
+        .route("/feeds/:token/calendar.ics", get(get_feed_calendar))
+        .route("/templates/:id/itineraries", post(instantiate_template))
+        .route("/templates/:id/shares", post(share_template))
+        .route(
+            "/templates/:id/shares/:user_id",
+            delete(unshare_template),
         )
 }
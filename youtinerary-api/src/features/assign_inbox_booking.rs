@@ -0,0 +1,148 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use sqlx::types::Json as JsonColumn;
+use sqlx::PgPool;
+
+use super::ingest_itinerary::{InsertProposalRepository, ProposedItem};
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Moves a booking from the user's inbox into an itinerary they own or edit. The booking is
+/// created as it was read, or as `item` when the parser got something wrong.
+#[tracing::instrument(name = "Assign Inbox Booking", skip(db))]
+pub async fn assign_inbox_booking(
+    State(db): State<PgPool>,
+    user: User,
+    Path(booking_id): Path<i32>,
+    Json(assign): Json<AssignInboxBookingRequest>,
+) -> Result<impl IntoResponse, AssignInboxBookingError> {
+    let location = db
+        .assign_inbox_booking(user.id, booking_id, assign.itinerary_id, assign.item)
+        .await?;
+    let issues = db.get_issues(assign.itinerary_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WriteResponse {
+            location: Some(location),
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignInboxBookingRequest {
+    itinerary_id: i32,
+    /// Replaces the booking as it was read, as `{ "type": …, "item": … }` like the inbox lists it.
+    item: Option<ProposedItem>,
+}
+
+#[derive(Debug)]
+pub enum AssignInboxBookingError {
+    NotFound,
+    Access(AccessError),
+    Invalid(String),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for AssignInboxBookingError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for AssignInboxBookingError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for AssignInboxBookingError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for AssignInboxBookingError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::Access(error) => error.into_response(),
+            Self::Invalid(reason) => (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait AssignInboxBookingRepository {
+    /// Creates the booking in the itinerary and takes it out of the inbox, returning where it went.
+    async fn assign_inbox_booking(
+        &self,
+        user_id: i32,
+        booking_id: i32,
+        itinerary_id: i32,
+        item: Option<ProposedItem>,
+    ) -> Result<String, AssignInboxBookingError>;
+}
+
+impl AssignInboxBookingRepository for PgPool {
+    async fn assign_inbox_booking(
+        &self,
+        user_id: i32,
+        booking_id: i32,
+        itinerary_id: i32,
+        item: Option<ProposedItem>,
+    ) -> Result<String, AssignInboxBookingError> {
+        let mut transaction = self.begin().await?;
+
+        // Locked so the same booking can't be assigned twice at once.
+        let booking = sqlx::query!(
+            r#"
+                select b.proposal as "proposal: JsonColumn<ProposedItem>"
+                from inbox_bookings b
+                    join inbox_messages m on m.id = b.message_id
+                where b.id = $1
+                    and m.user_id = $2
+                for update of b
+            "#,
+            booking_id,
+            user_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AssignInboxBookingError::NotFound)?;
+
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        let insert = item
+            .unwrap_or(booking.proposal.0)
+            .to_insert(user_id, itinerary_id)
+            .map_err(AssignInboxBookingError::Invalid)?;
+        let location = transaction.insert_proposal(itinerary_id, insert).await?;
+
+        sqlx::query!(
+            r#"
+                delete from inbox_bookings
+                where id = $1
+            "#,
+            booking_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(location)
+    }
+}
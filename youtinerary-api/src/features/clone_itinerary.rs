@@ -0,0 +1,540 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::access::{find_role, ItineraryRole};
+use crate::{ItemVisibility, User};
+
+#[tracing::instrument(name = "Clone Itinerary", skip(db))]
+pub async fn clone_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Json(clone_itinerary): Json<CloneItineraryRequest>,
+) -> Result<impl IntoResponse, CloneItineraryError> {
+    let source = db
+        .get_clone_source(user.id, itinerary_id)
+        .await?
+        .ok_or(CloneItineraryError::ItineraryNotFound)?;
+
+    let offset_days = match (clone_itinerary.offset_days, clone_itinerary.start_date) {
+        (Some(_), Some(_)) => return Err(CloneItineraryError::ConflictingShift),
+        (Some(offset_days), None) => offset_days,
+        (None, Some(start_date)) => {
+            let source_start_date = source
+                .start_date
+                .ok_or(CloneItineraryError::SourceHasNoStartDate)?;
+            (start_date - source_start_date).num_days() as i32
+        }
+        (None, None) => 0,
+    };
+
+    let mut transaction = db.begin().await?;
+    let cloned_id = transaction
+        .clone_itinerary(CopyItinerary {
+            source_itinerary_id: itinerary_id,
+            user_id: user.id,
+            name: clone_itinerary.name.unwrap_or(source.name),
+            offset_days,
+            is_template: false,
+            template_start_date: None,
+            copy_editor_items: source.role.can_see(ItemVisibility::Editors),
+        })
+        .await?;
+    transaction.commit().await?;
+
+    Ok((StatusCode::CREATED, format!("/itineraries/{}", cloned_id)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloneItineraryRequest {
+    name: Option<String>,
+    /// Number of days every date and time is moved by, may be negative.
+    offset_days: Option<i32>,
+    /// Start date of the copy, the shift is computed from the source's start date.
+    start_date: Option<NaiveDate>,
+}
+
+#[derive(Debug)]
+pub enum CloneItineraryError {
+    ItineraryNotFound,
+    ConflictingShift,
+    SourceHasNoStartDate,
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for CloneItineraryError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for CloneItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::ConflictingShift => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Pass either offset_days or start_date, not both",
+            )
+                .into_response(),
+            Self::SourceHasNoStartDate => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The itinerary has no start date to shift from, pass offset_days instead",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+pub struct CloneSource {
+    pub name: String,
+    pub start_date: Option<NaiveDate>,
+    pub role: ItineraryRole,
+}
+
+pub struct CopyItinerary {
+    pub source_itinerary_id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub offset_days: i32,
+    pub is_template: bool,
+    /// Date an instantiated template starts on. Items with recorded template times are placed by
+    /// their day offsets and local times from this date instead of being shifted.
+    pub template_start_date: Option<NaiveDate>,
+    /// Whether items only editors see are copied too. Viewers get the items they can see.
+    pub copy_editor_items: bool,
+}
+
+pub trait CloneSourceRepository {
+    /// Name and effective start date of an itinerary the user owns or that is shared with them,
+    /// and their role on it.
+    async fn get_clone_source(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<CloneSource>>;
+}
+
+impl CloneSourceRepository for PgPool {
+    async fn get_clone_source(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<CloneSource>> {
+        let mut connection = self.acquire().await?;
+        let Some(role) = find_role(&mut connection, user_id, itinerary_id).await? else {
+            return Ok(None);
+        };
+
+        let source = sqlx::query!(
+            r#"
+                select
+                    i.name,
+                    d.start_date
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                where i.itinerary_id = $1
+                    and not i.is_template
+            "#,
+            itinerary_id
+        )
+        .fetch_optional(&mut *connection)
+        .await?;
+
+        Ok(source.map(|x| CloneSource {
+            name: x.name,
+            start_date: x.start_date,
+            role,
+        }))
+    }
+}
+
+pub trait CloneItineraryRepository {
+    /// Deep copies an itinerary and everything in it into a new draft owned by `user_id`, or into a
+    /// template when `is_template` is set.
+    ///
+    /// Dates move by `offset_days`, and flight times move in their local timezone so the wall
+    /// clock time printed on the ticket stays the same across daylight saving changes. With a
+    /// `template_start_date`, the template times of an item take precedence and are resolved in
+    /// the item's timezone on the day it lands on.
+    async fn clone_itinerary(&mut self, copy: CopyItinerary) -> Result<i32>;
+}
+
+impl CloneItineraryRepository for Transaction<'_, Postgres> {
+    async fn clone_itinerary(&mut self, copy: CopyItinerary) -> Result<i32> {
+        let cloned = sqlx::query!(
+            r#"
+                insert into itineraries (user_id, name, is_template)
+                values ($1, $2, $3)
+                returning itinerary_id
+            "#,
+            copy.user_id,
+            copy.name,
+            copy.is_template
+        )
+        .fetch_one(&mut **self)
+        .await?;
+        let cloned_id = cloned.itinerary_id;
+
+        sqlx::query!(
+            r#"
+                insert into itinerary_start_date (itinerary_id, start_date)
+                select $2, start_date + $3::integer
+                from itinerary_start_date
+                where itinerary_id = $1
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                insert into itinerary_end_date (itinerary_id, end_date)
+                select $2, end_date + $3::integer
+                from itinerary_end_date
+                where itinerary_id = $1
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days
+        )
+        .execute(&mut **self)
+        .await?;
+
+        // Every kind is copied with its item, which carries its own position and visibility into
+        // the new itinerary. New item ids are drawn up front so each booking can point at its
+        // copied item within the same statement.
+        sqlx::query!(
+            r#"
+                with source as (
+                    select
+                        f.id as flight_id,
+                        f.itinerary_item_id as item_id,
+                        nextval(pg_get_serial_sequence('itinerary_items', 'id'))::integer
+                            as copied_item_id,
+                        nextval(pg_get_serial_sequence('flights', 'id'))::integer
+                            as copied_flight_id
+                    from flights f
+                        join itinerary_items i on i.id = f.itinerary_item_id
+                    where i.itinerary_id = $1
+                        and ($5 or i.visibility = 'everyone')
+                ),
+                items as (
+                    insert into itinerary_items (id, itinerary_id, item_type, position, visibility)
+                    select source.copied_item_id, $2, i.item_type, i.position, i.visibility
+                    from source
+                        join itinerary_items i on i.id = source.item_id
+                ),
+                copied as (
+                    insert into flights (
+                        id,
+                        itinerary_item_id,
+                        airline,
+                        confirmation_code,
+                        departure_time,
+                        arrival_time,
+                        departure_timezone,
+                        arrival_timezone,
+                        departure_airport,
+                        arrival_airport,
+                        departure_location,
+                        arrival_location,
+                        flight_number,
+                        operating_carrier,
+                        departure_terminal,
+                        departure_gate,
+                        arrival_terminal,
+                        arrival_gate,
+                        cabin_class,
+                        aircraft_type,
+                        notes
+                    )
+                    select
+                        source.copied_flight_id,
+                        source.copied_item_id,
+                        f.airline,
+                        f.confirmation_code,
+                        coalesce(
+                            ($4::date + t.start_day + t.start_time)
+                                at time zone coalesce(f.departure_timezone, 'UTC'),
+                            ((f.departure_time
+                                at time zone coalesce(f.departure_timezone, 'UTC'))
+                                + make_interval(days => $3))
+                                at time zone coalesce(f.departure_timezone, 'UTC')
+                        ),
+                        coalesce(
+                            ($4::date + t.end_day + t.end_time)
+                                at time zone coalesce(f.arrival_timezone, 'UTC'),
+                            ((f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))
+                                + make_interval(days => $3))
+                                at time zone coalesce(f.arrival_timezone, 'UTC')
+                        ),
+                        f.departure_timezone,
+                        f.arrival_timezone,
+                        f.departure_airport,
+                        f.arrival_airport,
+                        f.departure_location,
+                        f.arrival_location,
+                        f.flight_number,
+                        f.operating_carrier,
+                        f.departure_terminal,
+                        f.departure_gate,
+                        f.arrival_terminal,
+                        f.arrival_gate,
+                        f.cabin_class,
+                        f.aircraft_type,
+                        f.notes
+                    from source
+                        join flights f on f.id = source.flight_id
+                        left join template_item_times t
+                            on t.itinerary_item_id = f.itinerary_item_id
+                )
+                insert into flight_passengers (
+                    flight_id,
+                    position,
+                    name,
+                    booking_reference,
+                    seat
+                )
+                select source.copied_flight_id, p.position, p.name, p.booking_reference, p.seat
+                from source
+                    join flight_passengers p on p.flight_id = source.flight_id
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days,
+            copy.template_start_date,
+            copy.copy_editor_items
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                with source as (
+                    select
+                        i.id as item_id,
+                        nextval(pg_get_serial_sequence('itinerary_items', 'id'))::integer
+                            as copied_item_id
+                    from itinerary_items i
+                    where i.itinerary_id = $1
+                        and i.item_type = 'stay'
+                        and ($5 or i.visibility = 'everyone')
+                ),
+                items as (
+                    insert into itinerary_items (id, itinerary_id, item_type, position, visibility)
+                    select source.copied_item_id, $2, i.item_type, i.position, i.visibility
+                    from source
+                        join itinerary_items i on i.id = source.item_id
+                )
+                insert into stays (
+                    itinerary_item_id,
+                    summary,
+                    start_date,
+                    end_date,
+                    check_in_time,
+                    check_out_time,
+                    address,
+                    location,
+                    confirmation_number,
+                    notes
+                )
+                select
+                    source.copied_item_id,
+                    s.summary,
+                    coalesce($4::date + t.start_day, s.start_date + $3::integer),
+                    coalesce($4::date + t.end_day, s.end_date + $3::integer),
+                    s.check_in_time,
+                    s.check_out_time,
+                    s.address,
+                    s.location,
+                    s.confirmation_number,
+                    s.notes
+                from source
+                    join stays s on s.itinerary_item_id = source.item_id
+                    left join template_item_times t
+                        on t.itinerary_item_id = s.itinerary_item_id
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days,
+            copy.template_start_date,
+            copy.copy_editor_items
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                with source as (
+                    select
+                        i.id as item_id,
+                        nextval(pg_get_serial_sequence('itinerary_items', 'id'))::integer
+                            as copied_item_id
+                    from itinerary_items i
+                    where i.itinerary_id = $1
+                        and i.item_type = 'activity'
+                        and ($5 or i.visibility = 'everyone')
+                ),
+                items as (
+                    insert into itinerary_items (id, itinerary_id, item_type, position, visibility)
+                    select source.copied_item_id, $2, i.item_type, i.position, i.visibility
+                    from source
+                        join itinerary_items i on i.id = source.item_id
+                )
+                insert into activities (
+                    itinerary_item_id,
+                    summary,
+                    category,
+                    start_date,
+                    end_date,
+                    start_time,
+                    end_time,
+                    address,
+                    location,
+                    booking_reference,
+                    cost,
+                    cost_currency,
+                    notes
+                )
+                select
+                    source.copied_item_id,
+                    a.summary,
+                    a.category,
+                    coalesce($4::date + t.start_day, a.start_date + $3::integer),
+                    coalesce($4::date + t.end_day, a.end_date + $3::integer),
+                    a.start_time,
+                    a.end_time,
+                    a.address,
+                    a.location,
+                    a.booking_reference,
+                    a.cost,
+                    a.cost_currency,
+                    a.notes
+                from source
+                    join activities a on a.itinerary_item_id = source.item_id
+                    left join template_item_times t
+                        on t.itinerary_item_id = a.itinerary_item_id
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days,
+            copy.template_start_date,
+            copy.copy_editor_items
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                with source as (
+                    select
+                        i.id as item_id,
+                        nextval(pg_get_serial_sequence('itinerary_items', 'id'))::integer
+                            as copied_item_id
+                    from itinerary_items i
+                    where i.itinerary_id = $1
+                        and i.item_type = 'travel_leg'
+                        and ($5 or i.visibility = 'everyone')
+                ),
+                items as (
+                    insert into itinerary_items (id, itinerary_id, item_type, position, visibility)
+                    select source.copied_item_id, $2, i.item_type, i.position, i.visibility
+                    from source
+                        join itinerary_items i on i.id = source.item_id
+                )
+                insert into travel_legs (
+                    itinerary_item_id,
+                    name,
+                    travel_leg_type,
+                    start_date,
+                    end_date,
+                    start_time,
+                    end_time,
+                    start_place,
+                    end_place,
+                    start_location,
+                    end_location,
+                    start_timezone,
+                    end_timezone,
+                    operator,
+                    booking_reference,
+                    service_number,
+                    coach,
+                    seat,
+                    platform,
+                    vehicle,
+                    cabin,
+                    notes
+                )
+                select
+                    source.copied_item_id,
+                    t.name,
+                    t.travel_leg_type,
+                    coalesce($4::date + tt.start_day, t.start_date + $3::integer),
+                    coalesce($4::date + tt.end_day, t.end_date + $3::integer),
+                    coalesce(
+                        ($4::date + tt.start_day + tt.start_time)
+                            at time zone coalesce(t.start_timezone, 'UTC'),
+                        ((t.start_time at time zone coalesce(t.start_timezone, 'UTC'))
+                            + make_interval(days => $3))
+                            at time zone coalesce(t.start_timezone, 'UTC')
+                    ),
+                    coalesce(
+                        ($4::date + tt.end_day + tt.end_time)
+                            at time zone coalesce(t.end_timezone, 'UTC'),
+                        ((t.end_time at time zone coalesce(t.end_timezone, 'UTC'))
+                            + make_interval(days => $3))
+                            at time zone coalesce(t.end_timezone, 'UTC')
+                    ),
+                    t.start_place,
+                    t.end_place,
+                    t.start_location,
+                    t.end_location,
+                    t.start_timezone,
+                    t.end_timezone,
+                    t.operator,
+                    t.booking_reference,
+                    t.service_number,
+                    t.coach,
+                    t.seat,
+                    t.platform,
+                    t.vehicle,
+                    t.cabin,
+                    t.notes
+                from source
+                    join travel_legs t on t.itinerary_item_id = source.item_id
+                    left join template_item_times tt
+                        on tt.itinerary_item_id = t.itinerary_item_id
+            "#,
+            copy.source_itinerary_id,
+            cloned_id,
+            copy.offset_days,
+            copy.template_start_date,
+            copy.copy_editor_items
+        )
+        .execute(&mut **self)
+        .await?;
+
+        Ok(cloned_id)
+    }
+}
@@ -0,0 +1,191 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::activities::ActivityFields;
+use crate::fields::InvalidField;
+use crate::items::insert_item;
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{ActivityCategory, ItineraryItemType, User};
+
+/// Adds an activity to an itinerary the user owns or edits.
+#[tracing::instrument(name = "Create Activity", skip(db))]
+pub async fn create_activity(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Json(create_activity): Json<CreateActivityRequest>,
+) -> Result<impl IntoResponse, CreateActivityError> {
+    let activity_id = db
+        .create_activity((user.id, itinerary_id, create_activity).try_into()?)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/activities/{}",
+                itinerary_id, activity_id
+            )),
+            issues,
+        }),
+    ))
+}
+
+/// `location` is `{ "latitude": …, "longitude": … }` and `cost` is `{ "amount": "12.50",
+/// "currency": "EUR" }`. The category defaults to `other`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateActivityRequest {
+    #[serde(flatten)]
+    pub(crate) activity: ActivityFields,
+}
+
+impl TryFrom<(i32, i32, CreateActivityRequest)> for InsertActivity {
+    type Error = InvalidField;
+
+    fn try_from(
+        (user_id, itinerary_id, val): (i32, i32, CreateActivityRequest),
+    ) -> Result<Self, Self::Error> {
+        Ok(InsertActivity {
+            user_id,
+            itinerary_id,
+            activity: val.activity.validated()?,
+        })
+    }
+}
+
+struct InsertActivity {
+    user_id: i32,
+    itinerary_id: i32,
+    activity: ActivityFields,
+}
+
+#[derive(Debug)]
+pub enum CreateActivityError {
+    Access(AccessError),
+    InvalidField(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for CreateActivityError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<InvalidField> for CreateActivityError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl From<sqlx::Error> for CreateActivityError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for CreateActivityError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for CreateActivityError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::InvalidField(error) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()).into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait CreateActivityRespository {
+    /// Registers the activity's item and inserts the activity in one transaction.
+    async fn create_activity(
+        &self,
+        create_activity: InsertActivity,
+    ) -> Result<i32, CreateActivityError>;
+}
+
+impl CreateActivityRespository for PgPool {
+    async fn create_activity(
+        &self,
+        create_activity: InsertActivity,
+    ) -> Result<i32, CreateActivityError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(
+            &mut transaction,
+            create_activity.user_id,
+            create_activity.itinerary_id,
+        )
+        .await?;
+        let activity = create_activity.activity;
+        let item_id = insert_item(
+            &mut transaction,
+            create_activity.itinerary_id,
+            ItineraryItemType::Activity,
+        )
+        .await?;
+
+        let created = sqlx::query!(
+            r#"
+                insert into activities (
+                    itinerary_item_id,
+                    summary,
+                    category,
+                    start_date,
+                    end_date,
+                    start_time,
+                    end_time,
+                    address,
+                    location,
+                    booking_reference,
+                    cost,
+                    cost_currency,
+                    notes
+                )
+                values (
+                    $1, $2, $3, $4, $5, $6, $7, $8, point($9, $10), $11, $12::text::numeric, $13,
+                    $14
+                )
+                returning id
+            "#,
+            item_id,
+            activity.summary,
+            activity.category as ActivityCategory,
+            activity.start_date,
+            activity.end_date,
+            activity.start_time,
+            activity.end_time,
+            activity.address,
+            activity.location.map(|x| x.longitude),
+            activity.location.map(|x| x.latitude),
+            activity.booking_reference,
+            activity.cost.as_ref().map(|x| &x.amount),
+            activity.cost.as_ref().map(|x| &x.currency),
+            activity.notes
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(created.id)
+    }
+}
@@ -3,78 +3,273 @@ use axum::extract::{Path, State};
 use anyhow::Result;
 
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 
-use crate::error_handling::AppError;
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::{optional_text, required_text, InvalidField};
+use crate::flights::{
+    insert_passengers, resolve_airport, FlightAirport, FlightDetails, FlightInputError,
+};
+use crate::items::insert_item;
+use crate::local_time::resolve_instant;
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{CabinClass, ItineraryItemType, User};
 
+/// Adds a flight to an itinerary the user owns or edits.
 #[tracing::instrument(name = "Create Flight", skip(db))]
 pub async fn create_flight(
     State(db): State<PgPool>,
+    user: User,
     Path(itinerary_id): Path<i32>,
     Json(create_flight): Json<CreateFlightRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    let created_id = db
-        .create_flight((itinerary_id, create_flight).into())
+) -> Result<impl IntoResponse, CreateFlightError> {
+    let flight_id = db
+        .create_flight((user.id, itinerary_id, create_flight).try_into()?)
         .await?;
-    Ok((StatusCode::CREATED, format!("/itineraries/{}/flights/{}", itinerary_id, created_id)))
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/flights/{}",
+                itinerary_id, flight_id
+            )),
+            issues,
+        }),
+    ))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Times are given either as instants with an offset in `departure_time`/`arrival_time`, or as
+/// the wall clock times printed on the ticket in `local_departure_time`/`local_arrival_time`
+/// together with the airport's timezone. Codes of bundled airports fill in the timezones and
+/// positions, other codes are stored as given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateFlightRequest {
     pub(crate) airline: String,
     pub(crate) confirmation_code: String,
-    pub(crate) departure_time: DateTime<Utc>,
-    pub(crate) arrival_time: DateTime<Utc>,
+    pub(crate) departure_time: Option<DateTime<Utc>>,
+    pub(crate) local_departure_time: Option<NaiveDateTime>,
+    pub(crate) arrival_time: Option<DateTime<Utc>>,
+    pub(crate) local_arrival_time: Option<NaiveDateTime>,
+    /// IANA zone of the departure airport, unknown zones are rejected while deserializing.
+    pub(crate) departure_timezone: Option<Tz>,
+    pub(crate) arrival_timezone: Option<Tz>,
+    /// IATA or ICAO code of the departure airport.
+    pub(crate) departure_airport: Option<String>,
+    pub(crate) arrival_airport: Option<String>,
+    #[serde(flatten)]
+    pub(crate) details: FlightDetails,
+    #[serde(default)]
     pub(crate) notes: String,
 }
 
-impl Into<InsertFlight> for (i32, CreateFlightRequest) {
-    fn into(self) -> InsertFlight {
-        InsertFlight {
-            itinerary_id: self.0,
-            airline: self.1.airline,
-            confirmation_code: self.1.confirmation_code,
-            departure_time: self.1.departure_time,
-            arrival_time: self.1.arrival_time,
-            notes: self.1.notes,
-        }
+impl TryFrom<(i32, i32, CreateFlightRequest)> for InsertFlight {
+    type Error = FlightInputError;
+
+    fn try_from(
+        (user_id, itinerary_id, val): (i32, i32, CreateFlightRequest),
+    ) -> Result<Self, Self::Error> {
+        let (departure_airport, departure_timezone) =
+            resolve_airport(val.departure_airport.as_deref(), val.departure_timezone)?;
+        let (arrival_airport, arrival_timezone) =
+            resolve_airport(val.arrival_airport.as_deref(), val.arrival_timezone)?;
+
+        let departure_time = resolve_instant(
+            val.departure_time,
+            val.local_departure_time,
+            departure_timezone,
+        )
+        .map_err(|error| FlightInputError::InvalidTime("departure", error))?;
+        let arrival_time =
+            resolve_instant(val.arrival_time, val.local_arrival_time, arrival_timezone)
+                .map_err(|error| FlightInputError::InvalidTime("arrival", error))?;
+
+        Ok(InsertFlight {
+            user_id,
+            itinerary_id,
+            airline: required_text("airline", val.airline, 255)?,
+            confirmation_code: required_text("confirmation_code", val.confirmation_code, 50)?,
+            departure_time,
+            arrival_time,
+            departure_timezone,
+            arrival_timezone,
+            departure_airport,
+            arrival_airport,
+            details: val.details.validated()?,
+            notes: optional_text("notes", Some(val.notes), 255)?.unwrap_or_default(),
+        })
     }
 }
 
-struct InsertFlight {
+pub struct InsertFlight {
+    user_id: i32,
     itinerary_id: i32,
     airline: String,
     confirmation_code: String,
     departure_time: DateTime<Utc>,
     arrival_time: DateTime<Utc>,
+    departure_timezone: Option<Tz>,
+    arrival_timezone: Option<Tz>,
+    departure_airport: Option<FlightAirport>,
+    arrival_airport: Option<FlightAirport>,
+    details: FlightDetails,
     notes: String,
 }
 
+#[derive(Debug)]
+pub enum CreateFlightError {
+    Access(AccessError),
+    Invalid(FlightInputError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for CreateFlightError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for CreateFlightError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<FlightInputError> for CreateFlightError {
+    fn from(value: FlightInputError) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<InvalidField> for CreateFlightError {
+    fn from(value: InvalidField) -> Self {
+        Self::Invalid(value.into())
+    }
+}
+
+impl From<anyhow::Error> for CreateFlightError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for CreateFlightError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Invalid(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
 trait CreateFlightRespository {
-    async fn create_flight(&self, create_flight: InsertFlight) -> Result<i32>;
+    /// Checks the user can edit the itinerary and inserts the flight in one transaction.
+    async fn create_flight(&self, create_flight: InsertFlight) -> Result<i32, CreateFlightError>;
 }
 
 impl CreateFlightRespository for PgPool {
-    async fn create_flight(&self, create_flight: InsertFlight) -> Result<i32> {
+    async fn create_flight(&self, create_flight: InsertFlight) -> Result<i32, CreateFlightError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(
+            &mut transaction,
+            create_flight.user_id,
+            create_flight.itinerary_id,
+        )
+        .await?;
+        let flight_id = transaction.insert_flight(create_flight).await?;
+
+        transaction.commit().await?;
+
+        Ok(flight_id)
+    }
+}
+
+pub trait InsertFlightRepository {
+    /// Registers the flight's item and inserts the flight. Checking the user can edit the
+    /// itinerary is left to the caller.
+    async fn insert_flight(&mut self, create_flight: InsertFlight) -> Result<i32>;
+}
+
+impl InsertFlightRepository for Transaction<'_, Postgres> {
+    async fn insert_flight(&mut self, create_flight: InsertFlight) -> Result<i32> {
+        let details = create_flight.details;
+        let (departure_airport, arrival_airport) = (
+            create_flight.departure_airport,
+            create_flight.arrival_airport,
+        );
+        let departure_location = departure_airport.as_ref().and_then(|x| x.location);
+        let arrival_location = arrival_airport.as_ref().and_then(|x| x.location);
+        let item_id =
+            insert_item(self, create_flight.itinerary_id, ItineraryItemType::Flight).await?;
+
         let created_id = sqlx::query!(
             r#"
-            insert into flights (airline, confirmation_code, departure_time, arrival_time, notes)
-            values ($1, $2, $3, $4, $5)
+            insert into flights (
+                itinerary_item_id,
+                airline,
+                confirmation_code,
+                departure_time,
+                arrival_time,
+                departure_timezone,
+                arrival_timezone,
+                departure_airport,
+                arrival_airport,
+                departure_location,
+                arrival_location,
+                flight_number,
+                operating_carrier,
+                departure_terminal,
+                departure_gate,
+                arrival_terminal,
+                arrival_gate,
+                cabin_class,
+                aircraft_type,
+                notes
+            )
+            values (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, point($10, $11), point($12, $13),
+                $14, $15, $16, $17, $18, $19, $20, $21, $22
+            )
             returning id
             "#,
+            item_id,
             create_flight.airline,
             create_flight.confirmation_code,
             create_flight.departure_time,
             create_flight.arrival_time,
+            create_flight.departure_timezone.map(|tz| tz.name()),
+            create_flight.arrival_timezone.map(|tz| tz.name()),
+            departure_airport.as_ref().map(|x| x.code.as_str()),
+            arrival_airport.as_ref().map(|x| x.code.as_str()),
+            departure_location.map(|x| x.longitude),
+            departure_location.map(|x| x.latitude),
+            arrival_location.map(|x| x.longitude),
+            arrival_location.map(|x| x.latitude),
+            details.flight_number,
+            details.operating_carrier,
+            details.departure_terminal,
+            details.departure_gate,
+            details.arrival_terminal,
+            details.arrival_gate,
+            details.cabin_class as Option<CabinClass>,
+            details.aircraft_type,
             create_flight.notes,
         )
-        .fetch_one(self)
+        .fetch_one(&mut **self)
         .await?;
+        insert_passengers(self, created_id.id, &details.passengers).await?;
 
-        Ok(created_id.id as i32)
+        Ok(created_id.id)
     }
 }
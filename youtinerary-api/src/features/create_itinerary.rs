@@ -3,20 +3,28 @@ use axum::extract::State;
 use anyhow::Result;
 
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
 use crate::User;
-use crate::error_handling::AppError;
 
 #[tracing::instrument(name = "Create Itinerary", skip(db))]
 pub async fn create_itinerary(
     State(db): State<PgPool>,
     user: User,
     Json(create_itinerary): Json<CreateItineraryRequest>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<impl IntoResponse, CreateItineraryError> {
+    if let (Some(start_date), Some(end_date)) =
+        (create_itinerary.start_date, create_itinerary.end_date)
+    {
+        if end_date < start_date {
+            return Err(CreateItineraryError::EndBeforeStart);
+        }
+    }
+
     let itinerary_id = db
         .create_itinerary((user.id, create_itinerary).into())
         .await?;
@@ -29,6 +37,8 @@ pub async fn create_itinerary(
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateItineraryRequest {
     name: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
 }
 
 impl From<(i32, CreateItineraryRequest)> for InsertItinerary {
@@ -36,6 +46,40 @@ impl From<(i32, CreateItineraryRequest)> for InsertItinerary {
         InsertItinerary {
             user_id: val.0,
             name: val.1.name,
+            start_date: val.1.start_date,
+            end_date: val.1.end_date,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateItineraryError {
+    EndBeforeStart,
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for CreateItineraryError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for CreateItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::EndBeforeStart => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "end_date must not be before start_date",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
         }
     }
 }
@@ -43,6 +87,8 @@ impl From<(i32, CreateItineraryRequest)> for InsertItinerary {
 struct InsertItinerary {
     user_id: i32,
     name: String,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
 }
 
 trait CreateItineraryRespository {
@@ -51,6 +97,8 @@ trait CreateItineraryRespository {
 
 impl CreateItineraryRespository for PgPool {
     async fn create_itinerary(&self, create_itinerary: InsertItinerary) -> Result<i32> {
+        let mut transaction = self.begin().await?;
+
         let inserted = sqlx::query!(
             r#"
             INSERT INTO itineraries (user_id, name)
@@ -60,9 +108,37 @@ impl CreateItineraryRespository for PgPool {
             create_itinerary.user_id as i32,
             create_itinerary.name,
         )
-        .fetch_one(self)
+        .fetch_one(&mut *transaction)
         .await?;
 
+        if let Some(start_date) = create_itinerary.start_date {
+            sqlx::query!(
+                r#"
+                INSERT INTO itinerary_start_date (itinerary_id, start_date)
+                VALUES ($1, $2)
+                "#,
+                inserted.itinerary_id,
+                start_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if let Some(end_date) = create_itinerary.end_date {
+            sqlx::query!(
+                r#"
+                INSERT INTO itinerary_end_date (itinerary_id, end_date)
+                VALUES ($1, $2)
+                "#,
+                inserted.itinerary_id,
+                end_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
         Ok(inserted.itinerary_id as i32)
     }
 }
@@ -0,0 +1,54 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::links::PublicUrl;
+use crate::User;
+
+/// Issues a new secret calendar feed URL for the itinerary. Any earlier URL stops working.
+#[tracing::instrument(name = "Create Itinerary Feed", skip(db, public_url))]
+pub async fn create_itinerary_feed(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+    State(public_url): State<PublicUrl>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(token) = db.create_itinerary_feed(user.id, itinerary_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    Ok((StatusCode::CREATED, public_url.feed_calendar(token)).into_response())
+}
+
+trait CreateItineraryFeedRepository {
+    async fn create_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<Option<Uuid>>;
+}
+
+impl CreateItineraryFeedRepository for PgPool {
+    async fn create_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<Option<Uuid>> {
+        let feed = sqlx::query!(
+            r#"
+                insert into itinerary_feeds (itinerary_id)
+                select itinerary_id
+                from itineraries
+                where user_id = $1
+                    and itinerary_id = $2
+                on conflict (itinerary_id)
+                do update set token = gen_random_uuid(),
+                    created_at = now()
+                returning token
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(feed.map(|x| x.token))
+    }
+}
@@ -0,0 +1,181 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::InvalidField;
+use crate::items::insert_item;
+use crate::stays::StayFields;
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{ItineraryItemType, User};
+
+/// Adds a stay to an itinerary the user owns or edits.
+#[tracing::instrument(name = "Create Stay", skip(db))]
+pub async fn create_stay(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Json(create_stay): Json<CreateStayRequest>,
+) -> Result<impl IntoResponse, CreateStayError> {
+    let stay_id = db
+        .create_stay((user.id, itinerary_id, create_stay).try_into()?)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WriteResponse {
+            location: Some(format!("/itineraries/{}/stays/{}", itinerary_id, stay_id)),
+            issues,
+        }),
+    ))
+}
+
+/// `location` is `{ "latitude": …, "longitude": … }`, check-in and check-out times are local.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateStayRequest {
+    #[serde(flatten)]
+    pub(crate) stay: StayFields,
+}
+
+impl TryFrom<(i32, i32, CreateStayRequest)> for InsertStay {
+    type Error = InvalidField;
+
+    fn try_from(
+        (user_id, itinerary_id, val): (i32, i32, CreateStayRequest),
+    ) -> Result<Self, Self::Error> {
+        Ok(InsertStay {
+            user_id,
+            itinerary_id,
+            stay: val.stay.validated()?,
+        })
+    }
+}
+
+pub struct InsertStay {
+    user_id: i32,
+    itinerary_id: i32,
+    stay: StayFields,
+}
+
+#[derive(Debug)]
+pub enum CreateStayError {
+    Access(AccessError),
+    InvalidField(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for CreateStayError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<InvalidField> for CreateStayError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl From<sqlx::Error> for CreateStayError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for CreateStayError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for CreateStayError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::InvalidField(error) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()).into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait CreateStayRespository {
+    /// Checks the user can edit the itinerary and inserts the stay in one transaction.
+    async fn create_stay(&self, create_stay: InsertStay) -> Result<i32, CreateStayError>;
+}
+
+impl CreateStayRespository for PgPool {
+    async fn create_stay(&self, create_stay: InsertStay) -> Result<i32, CreateStayError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(
+            &mut transaction,
+            create_stay.user_id,
+            create_stay.itinerary_id,
+        )
+        .await?;
+        let stay_id = transaction.insert_stay(create_stay).await?;
+
+        transaction.commit().await?;
+
+        Ok(stay_id)
+    }
+}
+
+pub trait InsertStayRepository {
+    /// Registers the stay's item and inserts the stay. Checking the user can edit the itinerary
+    /// is left to the caller.
+    async fn insert_stay(&mut self, create_stay: InsertStay) -> Result<i32>;
+}
+
+impl InsertStayRepository for Transaction<'_, Postgres> {
+    async fn insert_stay(&mut self, create_stay: InsertStay) -> Result<i32> {
+        let stay = create_stay.stay;
+        let item_id = insert_item(self, create_stay.itinerary_id, ItineraryItemType::Stay).await?;
+
+        let created = sqlx::query!(
+            r#"
+                insert into stays (
+                    itinerary_item_id,
+                    summary,
+                    start_date,
+                    end_date,
+                    check_in_time,
+                    check_out_time,
+                    address,
+                    location,
+                    confirmation_number,
+                    notes
+                )
+                values ($1, $2, $3, $4, $5, $6, $7, point($8, $9), $10, $11)
+                returning id
+            "#,
+            item_id,
+            stay.summary,
+            stay.start_date,
+            stay.end_date,
+            stay.check_in_time,
+            stay.check_out_time,
+            stay.address,
+            stay.location.map(|x| x.longitude),
+            stay.location.map(|x| x.latitude),
+            stay.confirmation_number,
+            stay.notes
+        )
+        .fetch_one(&mut **self)
+        .await?;
+
+        Ok(created.id)
+    }
+}
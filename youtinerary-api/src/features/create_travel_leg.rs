@@ -0,0 +1,269 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::items::insert_item;
+use crate::timeline::Coordinates;
+use crate::travel_legs::{resolve_end, LegDetails, TravelLegFields, TravelLegInputError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{ItineraryItemType, TravelLegType, User};
+
+/// Adds a train, bus, car, ferry or other leg to an itinerary the user owns or edits.
+#[tracing::instrument(name = "Create Travel Leg", skip(db))]
+pub async fn create_travel_leg(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Json(create_travel_leg): Json<CreateTravelLegRequest>,
+) -> Result<impl IntoResponse, CreateTravelLegError> {
+    let leg_id = db
+        .create_travel_leg((user.id, itinerary_id, create_travel_leg).try_into()?)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/travel_legs/{}",
+                itinerary_id, leg_id
+            )),
+            issues,
+        }),
+    ))
+}
+
+/// Each end of the leg takes either a UTC `*_time` or a `local_*_time` read in that end's
+/// timezone, and its date is then the local date of that time. An end without a time needs
+/// its date instead.
+///
+/// The mode-specific fields of [`LegDetails`] sit at the top level and are rejected for modes
+/// they don't apply to.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTravelLegRequest {
+    pub(crate) name: String,
+    pub(crate) travel_leg_type: TravelLegType,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+    pub(crate) start_time: Option<DateTime<Utc>>,
+    pub(crate) local_start_time: Option<NaiveDateTime>,
+    pub(crate) end_time: Option<DateTime<Utc>>,
+    pub(crate) local_end_time: Option<NaiveDateTime>,
+    pub(crate) start_timezone: Option<Tz>,
+    pub(crate) end_timezone: Option<Tz>,
+    pub(crate) start_place: Option<String>,
+    pub(crate) end_place: Option<String>,
+    pub(crate) start_location: Option<Coordinates>,
+    pub(crate) end_location: Option<Coordinates>,
+    pub(crate) operator: Option<String>,
+    pub(crate) booking_reference: Option<String>,
+    #[serde(flatten)]
+    pub(crate) details: LegDetails,
+    #[serde(default)]
+    pub(crate) notes: String,
+}
+
+impl TryFrom<(i32, i32, CreateTravelLegRequest)> for InsertTravelLeg {
+    type Error = TravelLegInputError;
+
+    fn try_from(
+        (user_id, itinerary_id, val): (i32, i32, CreateTravelLegRequest),
+    ) -> Result<Self, Self::Error> {
+        let (start_date, start_time) = resolve_end(
+            "start",
+            "start_date",
+            val.start_date,
+            val.start_time,
+            val.local_start_time,
+            val.start_timezone,
+        )?;
+        let (end_date, end_time) = resolve_end(
+            "end",
+            "end_date",
+            val.end_date,
+            val.end_time,
+            val.local_end_time,
+            val.end_timezone,
+        )?;
+
+        Ok(InsertTravelLeg {
+            user_id,
+            itinerary_id,
+            leg: TravelLegFields {
+                name: val.name,
+                travel_leg_type: val.travel_leg_type,
+                start_date,
+                end_date,
+                start_time,
+                end_time,
+                start_timezone: val.start_timezone,
+                end_timezone: val.end_timezone,
+                start_place: val.start_place,
+                end_place: val.end_place,
+                start_location: val.start_location,
+                end_location: val.end_location,
+                operator: val.operator,
+                booking_reference: val.booking_reference,
+                details: val.details,
+                notes: val.notes,
+            }
+            .validated()?,
+        })
+    }
+}
+
+struct InsertTravelLeg {
+    user_id: i32,
+    itinerary_id: i32,
+    leg: TravelLegFields,
+}
+
+#[derive(Debug)]
+pub enum CreateTravelLegError {
+    Access(AccessError),
+    Invalid(TravelLegInputError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for CreateTravelLegError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<TravelLegInputError> for CreateTravelLegError {
+    fn from(value: TravelLegInputError) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<sqlx::Error> for CreateTravelLegError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for CreateTravelLegError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for CreateTravelLegError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Invalid(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait CreateTravelLegRespository {
+    /// Registers the leg's item and inserts the leg in one transaction.
+    async fn create_travel_leg(
+        &self,
+        create_travel_leg: InsertTravelLeg,
+    ) -> Result<i32, CreateTravelLegError>;
+}
+
+impl CreateTravelLegRespository for PgPool {
+    async fn create_travel_leg(
+        &self,
+        create_travel_leg: InsertTravelLeg,
+    ) -> Result<i32, CreateTravelLegError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(
+            &mut transaction,
+            create_travel_leg.user_id,
+            create_travel_leg.itinerary_id,
+        )
+        .await?;
+        let leg = create_travel_leg.leg;
+        let details = &leg.details;
+        let item_id = insert_item(
+            &mut transaction,
+            create_travel_leg.itinerary_id,
+            ItineraryItemType::TravelLeg,
+        )
+        .await?;
+
+        let created = sqlx::query!(
+            r#"
+                insert into travel_legs (
+                    itinerary_item_id,
+                    name,
+                    travel_leg_type,
+                    start_date,
+                    end_date,
+                    start_time,
+                    end_time,
+                    start_timezone,
+                    end_timezone,
+                    start_place,
+                    end_place,
+                    start_location,
+                    end_location,
+                    operator,
+                    booking_reference,
+                    service_number,
+                    coach,
+                    seat,
+                    platform,
+                    vehicle,
+                    cabin,
+                    notes
+                )
+                values (
+                    $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
+                    point($12, $13), point($14, $15),
+                    $16, $17, $18, $19, $20, $21, $22, $23, $24
+                )
+                returning id
+            "#,
+            item_id,
+            leg.name,
+            leg.travel_leg_type as TravelLegType,
+            leg.start_date,
+            leg.end_date,
+            leg.start_time,
+            leg.end_time,
+            leg.start_timezone.map(|tz| tz.name()),
+            leg.end_timezone.map(|tz| tz.name()),
+            leg.start_place,
+            leg.end_place,
+            leg.start_location.map(|x| x.longitude),
+            leg.start_location.map(|x| x.latitude),
+            leg.end_location.map(|x| x.longitude),
+            leg.end_location.map(|x| x.latitude),
+            leg.operator,
+            leg.booking_reference,
+            details.service_number,
+            details.coach,
+            details.seat,
+            details.platform,
+            details.vehicle,
+            details.cabin,
+            leg.notes
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(created.id)
+    }
+}
@@ -0,0 +1,111 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Removes an activity from an itinerary.
+#[tracing::instrument(name = "Delete Activity", skip(db))]
+pub async fn delete_activity(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, activity_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, DeleteActivityError> {
+    db.delete_activity(user.id, itinerary_id, activity_id)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug)]
+pub enum DeleteActivityError {
+    Access(AccessError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for DeleteActivityError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for DeleteActivityError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for DeleteActivityError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for DeleteActivityError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait DeleteActivityRepository {
+    async fn delete_activity(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: i32,
+    ) -> Result<(), DeleteActivityError>;
+}
+
+impl DeleteActivityRepository for PgPool {
+    async fn delete_activity(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: i32,
+    ) -> Result<(), DeleteActivityError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        // The activity cascades with its item.
+        sqlx::query!(
+            r#"
+                delete from itinerary_items i
+                using activities a
+                where a.itinerary_item_id = i.id
+                    and i.itinerary_id = $1
+                    and a.id = $2
+                returning a.id
+            "#,
+            itinerary_id,
+            activity_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Removes a flight from an itinerary along with its passengers.
+#[tracing::instrument(name = "Delete Flight", skip(db))]
+pub async fn delete_flight(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, flight_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, DeleteFlightError> {
+    db.delete_flight(user.id, itinerary_id, flight_id).await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug)]
+pub enum DeleteFlightError {
+    Access(AccessError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for DeleteFlightError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for DeleteFlightError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for DeleteFlightError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for DeleteFlightError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait DeleteFlightRepository {
+    async fn delete_flight(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: i32,
+    ) -> Result<(), DeleteFlightError>;
+}
+
+impl DeleteFlightRepository for PgPool {
+    async fn delete_flight(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: i32,
+    ) -> Result<(), DeleteFlightError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        // The flight and its passengers cascade with its item.
+        sqlx::query!(
+            r#"
+                delete from itinerary_items i
+                using flights f
+                where f.itinerary_item_id = i.id
+                    and i.itinerary_id = $1
+                    and f.id = $2
+                returning f.id
+            "#,
+            itinerary_id,
+            flight_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Removes an item of any kind from an itinerary, along with the booking behind it.
+#[tracing::instrument(name = "Delete Item", skip(db))]
+pub async fn delete_item(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, item_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, DeleteItemError> {
+    db.delete_item(user.id, itinerary_id, item_id).await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug)]
+pub enum DeleteItemError {
+    Access(AccessError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for DeleteItemError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for DeleteItemError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for DeleteItemError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for DeleteItemError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait DeleteItemRepository {
+    async fn delete_item(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: i32,
+    ) -> Result<(), DeleteItemError>;
+}
+
+impl DeleteItemRepository for PgPool {
+    async fn delete_item(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: i32,
+    ) -> Result<(), DeleteItemError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        // The flight, stay, activity or leg cascades with its item.
+        sqlx::query!(
+            r#"
+                delete from itinerary_items
+                where itinerary_id = $1
+                    and id = $2
+                returning id
+            "#,
+            itinerary_id,
+            item_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::User;
+
+/// Archives the itinerary instead of dropping it, so it can still be restored.
+#[tracing::instrument(name = "Delete Itinerary", skip(db))]
+pub async fn delete_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AppError> {
+    match db.archive_itinerary(user.id, itinerary_id).await? {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Ok(StatusCode::NOT_FOUND),
+    }
+}
+
+trait ArchiveItineraryRespository {
+    async fn archive_itinerary(&self, user_id: i32, itinerary_id: i32) -> Result<Option<i32>>;
+}
+
+impl ArchiveItineraryRespository for PgPool {
+    async fn archive_itinerary(&self, user_id: i32, itinerary_id: i32) -> Result<Option<i32>> {
+        let archived = sqlx::query!(
+            r#"
+                update itineraries
+                set archived_from = case
+                        when status = 'archived' then archived_from
+                        else status
+                    end,
+                    status = 'archived',
+                    updated_at = case
+                        when status = 'archived' then updated_at
+                        else clock_timestamp()
+                    end
+                where user_id = $1
+                    and itinerary_id = $2
+                returning itinerary_id
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(archived.map(|x| x.itinerary_id))
+    }
+}
@@ -0,0 +1,110 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Removes a stay from an itinerary.
+#[tracing::instrument(name = "Delete Stay", skip(db))]
+pub async fn delete_stay(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, stay_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, DeleteStayError> {
+    db.delete_stay(user.id, itinerary_id, stay_id).await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug)]
+pub enum DeleteStayError {
+    Access(AccessError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for DeleteStayError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for DeleteStayError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for DeleteStayError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for DeleteStayError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait DeleteStayRepository {
+    async fn delete_stay(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: i32,
+    ) -> Result<(), DeleteStayError>;
+}
+
+impl DeleteStayRepository for PgPool {
+    async fn delete_stay(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: i32,
+    ) -> Result<(), DeleteStayError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        // The stay cascades with its item.
+        sqlx::query!(
+            r#"
+                delete from itinerary_items i
+                using stays s
+                where s.itinerary_item_id = i.id
+                    and i.itinerary_id = $1
+                    and s.id = $2
+                returning s.id
+            "#,
+            itinerary_id,
+            stay_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Removes a travel leg from an itinerary.
+#[tracing::instrument(name = "Delete Travel Leg", skip(db))]
+pub async fn delete_travel_leg(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, leg_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, DeleteTravelLegError> {
+    db.delete_travel_leg(user.id, itinerary_id, leg_id).await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug)]
+pub enum DeleteTravelLegError {
+    Access(AccessError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for DeleteTravelLegError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for DeleteTravelLegError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for DeleteTravelLegError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for DeleteTravelLegError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait DeleteTravelLegRepository {
+    async fn delete_travel_leg(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: i32,
+    ) -> Result<(), DeleteTravelLegError>;
+}
+
+impl DeleteTravelLegRepository for PgPool {
+    async fn delete_travel_leg(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: i32,
+    ) -> Result<(), DeleteTravelLegError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        // The leg cascades with its item.
+        sqlx::query!(
+            r#"
+                delete from itinerary_items i
+                using travel_legs t
+                where t.itinerary_item_id = i.id
+                    and i.itinerary_id = $1
+                    and t.id = $2
+                returning t.id
+            "#,
+            itinerary_id,
+            leg_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::User;
+
+/// Removes a booking from the user's inbox without adding it to any itinerary.
+#[tracing::instrument(name = "Dismiss Inbox Booking", skip(db))]
+pub async fn dismiss_inbox_booking(
+    user: User,
+    Path(booking_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    if db.dismiss_inbox_booking(user.id, booking_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
+trait DismissInboxBookingRepository {
+    async fn dismiss_inbox_booking(&self, user_id: i32, booking_id: i32) -> Result<bool>;
+}
+
+impl DismissInboxBookingRepository for PgPool {
+    async fn dismiss_inbox_booking(&self, user_id: i32, booking_id: i32) -> Result<bool> {
+        let dismissed = sqlx::query!(
+            r#"
+                delete from inbox_bookings b
+                using inbox_messages m
+                where m.id = b.message_id
+                    and b.id = $1
+                    and m.user_id = $2
+            "#,
+            booking_id,
+            user_id
+        )
+        .execute(self)
+        .await?;
+
+        Ok(dismissed.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,146 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::access::{find_role, ItineraryRole};
+use crate::activities::get_activities;
+use crate::error_handling::AppError;
+use crate::flights::get_flights;
+use crate::itinerary_document::{
+    ActivityRecord, FlightRecord, ItineraryDocument, ItineraryRecord, ShareRecord, StayRecord,
+    TravelLegRecord, ITINERARY_DOCUMENT_VERSION,
+};
+use crate::stays::get_stays;
+use crate::travel_legs::get_travel_legs;
+use crate::{ItineraryShareType, ItineraryStatus, User};
+
+#[tracing::instrument(name = "Export Itinerary", skip(db))]
+pub async fn export_itinerary(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some(document) = db.export_itinerary(user.id, itinerary_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    Ok((
+        StatusCode::OK,
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"itinerary-{}.json\"", itinerary_id),
+        )],
+        Json(document),
+    )
+        .into_response())
+}
+
+trait ExportItineraryRepository {
+    /// Reads the whole itinerary in one snapshot, or `None` when the user can't see it. Viewers
+    /// get the items shown to them and only the owner gets the shares.
+    async fn export_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<ItineraryDocument>>;
+}
+
+impl ExportItineraryRepository for PgPool {
+    async fn export_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<ItineraryDocument>> {
+        let mut transaction = self.begin().await?;
+        sqlx::query!("set transaction isolation level repeatable read, read only")
+            .execute(&mut *transaction)
+            .await?;
+
+        let Some(role) = find_role(&mut transaction, user_id, itinerary_id).await? else {
+            return Ok(None);
+        };
+
+        let itinerary = sqlx::query_as!(
+            ItineraryRecord,
+            r#"
+                select
+                    i.itinerary_id as "id?",
+                    i.name,
+                    i.status as "status: ItineraryStatus",
+                    i.is_template,
+                    s.start_date as "start_date?",
+                    e.end_date as "end_date?"
+                from itineraries i
+                    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+                    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+                where i.itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        // The same reads as the item endpoints, all within the snapshot.
+        let flights = get_flights(&mut transaction, itinerary_id, None).await?;
+        let stays = get_stays(&mut transaction, itinerary_id, None).await?;
+        let activities = get_activities(&mut transaction, itinerary_id, None).await?;
+        let travel_legs = get_travel_legs(&mut transaction, itinerary_id, None).await?;
+
+        let shares = match role {
+            ItineraryRole::Owner => {
+                sqlx::query_as!(
+                    ShareRecord,
+                    r#"
+                    select
+                        u.email,
+                        s.share_type as "share_type: ItineraryShareType",
+                        s.share_message
+                    from itinerary_shares s
+                        join users u on u.user_id = s.user_id
+                    where s.itinerary_id = $1
+                    order by u.email
+                "#,
+                    itinerary_id
+                )
+                .fetch_all(&mut *transaction)
+                .await?
+            }
+            ItineraryRole::Editor | ItineraryRole::Viewer => vec![],
+        };
+
+        transaction.commit().await?;
+
+        Ok(Some(ItineraryDocument {
+            version: ITINERARY_DOCUMENT_VERSION,
+            exported_at: Some(Utc::now()),
+            itinerary,
+            flights: flights
+                .into_iter()
+                .filter(|x| role.can_see(x.item.visibility))
+                .map(FlightRecord::from)
+                .collect(),
+            stays: stays
+                .into_iter()
+                .filter(|x| role.can_see(x.item.visibility))
+                .map(StayRecord::from)
+                .collect(),
+            activities: activities
+                .into_iter()
+                .filter(|x| role.can_see(x.item.visibility))
+                .map(ActivityRecord::from)
+                .collect(),
+            travel_legs: travel_legs
+                .into_iter()
+                .filter(|x| role.can_see(x.item.visibility))
+                .map(TravelLegRecord::from)
+                .collect(),
+            shares,
+        }))
+    }
+}
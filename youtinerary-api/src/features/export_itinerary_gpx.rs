@@ -0,0 +1,73 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::access::{find_role, ItineraryRole};
+use crate::error_handling::AppError;
+use crate::map::itinerary_gpx;
+use crate::timeline::TimelineRepository;
+use crate::User;
+
+#[tracing::instrument(name = "Export Itinerary GPX", skip(db))]
+pub async fn export_itinerary_gpx(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some((name, role)) = db.get_gpx_name(user.id, itinerary_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let mut timeline = db.get_timeline(itinerary_id).await?;
+    timeline.retain(|entry| role.can_see(entry.item().visibility));
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/gpx+xml".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"itinerary-{}.gpx\"", itinerary_id),
+            ),
+        ],
+        itinerary_gpx(&name, &timeline),
+    )
+        .into_response())
+}
+
+trait GpxNameRepository {
+    async fn get_gpx_name(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<(String, ItineraryRole)>>;
+}
+
+impl GpxNameRepository for PgPool {
+    async fn get_gpx_name(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<(String, ItineraryRole)>> {
+        let mut connection = self.acquire().await?;
+        let Some(role) = find_role(&mut connection, user_id, itinerary_id).await? else {
+            return Ok(None);
+        };
+
+        let itinerary = sqlx::query!(
+            r#"
+                select name
+                from itineraries
+                where itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .fetch_one(&mut *connection)
+        .await?;
+
+        Ok(Some((itinerary.name, role)))
+    }
+}
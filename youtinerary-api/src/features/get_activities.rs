@@ -0,0 +1,66 @@
+use axum::extract::{Path, State};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_view, AccessError};
+use crate::activities;
+use crate::timeline::ActivityEntry;
+use crate::User;
+
+/// Lists the activities of an itinerary in the order they start.
+#[tracing::instrument(name = "Get Activities", skip(db))]
+pub async fn get_activities(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AccessError> {
+    let activities = db.get_activities(user.id, itinerary_id, None).await?;
+
+    Ok((StatusCode::OK, Json(activities)))
+}
+
+#[tracing::instrument(name = "Get Activity", skip(db))]
+pub async fn get_activity(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, activity_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AccessError> {
+    let activity = db
+        .get_activities(user.id, itinerary_id, Some(activity_id))
+        .await?
+        .pop()
+        .ok_or(AccessError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(activity)))
+}
+
+trait GetActivitiesRepository {
+    /// Loads the activities of an itinerary the user can see, or only `activity_id` when given.
+    async fn get_activities(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: Option<i32>,
+    ) -> Result<Vec<ActivityEntry>, AccessError>;
+}
+
+impl GetActivitiesRepository for PgPool {
+    async fn get_activities(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: Option<i32>,
+    ) -> Result<Vec<ActivityEntry>, AccessError> {
+        let mut connection = self.acquire().await?;
+        let role = check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let mut activities =
+            activities::get_activities(&mut connection, itinerary_id, activity_id).await?;
+        activities.retain(|x| role.can_see(x.item.visibility));
+
+        Ok(activities)
+    }
+}
@@ -0,0 +1,33 @@
+use axum::extract::{Path, Query};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+use crate::airports::{find_airport, search_airports};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct GetAirportsQuery {
+    /// An IATA or ICAO code, or part of an airport or city name.
+    q: String,
+    limit: Option<usize>,
+}
+
+#[tracing::instrument(name = "Get Airports")]
+pub async fn get_airports(Query(query): Query<GetAirportsQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    Json(search_airports(&query.q, limit))
+}
+
+#[tracing::instrument(name = "Get Airport")]
+pub async fn get_airport(Path(code): Path<String>) -> impl IntoResponse {
+    match find_airport(&code) {
+        Some(airport) => Json(airport).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,69 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use chrono::Utc;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+use crate::access::ItineraryRole;
+use crate::error_handling::AppError;
+use crate::ical::itinerary_calendar;
+use crate::timeline::TimelineRepository;
+
+/// Serves an itinerary's calendar to subscribed calendar apps. The token in the path is the only
+/// credential, so there is deliberately no session here.
+#[tracing::instrument(name = "Get Feed Calendar", skip_all)]
+pub async fn get_feed_calendar(
+    Path(token): Path<String>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let Ok(token) = Uuid::parse_str(&token) else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let Some((itinerary_id, name)) = db.get_feed_itinerary(token).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    // Whoever holds the token only sees what a viewer of the itinerary would.
+    let mut timeline = db.get_timeline(itinerary_id).await?;
+    timeline.retain(|entry| ItineraryRole::Viewer.can_see(entry.item().visibility));
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        itinerary_calendar(&name, &timeline, Utc::now()),
+    )
+        .into_response())
+}
+
+trait FeedItineraryRepository {
+    /// Id and name of the itinerary behind a feed token that has not been revoked. Archived
+    /// itineraries are left out, their feeds come back when the itinerary is restored.
+    async fn get_feed_itinerary(&self, token: Uuid) -> Result<Option<(i32, String)>>;
+}
+
+impl FeedItineraryRepository for PgPool {
+    async fn get_feed_itinerary(&self, token: Uuid) -> Result<Option<(i32, String)>> {
+        let itinerary = sqlx::query!(
+            r#"
+                select
+                    i.itinerary_id,
+                    i.name
+                from itinerary_feeds f
+                    join itineraries i on i.itinerary_id = f.itinerary_id
+                where f.token = $1
+                    and i.status <> 'archived'
+            "#,
+            token
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(itinerary.map(|x| (x.itinerary_id, x.name)))
+    }
+}
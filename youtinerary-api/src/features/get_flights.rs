@@ -0,0 +1,65 @@
+use axum::extract::{Path, State};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_view, AccessError};
+use crate::flights;
+use crate::timeline::FlightEntry;
+use crate::User;
+
+/// Lists the flights of an itinerary in departure order.
+#[tracing::instrument(name = "Get Flights", skip(db))]
+pub async fn get_flights(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AccessError> {
+    let flights = db.get_flights(user.id, itinerary_id, None).await?;
+
+    Ok((StatusCode::OK, Json(flights)))
+}
+
+#[tracing::instrument(name = "Get Flight", skip(db))]
+pub async fn get_flight(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, flight_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AccessError> {
+    let flight = db
+        .get_flights(user.id, itinerary_id, Some(flight_id))
+        .await?
+        .pop()
+        .ok_or(AccessError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(flight)))
+}
+
+trait GetFlightsRepository {
+    /// Loads the flights of an itinerary the user can see, or only `flight_id` when given.
+    async fn get_flights(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: Option<i32>,
+    ) -> Result<Vec<FlightEntry>, AccessError>;
+}
+
+impl GetFlightsRepository for PgPool {
+    async fn get_flights(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: Option<i32>,
+    ) -> Result<Vec<FlightEntry>, AccessError> {
+        let mut connection = self.acquire().await?;
+        let role = check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let mut flights = flights::get_flights(&mut connection, itinerary_id, flight_id).await?;
+        flights.retain(|x| role.can_see(x.item.visibility));
+
+        Ok(flights)
+    }
+}
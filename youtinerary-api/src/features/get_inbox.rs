@@ -0,0 +1,135 @@
+use axum::extract::State;
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::types::Json as JsonColumn;
+use sqlx::PgPool;
+
+use super::ingest_itinerary::ProposedItem;
+use crate::error_handling::AppError;
+use crate::smtp::Inbox;
+use crate::{InboxMessageStatus, User};
+
+/// Number of received messages listed, newest first.
+const RECENT_MESSAGES: i64 = 50;
+
+#[derive(Serialize)]
+pub struct InboxViewModel {
+    /// Where to forward confirmations to, missing when the service doesn't receive mail.
+    address: Option<String>,
+    /// Bookings read from forwarded messages that aren't in an itinerary yet.
+    bookings: Vec<InboxBookingViewModel>,
+    messages: Vec<InboxMessageViewModel>,
+}
+
+#[derive(Serialize)]
+pub struct InboxBookingViewModel {
+    id: i32,
+    message_id: i32,
+    #[serde(flatten)]
+    item: ProposedItem,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct InboxMessageViewModel {
+    id: i32,
+    sender: String,
+    /// Read when the message is parsed.
+    subject: Option<String>,
+    status: InboxMessageStatus,
+    parser: Option<String>,
+    error: Option<String>,
+    received_at: NaiveDateTime,
+}
+
+/// Lists the bookings read from the confirmations the user forwarded, and the messages they came
+/// from, including the ones nothing was found in.
+#[tracing::instrument(name = "Get Inbox", skip(db, inbox))]
+pub async fn get_inbox(
+    user: User,
+    State(db): State<PgPool>,
+    State(inbox): State<Inbox>,
+) -> Result<impl IntoResponse, AppError> {
+    let inbox = db.get_inbox(user.id, &inbox).await?;
+
+    Ok((StatusCode::OK, Json(inbox)))
+}
+
+trait GetInboxRepository {
+    async fn get_inbox(&self, user_id: i32, inbox: &Inbox) -> Result<InboxViewModel>;
+}
+
+impl GetInboxRepository for PgPool {
+    async fn get_inbox(&self, user_id: i32, inbox: &Inbox) -> Result<InboxViewModel> {
+        let token = sqlx::query!(
+            r#"
+                select inbox_token
+                from users
+                where user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(self)
+        .await?
+        .map(|x| x.inbox_token);
+
+        let bookings = sqlx::query!(
+            r#"
+                select
+                    b.id,
+                    b.message_id,
+                    b.proposal as "proposal: JsonColumn<ProposedItem>",
+                    b.created_at
+                from inbox_bookings b
+                    join inbox_messages m on m.id = b.message_id
+                where m.user_id = $1
+                order by b.created_at, b.id
+            "#,
+            user_id
+        )
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|x| InboxBookingViewModel {
+            id: x.id,
+            message_id: x.message_id,
+            item: x.proposal.0,
+            created_at: x.created_at,
+        })
+        .collect();
+
+        let messages = sqlx::query_as!(
+            InboxMessageViewModel,
+            r#"
+                select
+                    id,
+                    sender,
+                    subject,
+                    status as "status: InboxMessageStatus",
+                    parser,
+                    error,
+                    received_at
+                from inbox_messages
+                where user_id = $1
+                order by received_at desc, id desc
+                limit $2
+            "#,
+            user_id,
+            RECENT_MESSAGES
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(InboxViewModel {
+            address: token.and_then(|token| inbox.address(token)),
+            bookings,
+            messages,
+        })
+    }
+}
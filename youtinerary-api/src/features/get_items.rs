@@ -0,0 +1,64 @@
+use axum::extract::{Path, State};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_view, AccessError};
+use crate::items::{self, ItemSummary};
+use crate::User;
+
+/// Lists every item of an itinerary whatever its kind, in timeline order.
+#[tracing::instrument(name = "Get Items", skip(db))]
+pub async fn get_items(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AccessError> {
+    let items = db.get_items(user.id, itinerary_id, None).await?;
+
+    Ok((StatusCode::OK, Json(items)))
+}
+
+#[tracing::instrument(name = "Get Item", skip(db))]
+pub async fn get_item(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, item_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AccessError> {
+    let item = db
+        .get_items(user.id, itinerary_id, Some(item_id))
+        .await?
+        .pop()
+        .ok_or(AccessError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(item)))
+}
+
+trait GetItemsRepository {
+    /// Loads the items of an itinerary the user can see, or only `item_id` when given.
+    async fn get_items(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: Option<i32>,
+    ) -> Result<Vec<ItemSummary>, AccessError>;
+}
+
+impl GetItemsRepository for PgPool {
+    async fn get_items(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: Option<i32>,
+    ) -> Result<Vec<ItemSummary>, AccessError> {
+        let mut connection = self.acquire().await?;
+        let role = check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let mut items = items::get_items(&mut connection, itinerary_id, item_id).await?;
+        items.retain(|x| role.can_see(x.visibility));
+
+        Ok(items)
+    }
+}
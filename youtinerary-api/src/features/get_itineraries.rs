@@ -1,79 +1,448 @@
-use axum::extract::{Path, State};
+use axum::extract::{OriginalUri, Query, State};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
-use chrono::{DateTime, Utc};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
 
-use crate::User;
-use crate::error_handling::AppError;
+use crate::{ItineraryStatus, User};
+
+const DEFAULT_PAGE_SIZE: i64 = 25;
+const MAX_PAGE_SIZE: i64 = 100;
 
 #[derive(Serialize, Deserialize)]
-struct IntinerarySummaryView<'a> {
+struct IntinerarySummaryView {
     id: i32,
-    name: &'a str,
+    name: String,
+    status: ItineraryStatus,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+    updated_at: NaiveDateTime,
 }
 
-impl<'a> From<&'a Itinerary> for IntinerarySummaryView<'a> {
-    fn from(value: &'a Itinerary) -> Self {
+impl From<Itinerary> for IntinerarySummaryView {
+    fn from(value: Itinerary) -> Self {
         Self {
             id: value.id,
-            name: &value.name,
+            name: value.name,
+            status: value.status,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            updated_at: value.updated_at,
         }
     }
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GetItinerariesQuery {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    include_archived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<ItineraryStatus>,
+    /// Only trips that overlap `from..=to` are returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<NaiveDate>,
+    /// Case insensitive substring of the itinerary name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(default)]
+    sort: ItinerarySort,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItinerarySort {
+    #[serde(rename = "start_date")]
+    StartDateAscending,
+    #[serde(rename = "-start_date")]
+    StartDateDescending,
+    #[serde(rename = "updated_at")]
+    UpdatedAtAscending,
+    #[default]
+    #[serde(rename = "-updated_at")]
+    UpdatedAtDescending,
+}
+
+impl ItinerarySort {
+    fn is_descending(self) -> bool {
+        matches!(self, Self::StartDateDescending | Self::UpdatedAtDescending)
+    }
+}
+
 #[tracing::instrument(name = "Get Itineraries", skip(db))]
 pub async fn get_itineraries(
     user: User,
     State(db): State<PgPool>,
-) -> Result<impl IntoResponse, AppError> {
-    // let itineraries: Vec<IntinerarySummaryView> = db
-    //     .get_itineraries(user.id)
-    //     .await?
-    //     .into_iter()
-    //     .map(|x| x.into())
-    //     .collect();
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<GetItinerariesQuery>,
+) -> Result<impl IntoResponse, GetItinerariesError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(GetItinerariesError::InvalidLimit);
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if to < from {
+            return Err(GetItinerariesError::InvalidDateRange);
+        }
+    }
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|cursor| Cursor::decode(cursor, query.sort))
+        .transpose()?;
+
+    let mut itineraries = db
+        .get_itineraries(user.id, &query, cursor.as_ref(), limit + 1)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    if itineraries.len() as i64 > limit {
+        itineraries.truncate(limit as usize);
+        let last = itineraries.last().expect("limit is at least one");
+        let next = GetItinerariesQuery {
+            limit: Some(limit),
+            cursor: Some(Cursor::after(last, query.sort).encode()),
+            ..query
+        };
+        let link = format!(
+            "<{}?{}>; rel=\"next\"",
+            uri.path(),
+            serde_urlencoded::to_string(&next).context("failed to encode the next page link")?
+        );
+        headers.insert(
+            header::LINK,
+            link.parse().context("failed to build the Link header")?,
+        );
+    }
+
+    let itineraries: Vec<IntinerarySummaryView> =
+        itineraries.into_iter().map(|x| x.into()).collect();
+
+    Ok((StatusCode::OK, headers, Json(itineraries)))
+}
+
+#[derive(Debug)]
+pub enum GetItinerariesError {
+    InvalidCursor,
+    InvalidLimit,
+    InvalidDateRange,
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for GetItinerariesError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for GetItinerariesError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::InvalidCursor => (
+                StatusCode::BAD_REQUEST,
+                "cursor is not valid for this sort order",
+            )
+                .into_response(),
+            Self::InvalidLimit => (
+                StatusCode::BAD_REQUEST,
+                format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+            )
+                .into_response(),
+            Self::InvalidDateRange => {
+                (StatusCode::BAD_REQUEST, "to must not be before from").into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Position of the last row of a page, so the next page can continue after it.
+///
+/// Trips without a start date sort as if they started on the last possible day: after every
+/// dated trip in ascending order and ahead of them in descending order.
+#[derive(Debug, PartialEq)]
+enum Cursor {
+    StartDate(Option<NaiveDate>, i32),
+    UpdatedAt(NaiveDateTime, i32),
+}
+
+const NO_START_DATE: &str = "none";
 
-    let itineraries: Vec<IntinerarySummaryView> = vec![];
+impl Cursor {
+    fn after(itinerary: &Itinerary, sort: ItinerarySort) -> Self {
+        match sort {
+            ItinerarySort::StartDateAscending | ItinerarySort::StartDateDescending => {
+                Self::StartDate(itinerary.start_date, itinerary.id)
+            }
+            ItinerarySort::UpdatedAtAscending | ItinerarySort::UpdatedAtDescending => {
+                Self::UpdatedAt(itinerary.updated_at, itinerary.id)
+            }
+        }
+    }
+
+    fn encode(&self) -> String {
+        let raw = match self {
+            Self::StartDate(Some(start_date), id) => format!("{}|{}", start_date, id),
+            Self::StartDate(None, id) => format!("{}|{}", NO_START_DATE, id),
+            Self::UpdatedAt(updated_at, id) => {
+                format!("{}|{}", updated_at.and_utc().timestamp_micros(), id)
+            }
+        };
+        URL_SAFE_NO_PAD.encode(raw)
+    }
 
-    // let itineraries: Vec<IntinerarySummaryView> = db
-    //     .get_itineraries(user.id);
+    fn decode(cursor: &str, sort: ItinerarySort) -> Result<Self, GetItinerariesError> {
+        let raw = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|raw| String::from_utf8(raw).ok())
+            .ok_or(GetItinerariesError::InvalidCursor)?;
+        let (key, id) = raw
+            .split_once('|')
+            .ok_or(GetItinerariesError::InvalidCursor)?;
+        let id = id.parse().map_err(|_| GetItinerariesError::InvalidCursor)?;
 
-    Ok((StatusCode::OK, Json(itineraries)))
+        match sort {
+            ItinerarySort::StartDateAscending | ItinerarySort::StartDateDescending
+                if key == NO_START_DATE =>
+            {
+                Ok(Self::StartDate(None, id))
+            }
+            ItinerarySort::StartDateAscending | ItinerarySort::StartDateDescending => key
+                .parse()
+                .map(|start_date| Self::StartDate(Some(start_date), id))
+                .map_err(|_| GetItinerariesError::InvalidCursor),
+            ItinerarySort::UpdatedAtAscending | ItinerarySort::UpdatedAtDescending => key
+                .parse()
+                .ok()
+                .and_then(NaiveDateTime::from_timestamp_micros)
+                .map(|updated_at| Self::UpdatedAt(updated_at, id))
+                .ok_or(GetItinerariesError::InvalidCursor),
+        }
+    }
 }
 
 trait GetItineraryRespository {
-    async fn get_itineraries(&self, user_id: i32) -> Result<Vec<Itinerary>>;
+    async fn get_itineraries(
+        &self,
+        user_id: i32,
+        query: &GetItinerariesQuery,
+        cursor: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Itinerary>>;
 }
 
 impl GetItineraryRespository for PgPool {
-    async fn get_itineraries(&self, user_id: i32) -> Result<Vec<Itinerary>> {
-        let itineraries = sqlx::query_as!(
-            Itinerary,
+    async fn get_itineraries(
+        &self,
+        user_id: i32,
+        query: &GetItinerariesQuery,
+        cursor: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Itinerary>> {
+        let sort_key = match query.sort {
+            ItinerarySort::StartDateAscending | ItinerarySort::StartDateDescending => {
+                "coalesce(d.start_date, 'infinity'::date)"
+            }
+            ItinerarySort::UpdatedAtAscending | ItinerarySort::UpdatedAtDescending => {
+                "i.updated_at"
+            }
+        };
+        let (comparison, direction) = match query.sort.is_descending() {
+            true => ("<", "desc"),
+            false => (">", "asc"),
+        };
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
-                select 
-                    itinerary_id as "id!",
-                    name,
-                    user_id
-                from itineraries
-                where user_id = $1
-            "#,
-            user_id
-        )
-        .fetch_all(self)
-        .await?;
+                select
+                    i.itinerary_id as id,
+                    i.name,
+                    i.status,
+                    i.updated_at,
+                    d.start_date,
+                    d.end_date
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                where not i.is_template
+                    and (i.user_id = "#,
+        );
+        builder
+            .push_bind(user_id)
+            .push(
+                " or exists (select 1 from itinerary_shares s \
+                where s.itinerary_id = i.itinerary_id and s.user_id = ",
+            )
+            .push_bind(user_id)
+            .push("))");
+
+        match query.status {
+            Some(status) => {
+                builder.push(" and i.status = ").push_bind(status);
+            }
+            None if !query.include_archived => {
+                builder.push(" and i.status <> 'archived'");
+            }
+            None => {}
+        }
+        if let Some(from) = query.from {
+            builder.push(" and d.end_date >= ").push_bind(from);
+        }
+        if let Some(to) = query.to {
+            builder.push(" and d.start_date <= ").push_bind(to);
+        }
+        if let Some(q) = query.q.as_deref().filter(|q| !q.is_empty()) {
+            let escaped = q
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            builder
+                .push(" and i.name ilike ")
+                .push_bind(format!("%{}%", escaped));
+        }
+
+        match cursor {
+            Some(Cursor::StartDate(start_date, id)) => {
+                builder.push(format!(
+                    " and ({}, i.itinerary_id) {} (coalesce(",
+                    sort_key, comparison
+                ));
+                builder
+                    .push_bind(*start_date)
+                    .push(", 'infinity'::date), ")
+                    .push_bind(*id)
+                    .push(")");
+            }
+            Some(Cursor::UpdatedAt(updated_at, id)) => {
+                builder.push(format!(
+                    " and ({}, i.itinerary_id) {} (",
+                    sort_key, comparison
+                ));
+                builder
+                    .push_bind(*updated_at)
+                    .push(", ")
+                    .push_bind(*id)
+                    .push(")");
+            }
+            None => {}
+        }
+
+        builder.push(format!(
+            " order by {} {}, i.itinerary_id {} limit ",
+            sort_key, direction, direction
+        ));
+        builder.push_bind(limit);
+
+        let itineraries = builder
+            .build_query_as::<Itinerary>()
+            .fetch_all(self)
+            .await?;
 
         Ok(itineraries)
     }
 }
 
+#[derive(FromRow)]
 pub struct Itinerary {
     pub id: i32,
     pub name: String,
-    pub user_id: i32,
+    pub status: ItineraryStatus,
+    pub updated_at: NaiveDateTime,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(cursor: Cursor, sort: ItinerarySort) -> Cursor {
+        Cursor::decode(&cursor.encode(), sort).expect("an encoded cursor decodes")
+    }
+
+    #[test]
+    fn start_date_cursors_round_trip() {
+        let start_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+        for sort in [
+            ItinerarySort::StartDateAscending,
+            ItinerarySort::StartDateDescending,
+        ] {
+            assert_eq!(
+                round_trip(Cursor::StartDate(start_date, 42), sort),
+                Cursor::StartDate(start_date, 42)
+            );
+            assert_eq!(
+                round_trip(Cursor::StartDate(None, 7), sort),
+                Cursor::StartDate(None, 7)
+            );
+        }
+    }
+
+    #[test]
+    fn updated_at_cursors_round_trip_to_the_microsecond() {
+        let updated_at = NaiveDate::from_ymd_opt(2026, 3, 14)
+            .and_then(|date| date.and_hms_micro_opt(9, 26, 53, 589_793))
+            .unwrap();
+        for sort in [
+            ItinerarySort::UpdatedAtAscending,
+            ItinerarySort::UpdatedAtDescending,
+        ] {
+            assert_eq!(
+                round_trip(Cursor::UpdatedAt(updated_at, 3), sort),
+                Cursor::UpdatedAt(updated_at, 3)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        let updated_at_cursor = Cursor::UpdatedAt(NaiveDateTime::default(), 1).encode();
+        for (cursor, sort) in [
+            ("not base64!", ItinerarySort::StartDateAscending),
+            (
+                &URL_SAFE_NO_PAD.encode("2026-03-14"),
+                ItinerarySort::StartDateAscending,
+            ),
+            (
+                &URL_SAFE_NO_PAD.encode("2026-03-14|x"),
+                ItinerarySort::StartDateAscending,
+            ),
+            (
+                &URL_SAFE_NO_PAD.encode("yesterday|1"),
+                ItinerarySort::StartDateDescending,
+            ),
+            (
+                &URL_SAFE_NO_PAD.encode([0xff, b'|', b'1']),
+                ItinerarySort::StartDateAscending,
+            ),
+            (&updated_at_cursor, ItinerarySort::StartDateAscending),
+        ] {
+            assert!(
+                matches!(
+                    Cursor::decode(cursor, sort),
+                    Err(GetItinerariesError::InvalidCursor)
+                ),
+                "{} should be rejected",
+                cursor
+            );
+        }
+    }
 }
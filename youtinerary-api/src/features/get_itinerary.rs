@@ -1,32 +1,55 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 
 use anyhow::Result;
 
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use serde::Serialize;
-use serde_json::json;
+use axum_extra::TypedHeader;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 use sqlx::{Error, PgPool};
 
-use crate::User;
-use crate::error_handling::AppError;
+use crate::access::{find_role, ItineraryRole};
+use crate::etag::etag_from_updated_at;
+use crate::timeline::{TimelineEntry, TimelineRepository};
+use crate::{ItineraryStatus, User};
 
 #[derive(Serialize)]
 pub struct ItineraryViewModel {
     pub id: i32,
     pub name: String,
+    pub status: ItineraryStatus,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeline: Option<Vec<TimelineEntry>>,
 }
 impl From<Itinerary> for ItineraryViewModel {
     fn from(value: Itinerary) -> Self {
         Self {
             id: value.id,
             name: value.name,
+            status: value.status,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            timeline: None,
         }
     }
 }
-struct ErrorMessage {
-    message: String,
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GetItineraryQuery {
+    /// Comma separated list of related data to embed, `timeline` is the only one so far.
+    include: Option<String>,
+}
+
+impl GetItineraryQuery {
+    fn includes(&self, name: &str) -> bool {
+        self.include
+            .as_deref()
+            .is_some_and(|include| include.split(',').any(|x| x.trim() == name))
+    }
 }
 
 #[tracing::instrument(name = "Get Itinerary", skip(db))]
@@ -34,11 +57,25 @@ pub async fn get_itinerary(
     user: User,
     Path(itinerary_id): Path<i32>,
     State(db): State<PgPool>,
+    Query(query): Query<GetItineraryQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
     match db.get_itinerary(user.id, itinerary_id).await {
-        Ok(itinerary) => {
-            let itinerary_view_model = ItineraryViewModel::from(itinerary);
-            return Ok((StatusCode::OK, Json(itinerary_view_model)));
+        Ok((itinerary, role)) => {
+            let etag = etag_from_updated_at(itinerary.updated_at);
+            let mut itinerary_view_model = ItineraryViewModel::from(itinerary);
+            if query.includes("timeline") {
+                let mut timeline = db.get_timeline(itinerary_id).await.map_err(|error| {
+                    tracing::error!("failed to load the timeline: {:?}", error);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+                timeline.retain(|entry| role.can_see(entry.item().visibility));
+                itinerary_view_model.timeline = Some(timeline);
+            }
+            Ok((
+                StatusCode::OK,
+                TypedHeader(etag),
+                Json(itinerary_view_model),
+            ))
         }
         Err(error) => match error {
             GetItineraryError::UnableToFindItinerary => Err(StatusCode::NOT_FOUND),
@@ -47,18 +84,19 @@ pub async fn get_itinerary(
 }
 
 trait GetItineraryRespository {
+    /// Loads an itinerary the user owns or that is shared with them, along with their role.
     async fn get_itinerary(
         &self,
         user_id: i32,
         itinerary_id: i32,
-    ) -> Result<Itinerary, GetItineraryError>;
+    ) -> Result<(Itinerary, ItineraryRole), GetItineraryError>;
 }
 
 enum GetItineraryError {
     UnableToFindItinerary,
 }
 impl From<Error> for GetItineraryError {
-    fn from(value: Error) -> Self {
+    fn from(_value: Error) -> Self {
         Self::UnableToFindItinerary
     }
 }
@@ -68,31 +106,41 @@ impl GetItineraryRespository for PgPool {
         &self,
         user_id: i32,
         itinerary_id: i32,
-    ) -> Result<Itinerary, GetItineraryError> {
+    ) -> Result<(Itinerary, ItineraryRole), GetItineraryError> {
+        let mut connection = self.acquire().await?;
+        let role = find_role(&mut connection, user_id, itinerary_id)
+            .await?
+            .ok_or(GetItineraryError::UnableToFindItinerary)?;
+
         let itinerary = sqlx::query_as!(
             Itinerary,
             r#"
-                select 
-                    itinerary_id as "id!",
-                    name,
-                    user_id
-                from itineraries
-                where user_id = $1
-                    and itinerary_id = $2
+                select
+                    i.itinerary_id as "id!",
+                    i.name,
+                    i.status as "status: ItineraryStatus",
+                    d.start_date,
+                    d.end_date,
+                    i.updated_at
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                where i.itinerary_id = $1
                 limit 1
             "#,
-            user_id,
             itinerary_id
         )
-        .fetch_one(self)
+        .fetch_one(&mut *connection)
         .await?;
 
-        Ok(itinerary)
+        Ok((itinerary, role))
     }
 }
 
 pub struct Itinerary {
     pub id: i32,
     pub name: String,
-    pub user_id: i32,
+    pub status: ItineraryStatus,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub updated_at: NaiveDateTime,
 }
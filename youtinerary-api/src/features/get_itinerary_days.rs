@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::{NaiveDate, NaiveTime};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::access::{find_role, ItineraryRole};
+use crate::error_handling::AppError;
+use crate::timeline::{TimelineEntry, TimelineRepository};
+use crate::User;
+
+#[derive(Serialize)]
+struct DayViewModel {
+    date: NaiveDate,
+    items: Vec<DayItem>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StayMarker {
+    Ongoing,
+    CheckOut,
+    CheckIn,
+}
+
+#[derive(Serialize)]
+struct DayItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    marker: Option<StayMarker>,
+    /// Wall clock time the item starts at in its own timezone, for items that have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_time: Option<NaiveTime>,
+    #[serde(flatten)]
+    entry: TimelineEntry,
+}
+
+impl DayItem {
+    /// Check-outs come first in the morning and check-ins last, everything else sits between
+    /// them with untimed items ahead of timed ones.
+    fn sort_key(&self) -> (u8, Option<NaiveTime>) {
+        match self.marker {
+            Some(StayMarker::Ongoing) => (0, None),
+            Some(StayMarker::CheckOut) => (1, None),
+            None => (2, self.local_time),
+            Some(StayMarker::CheckIn) => (3, None),
+        }
+    }
+}
+
+#[tracing::instrument(name = "Get Itinerary Days", skip(db))]
+pub async fn get_itinerary_days(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let Some((role, dates)) = db.get_itinerary_range(user.id, itinerary_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let mut days: BTreeMap<NaiveDate, Vec<DayItem>> = BTreeMap::new();
+    if let (Some(start_date), Some(end_date)) = dates {
+        for date in start_date.iter_days().take_while(|date| *date <= end_date) {
+            days.entry(date).or_default();
+        }
+    }
+
+    for entry in db.get_timeline(itinerary_id).await? {
+        if !role.can_see(entry.item().visibility) {
+            continue;
+        }
+        for (date, item) in place_on_days(entry) {
+            days.entry(date).or_default().push(item);
+        }
+    }
+
+    let days: Vec<DayViewModel> = days
+        .into_iter()
+        .map(|(date, mut items)| {
+            items.sort_by_key(DayItem::sort_key);
+            DayViewModel { date, items }
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(days)).into_response())
+}
+
+/// Spreads an entry over the local calendar days it belongs to.
+///
+/// Flights land on the day they depart in the departure timezone. Stays, activities and travel
+/// legs already store local calendar dates, and a leg's date matches its local start time.
+fn place_on_days(entry: TimelineEntry) -> Vec<(NaiveDate, DayItem)> {
+    match &entry {
+        TimelineEntry::Flight(flight) => {
+            let departure = flight.local_departure();
+            vec![(
+                departure.date(),
+                DayItem {
+                    marker: None,
+                    local_time: Some(departure.time()),
+                    entry,
+                },
+            )]
+        }
+        TimelineEntry::Stay(stay) => {
+            let (start_date, end_date) = (stay.start_date, stay.end_date);
+            start_date
+                .iter_days()
+                .take_while(|date| *date <= end_date.max(start_date))
+                .map(|date| {
+                    let marker = if date == start_date {
+                        StayMarker::CheckIn
+                    } else if date == end_date {
+                        StayMarker::CheckOut
+                    } else {
+                        StayMarker::Ongoing
+                    };
+                    (
+                        date,
+                        DayItem {
+                            marker: Some(marker),
+                            local_time: None,
+                            entry: entry.clone(),
+                        },
+                    )
+                })
+                .collect()
+        }
+        TimelineEntry::Activity(activity) => {
+            let (start_date, end_date) = (activity.start_date, activity.end_date);
+            start_date
+                .iter_days()
+                .take_while(|date| *date <= end_date.max(start_date))
+                .map(|date| {
+                    (
+                        date,
+                        DayItem {
+                            marker: None,
+                            local_time: activity.start_time.filter(|_| date == start_date),
+                            entry: entry.clone(),
+                        },
+                    )
+                })
+                .collect()
+        }
+        TimelineEntry::TravelLeg(leg) => vec![(
+            leg.start_date,
+            DayItem {
+                marker: None,
+                local_time: leg.local_start().map(|start| start.time()),
+                entry,
+            },
+        )],
+    }
+}
+
+trait ItineraryRangeRepository {
+    /// The user's role and the itinerary's effective dates, or `None` when the user can't see
+    /// the itinerary.
+    async fn get_itinerary_range(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<(ItineraryRole, (Option<NaiveDate>, Option<NaiveDate>))>>;
+}
+
+impl ItineraryRangeRepository for PgPool {
+    async fn get_itinerary_range(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<Option<(ItineraryRole, (Option<NaiveDate>, Option<NaiveDate>))>> {
+        let mut connection = self.acquire().await?;
+        let Some(role) = find_role(&mut connection, user_id, itinerary_id).await? else {
+            return Ok(None);
+        };
+
+        let dates = sqlx::query!(
+            r#"
+                select
+                    d.start_date,
+                    d.end_date
+                from itinerary_dates d
+                where d.itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .fetch_one(&mut *connection)
+        .await?;
+
+        Ok(Some((role, (dates.start_date, dates.end_date))))
+    }
+}
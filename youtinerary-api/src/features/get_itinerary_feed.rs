@@ -0,0 +1,73 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::links::PublicUrl;
+use crate::User;
+
+#[derive(Serialize)]
+pub struct FeedViewModel {
+    url: String,
+    created_at: NaiveDateTime,
+}
+
+#[tracing::instrument(name = "Get Itinerary Feed", skip(db, public_url))]
+pub async fn get_itinerary_feed(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+    State(public_url): State<PublicUrl>,
+) -> Result<impl IntoResponse, AppError> {
+    match db.get_itinerary_feed(user.id, itinerary_id).await? {
+        Some(feed) => Ok((
+            StatusCode::OK,
+            Json(FeedViewModel {
+                url: public_url.feed_calendar(feed.token),
+                created_at: feed.created_at,
+            }),
+        )
+            .into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+struct Feed {
+    token: Uuid,
+    created_at: NaiveDateTime,
+}
+
+trait GetItineraryFeedRepository {
+    async fn get_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<Option<Feed>>;
+}
+
+impl GetItineraryFeedRepository for PgPool {
+    async fn get_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<Option<Feed>> {
+        let feed = sqlx::query_as!(
+            Feed,
+            r#"
+                select
+                    f.token,
+                    f.created_at
+                from itinerary_feeds f
+                    join itineraries i on i.itinerary_id = f.itinerary_id
+                where i.user_id = $1
+                    and i.itinerary_id = $2
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(feed)
+    }
+}
@@ -0,0 +1,40 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::find_role;
+use crate::error_handling::AppError;
+use crate::validation::IssuesRepository;
+use crate::User;
+
+/// Lists likely mistakes in an itinerary, such as overlapping bookings or missed connections.
+#[tracing::instrument(name = "Get Itinerary Issues", skip(db))]
+pub async fn get_itinerary_issues(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    if !db.can_view_issues(user.id, itinerary_id).await? {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((StatusCode::OK, Json(issues)).into_response())
+}
+
+trait IssuesAccessRepository {
+    async fn can_view_issues(&self, user_id: i32, itinerary_id: i32) -> Result<bool>;
+}
+
+impl IssuesAccessRepository for PgPool {
+    async fn can_view_issues(&self, user_id: i32, itinerary_id: i32) -> Result<bool> {
+        let role = find_role(&mut *self.acquire().await?, user_id, itinerary_id).await?;
+
+        Ok(role.is_some())
+    }
+}
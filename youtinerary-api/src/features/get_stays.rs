@@ -0,0 +1,65 @@
+use axum::extract::{Path, State};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_view, AccessError};
+use crate::stays;
+use crate::timeline::StayEntry;
+use crate::User;
+
+/// Lists the stays of an itinerary in check-in order.
+#[tracing::instrument(name = "Get Stays", skip(db))]
+pub async fn get_stays(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AccessError> {
+    let stays = db.get_stays(user.id, itinerary_id, None).await?;
+
+    Ok((StatusCode::OK, Json(stays)))
+}
+
+#[tracing::instrument(name = "Get Stay", skip(db))]
+pub async fn get_stay(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, stay_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AccessError> {
+    let stay = db
+        .get_stays(user.id, itinerary_id, Some(stay_id))
+        .await?
+        .pop()
+        .ok_or(AccessError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(stay)))
+}
+
+trait GetStaysRepository {
+    /// Loads the stays of an itinerary the user can see, or only `stay_id` when given.
+    async fn get_stays(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: Option<i32>,
+    ) -> Result<Vec<StayEntry>, AccessError>;
+}
+
+impl GetStaysRepository for PgPool {
+    async fn get_stays(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: Option<i32>,
+    ) -> Result<Vec<StayEntry>, AccessError> {
+        let mut connection = self.acquire().await?;
+        let role = check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let mut stays = stays::get_stays(&mut connection, itinerary_id, stay_id).await?;
+        stays.retain(|x| role.can_see(x.item.visibility));
+
+        Ok(stays)
+    }
+}
@@ -0,0 +1,64 @@
+use axum::extract::State;
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::{ItineraryShareType, User};
+
+#[derive(Serialize)]
+pub struct TemplateViewModel {
+    id: i32,
+    name: String,
+    /// Number of days the template spans, when it has any dated items.
+    days: Option<i32>,
+    /// How the template was shared with the user, or `None` for their own templates.
+    share_type: Option<ItineraryShareType>,
+}
+
+#[tracing::instrument(name = "Get Templates", skip(db))]
+pub async fn get_templates(
+    user: User,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let templates = db.get_templates(user.id).await?;
+    Ok((StatusCode::OK, Json(templates)))
+}
+
+trait GetTemplatesRepository {
+    /// Live templates the user owns, followed by the ones shared with them.
+    async fn get_templates(&self, user_id: i32) -> Result<Vec<TemplateViewModel>>;
+}
+
+impl GetTemplatesRepository for PgPool {
+    async fn get_templates(&self, user_id: i32) -> Result<Vec<TemplateViewModel>> {
+        let templates = sqlx::query_as!(
+            TemplateViewModel,
+            r#"
+                select
+                    i.itinerary_id as id,
+                    i.name,
+                    d.end_date - d.start_date + 1 as days,
+                    s.share_type as "share_type: ItineraryShareType"
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                    left join itinerary_shares s
+                        on s.itinerary_id = i.itinerary_id and s.user_id = $1
+                where i.is_template
+                    and i.status <> 'archived'
+                    and (i.user_id = $1 or s.user_id is not null)
+                order by s.share_type is not null, i.name, i.itinerary_id
+            "#,
+            user_id
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(templates)
+    }
+}
@@ -0,0 +1,65 @@
+use axum::extract::{Path, State};
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use sqlx::PgPool;
+
+use crate::access::{check_can_view, AccessError};
+use crate::timeline::TravelLegEntry;
+use crate::travel_legs;
+use crate::User;
+
+/// Lists the travel legs of an itinerary in the order they start.
+#[tracing::instrument(name = "Get Travel Legs", skip(db))]
+pub async fn get_travel_legs(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, AccessError> {
+    let legs = db.get_travel_legs(user.id, itinerary_id, None).await?;
+
+    Ok((StatusCode::OK, Json(legs)))
+}
+
+#[tracing::instrument(name = "Get Travel Leg", skip(db))]
+pub async fn get_travel_leg(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, leg_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AccessError> {
+    let leg = db
+        .get_travel_legs(user.id, itinerary_id, Some(leg_id))
+        .await?
+        .pop()
+        .ok_or(AccessError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(leg)))
+}
+
+trait GetTravelLegsRepository {
+    /// Loads the travel legs of an itinerary the user can see, or only `leg_id` when given.
+    async fn get_travel_legs(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: Option<i32>,
+    ) -> Result<Vec<TravelLegEntry>, AccessError>;
+}
+
+impl GetTravelLegsRepository for PgPool {
+    async fn get_travel_legs(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: Option<i32>,
+    ) -> Result<Vec<TravelLegEntry>, AccessError> {
+        let mut connection = self.acquire().await?;
+        let role = check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let mut legs = travel_legs::get_travel_legs(&mut connection, itinerary_id, leg_id).await?;
+        legs.retain(|x| role.can_see(x.item.visibility));
+
+        Ok(legs)
+    }
+}
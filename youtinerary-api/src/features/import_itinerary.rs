@@ -0,0 +1,483 @@
+use axum::extract::State;
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sqlx::PgPool;
+
+use super::save_template::TemplateTimesRepository;
+use crate::fields::InvalidField;
+use crate::flights::insert_passengers;
+use crate::itinerary_document::{
+    ActivityRecord, FlightRecord, ItineraryDocument, StayRecord, TravelLegRecord,
+    ITINERARY_DOCUMENT_VERSION,
+};
+use crate::{
+    ActivityCategory, CabinClass, ItemVisibility, ItineraryShareType, TravelLegType, User,
+};
+
+/// Recreates an exported itinerary under the calling user with fresh ids.
+///
+/// Shares are restored for users that exist in this environment, the others are dropped. The copy
+/// always starts as a draft, whatever status the document records, and is published through the
+/// status endpoint like any other trip.
+#[tracing::instrument(name = "Import Itinerary", skip(db, document))]
+pub async fn import_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Json(mut document): Json<ItineraryDocument>,
+) -> Result<impl IntoResponse, ImportItineraryError> {
+    if document.version == 0 || document.version > ITINERARY_DOCUMENT_VERSION {
+        return Err(ImportItineraryError::UnsupportedVersion(document.version));
+    }
+    validate_dates(&document)?;
+    document.itinerary = document
+        .itinerary
+        .validated()
+        .map_err(ImportItineraryError::InvalidItinerary)?;
+    document.flights = validate_all(document.flights, FlightRecord::validated)
+        .map_err(ImportItineraryError::InvalidFlight)?;
+    document.stays = validate_all(document.stays, StayRecord::validated)
+        .map_err(ImportItineraryError::InvalidStay)?;
+    document.activities = validate_all(document.activities, ActivityRecord::validated)
+        .map_err(ImportItineraryError::InvalidActivity)?;
+    document.travel_legs = validate_all(document.travel_legs, TravelLegRecord::validated)
+        .map_err(ImportItineraryError::InvalidTravelLeg)?;
+
+    let itinerary_id = db.import_itinerary(user.id, document).await?;
+    Ok((
+        StatusCode::CREATED,
+        format!("/itineraries/{}", itinerary_id),
+    ))
+}
+
+/// Checks every record of one kind the way the create endpoint of that kind does, so text that
+/// doesn't fit its column is rejected instead of failing the insert.
+fn validate_all<T>(
+    records: Vec<T>,
+    validated: fn(T) -> Result<T, InvalidField>,
+) -> Result<Vec<T>, InvalidField> {
+    records.into_iter().map(validated).collect()
+}
+
+fn validate_dates(document: &ItineraryDocument) -> Result<(), ImportItineraryError> {
+    let itinerary = &document.itinerary;
+    let checks = [
+        (
+            matches!(
+                (itinerary.start_date, itinerary.end_date),
+                (Some(start_date), Some(end_date)) if end_date < start_date
+            ),
+            "itinerary",
+        ),
+        (
+            document
+                .flights
+                .iter()
+                .any(|x| x.arrival_time < x.departure_time),
+            "flight",
+        ),
+        (
+            document.stays.iter().any(|x| x.end_date < x.start_date),
+            "stay",
+        ),
+        (
+            document
+                .activities
+                .iter()
+                .any(|x| x.end_date < x.start_date),
+            "activity",
+        ),
+        (
+            document
+                .travel_legs
+                .iter()
+                .any(|x| x.end_date < x.start_date),
+            "travel leg",
+        ),
+    ];
+
+    match checks.into_iter().find(|(failed, _)| *failed) {
+        Some((_, what)) => Err(ImportItineraryError::EndBeforeStart(what)),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportItineraryError {
+    UnsupportedVersion(u32),
+    EndBeforeStart(&'static str),
+    InvalidItinerary(InvalidField),
+    InvalidFlight(InvalidField),
+    InvalidStay(InvalidField),
+    InvalidActivity(InvalidField),
+    InvalidTravelLeg(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for ImportItineraryError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for ImportItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::UnsupportedVersion(version) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Unsupported document version {}, this server reads versions 1 to {}",
+                    version, ITINERARY_DOCUMENT_VERSION
+                ),
+            )
+                .into_response(),
+            Self::EndBeforeStart(what) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("A {} in the document ends before it starts", what),
+            )
+                .into_response(),
+            Self::InvalidItinerary(error) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("The itinerary in the document is invalid: {}", error),
+            )
+                .into_response(),
+            Self::InvalidFlight(error) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("A flight in the document is invalid: {}", error),
+            )
+                .into_response(),
+            Self::InvalidStay(error) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("A stay in the document is invalid: {}", error),
+            )
+                .into_response(),
+            Self::InvalidActivity(error) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("An activity in the document is invalid: {}", error),
+            )
+                .into_response(),
+            Self::InvalidTravelLeg(error) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("A travel leg in the document is invalid: {}", error),
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait ImportItineraryRepository {
+    /// Inserts every part of the document in a single transaction and returns the new id.
+    async fn import_itinerary(&self, user_id: i32, document: ItineraryDocument) -> Result<i32>;
+}
+
+impl ImportItineraryRepository for PgPool {
+    async fn import_itinerary(&self, user_id: i32, document: ItineraryDocument) -> Result<i32> {
+        let mut transaction = self.begin().await?;
+
+        let itinerary = document.itinerary;
+        let inserted = sqlx::query!(
+            r#"
+                insert into itineraries (user_id, name, status, is_template)
+                values ($1, $2, 'draft', $3)
+                returning itinerary_id
+            "#,
+            user_id,
+            itinerary.name,
+            itinerary.is_template
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        let itinerary_id = inserted.itinerary_id;
+
+        if let Some(start_date) = itinerary.start_date {
+            sqlx::query!(
+                r#"
+                    insert into itinerary_start_date (itinerary_id, start_date)
+                    values ($1, $2)
+                "#,
+                itinerary_id,
+                start_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if let Some(end_date) = itinerary.end_date {
+            sqlx::query!(
+                r#"
+                    insert into itinerary_end_date (itinerary_id, end_date)
+                    values ($1, $2)
+                "#,
+                itinerary_id,
+                end_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for flight in document.flights {
+            let details = flight.details;
+            let created = sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type, position, visibility)
+                        values ($1, 'flight', $23, $24)
+                        returning id
+                    )
+                    insert into flights (
+                        itinerary_item_id,
+                        airline,
+                        confirmation_code,
+                        departure_time,
+                        arrival_time,
+                        departure_timezone,
+                        arrival_timezone,
+                        departure_airport,
+                        arrival_airport,
+                        departure_location,
+                        arrival_location,
+                        flight_number,
+                        operating_carrier,
+                        departure_terminal,
+                        departure_gate,
+                        arrival_terminal,
+                        arrival_gate,
+                        cabin_class,
+                        aircraft_type,
+                        notes
+                    )
+                    select
+                        item.id, $2, $3, $4, $5, $6, $7, $8, $9, point($10, $11), point($12, $13),
+                        $14, $15, $16, $17, $18, $19, $20, $21, $22
+                    from item
+                    returning id
+                "#,
+                itinerary_id,
+                flight.airline,
+                flight.confirmation_code,
+                flight.departure_time,
+                flight.arrival_time,
+                flight.departure_timezone.map(|x| x.name().to_string()),
+                flight.arrival_timezone.map(|x| x.name().to_string()),
+                flight.departure_airport,
+                flight.arrival_airport,
+                flight.departure_location.map(|x| x.longitude),
+                flight.departure_location.map(|x| x.latitude),
+                flight.arrival_location.map(|x| x.longitude),
+                flight.arrival_location.map(|x| x.latitude),
+                details.flight_number,
+                details.operating_carrier,
+                details.departure_terminal,
+                details.departure_gate,
+                details.arrival_terminal,
+                details.arrival_gate,
+                details.cabin_class as Option<CabinClass>,
+                details.aircraft_type,
+                flight.notes,
+                flight.position,
+                flight.visibility as ItemVisibility
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+            insert_passengers(&mut transaction, created.id, &details.passengers).await?;
+        }
+
+        for stay in document.stays {
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type, position, visibility)
+                        values ($1, 'stay', $12, $13)
+                        returning id
+                    )
+                    insert into stays (
+                        itinerary_item_id,
+                        summary,
+                        start_date,
+                        end_date,
+                        check_in_time,
+                        check_out_time,
+                        address,
+                        location,
+                        confirmation_number,
+                        notes
+                    )
+                    select item.id, $2, $3, $4, $5, $6, $7, point($8, $9), $10, $11
+                    from item
+                "#,
+                itinerary_id,
+                stay.summary,
+                stay.start_date,
+                stay.end_date,
+                stay.check_in_time,
+                stay.check_out_time,
+                stay.address,
+                stay.location.map(|x| x.longitude),
+                stay.location.map(|x| x.latitude),
+                stay.confirmation_number,
+                stay.notes,
+                stay.position,
+                stay.visibility as ItemVisibility
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for activity in document.activities {
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type, position, visibility)
+                        values ($1, 'activity', $15, $16)
+                        returning id
+                    )
+                    insert into activities (
+                        itinerary_item_id,
+                        summary,
+                        category,
+                        start_date,
+                        end_date,
+                        start_time,
+                        end_time,
+                        address,
+                        location,
+                        booking_reference,
+                        cost,
+                        cost_currency,
+                        notes
+                    )
+                    select
+                        item.id, $2, $3, $4, $5, $6, $7, $8, point($9, $10), $11, $12::text::numeric,
+                        $13, $14
+                    from item
+                "#,
+                itinerary_id,
+                activity.summary,
+                activity.category as ActivityCategory,
+                activity.start_date,
+                activity.end_date,
+                activity.start_time,
+                activity.end_time,
+                activity.address,
+                activity.location.map(|x| x.longitude),
+                activity.location.map(|x| x.latitude),
+                activity.booking_reference,
+                activity.cost.as_ref().map(|x| &x.amount),
+                activity.cost.as_ref().map(|x| &x.currency),
+                activity.notes,
+                activity.position,
+                activity.visibility as ItemVisibility
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for leg in document.travel_legs {
+            let details = &leg.details;
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type, position, visibility)
+                        values ($1, 'travel_leg', $25, $26)
+                        returning id
+                    )
+                    insert into travel_legs (
+                        itinerary_item_id,
+                        name,
+                        travel_leg_type,
+                        start_date,
+                        end_date,
+                        start_time,
+                        end_time,
+                        start_place,
+                        end_place,
+                        start_location,
+                        end_location,
+                        start_timezone,
+                        end_timezone,
+                        operator,
+                        booking_reference,
+                        service_number,
+                        coach,
+                        seat,
+                        platform,
+                        vehicle,
+                        cabin,
+                        notes
+                    )
+                    select
+                        item.id, $2, $3, $4, $5, $6, $7, $8, $9, point($10, $11), point($12, $13),
+                        $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24
+                    from item
+                "#,
+                itinerary_id,
+                leg.name,
+                leg.travel_leg_type as TravelLegType,
+                leg.start_date,
+                leg.end_date,
+                leg.start_time,
+                leg.end_time,
+                leg.start_place,
+                leg.end_place,
+                leg.start_location.map(|x| x.longitude),
+                leg.start_location.map(|x| x.latitude),
+                leg.end_location.map(|x| x.longitude),
+                leg.end_location.map(|x| x.latitude),
+                leg.start_timezone.map(|x| x.name()),
+                leg.end_timezone.map(|x| x.name()),
+                leg.operator,
+                leg.booking_reference,
+                details.service_number,
+                details.coach,
+                details.seat,
+                details.platform,
+                details.vehicle,
+                details.cabin,
+                leg.notes,
+                leg.position,
+                leg.visibility as ItemVisibility
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for share in document.shares {
+            sqlx::query!(
+                r#"
+                    insert into itinerary_shares (itinerary_id, user_id, share_type, share_message)
+                    select $1, u.user_id, $3, $4
+                    from users u
+                    where u.email = $2
+                        and u.user_id <> $5
+                    on conflict (itinerary_id, user_id) do nothing
+                "#,
+                itinerary_id,
+                share.email,
+                share.share_type as ItineraryShareType,
+                share.share_message,
+                user_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if itinerary.is_template {
+            transaction.record_template_times(itinerary_id).await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(itinerary_id)
+    }
+}
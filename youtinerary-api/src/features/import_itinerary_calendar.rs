@@ -0,0 +1,897 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::extract::multipart::MultipartError;
+use axum::extract::{Multipart, Path, Query, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::access::{check_can_edit, check_can_view, AccessError};
+use crate::ical::{parse_events, CalendarEvent, EventTime};
+use crate::timeline::Coordinates;
+use crate::validation::{Issue, IssuesRepository};
+use crate::{CalendarEventItemType, User};
+
+/// Creates or updates itinerary items from the VEVENTs of an uploaded `.ics` file.
+///
+/// The multipart body carries the calendar in a `file` field and optionally a `mapping` field,
+/// a JSON object from event UID to `flight`, `stay`, `activity` or `skip`. The `*` key applies
+/// to every event without its own entry, and events that are not mapped at all are classified
+/// from their text. With `?preview=true` nothing is written, and viewers of the itinerary may
+/// preview an import too.
+#[tracing::instrument(name = "Import Itinerary Calendar", skip(db, multipart))]
+pub async fn import_itinerary_calendar(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Query(query): Query<ImportCalendarQuery>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ImportCalendarError> {
+    let upload = read_upload(multipart).await?;
+    let events = parse_events(&upload.calendar)
+        .map_err(|error| ImportCalendarError::InvalidCalendar(error.to_string()))?;
+    let planned = plan_events(events, &upload.mapping);
+
+    let (events, issues) = if query.preview {
+        (
+            db.preview_calendar_events(user.id, itinerary_id, planned)
+                .await?,
+            None,
+        )
+    } else {
+        let events = db
+            .import_calendar_events(user.id, itinerary_id, planned)
+            .await?;
+        (events, Some(db.get_issues(itinerary_id).await?))
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(CalendarImportViewModel {
+            preview: query.preview,
+            events,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportCalendarQuery {
+    #[serde(default)]
+    preview: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum EventMapping {
+    Flight,
+    Stay,
+    Activity,
+    Skip,
+}
+
+struct Upload {
+    calendar: String,
+    mapping: HashMap<String, EventMapping>,
+}
+
+async fn read_upload(mut multipart: Multipart) -> Result<Upload, ImportCalendarError> {
+    let mut calendar = None;
+    let mut mapping = HashMap::new();
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("file") => {
+                let bytes = field.bytes().await?;
+                calendar = Some(String::from_utf8(bytes.to_vec()).map_err(|_| {
+                    ImportCalendarError::InvalidCalendar("The file is not valid UTF-8".into())
+                })?);
+            }
+            Some("mapping") => {
+                let text = field.text().await?;
+                mapping = serde_json::from_str(&text)
+                    .map_err(|error| ImportCalendarError::InvalidMapping(error.to_string()))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Upload {
+        calendar: calendar.ok_or(ImportCalendarError::MissingFile)?,
+        mapping,
+    })
+}
+
+#[derive(Serialize)]
+struct CalendarImportViewModel {
+    preview: bool,
+    events: Vec<EventOutcome>,
+    /// Issues of the itinerary once the import is written, left out of previews.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issues: Option<Vec<Issue>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ImportAction {
+    Create,
+    Update,
+    Skip,
+}
+
+#[derive(Serialize)]
+struct EventOutcome {
+    uid: String,
+    summary: String,
+    action: ImportAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<PlannedItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NewFlight {
+    airline: String,
+    confirmation_code: String,
+    departure_time: DateTime<Utc>,
+    arrival_time: DateTime<Utc>,
+    departure_timezone: Option<String>,
+    arrival_timezone: Option<String>,
+    notes: String,
+}
+
+#[derive(Debug, Serialize)]
+struct NewDatedItem {
+    summary: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    location: Option<Coordinates>,
+    notes: String,
+}
+
+/// What an event turns into, shaped like the entries of the itinerary timeline.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PlannedItem {
+    Flight(NewFlight),
+    Stay(NewDatedItem),
+    Activity(NewDatedItem),
+}
+
+impl PlannedItem {
+    fn item_type(&self) -> CalendarEventItemType {
+        match self {
+            Self::Flight(_) => CalendarEventItemType::Flight,
+            Self::Stay(_) => CalendarEventItemType::Stay,
+            Self::Activity(_) => CalendarEventItemType::Activity,
+        }
+    }
+}
+
+struct PlannedEvent {
+    uid: String,
+    summary: String,
+    warnings: Vec<String>,
+    /// The item to write, or why the event is skipped.
+    item: Result<PlannedItem, String>,
+}
+
+impl PlannedEvent {
+    fn outcome(self, action: ImportAction, item_id: Option<i32>) -> EventOutcome {
+        let (item, reason) = match self.item {
+            Ok(item) => (Some(item), None),
+            Err(reason) => (None, Some(reason)),
+        };
+        EventOutcome {
+            uid: self.uid,
+            summary: self.summary,
+            action,
+            item_id,
+            item,
+            reason,
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn plan_events(
+    events: Vec<CalendarEvent>,
+    mapping: &HashMap<String, EventMapping>,
+) -> Vec<PlannedEvent> {
+    let mut seen = HashSet::new();
+    events
+        .into_iter()
+        .map(|event| {
+            let mut warnings = event.warnings.clone();
+            if event.recurring {
+                warnings.push("The event repeats, only its first occurrence is imported".into());
+            }
+
+            let item = if event.is_override {
+                Err("Changes one occurrence of a repeating event, which is not imported".into())
+            } else if !seen.insert(event.uid.clone()) {
+                Err("Another event in the file has the same UID".into())
+            } else {
+                let item_type = match mapping.get(&event.uid).or_else(|| mapping.get("*")) {
+                    Some(EventMapping::Flight) => Some(CalendarEventItemType::Flight),
+                    Some(EventMapping::Stay) => Some(CalendarEventItemType::Stay),
+                    Some(EventMapping::Activity) => Some(CalendarEventItemType::Activity),
+                    Some(EventMapping::Skip) => None,
+                    None => Some(guess_item_type(&event)),
+                };
+                match item_type {
+                    Some(item_type) => to_item(&event, item_type),
+                    None => Err("Skipped by the mapping".into()),
+                }
+            };
+
+            PlannedEvent {
+                uid: event.uid,
+                summary: event.summary,
+                warnings,
+                item,
+            }
+        })
+        .collect()
+}
+
+const FLIGHT_WORDS: [&str; 3] = ["flight", "boarding pass", "\u{2708}"];
+const STAY_WORDS: [&str; 10] = [
+    "hotel",
+    "hostel",
+    "airbnb",
+    "motel",
+    "resort",
+    "lodging",
+    "accommodation",
+    "check-in",
+    "check in",
+    "stay at",
+];
+
+/// Classifies an event that the mapping says nothing about.
+///
+/// Timed events that mention a flight or whose summary contains a flight number such as
+/// `AF 1234` are flights, events that mention a hotel or similar are stays, and everything
+/// else is an activity.
+fn guess_item_type(event: &CalendarEvent) -> CalendarEventItemType {
+    let text = format!(
+        "{} {} {}",
+        event.summary,
+        event.categories.join(" "),
+        event.description
+    )
+    .to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| text.contains(word));
+
+    let timed = matches!(event.start, EventTime::DateTime { .. });
+    if timed && (mentions(&FLIGHT_WORDS) || flight_carrier(&event.summary).is_some()) {
+        CalendarEventItemType::Flight
+    } else if mentions(&STAY_WORDS) {
+        CalendarEventItemType::Stay
+    } else {
+        CalendarEventItemType::Activity
+    }
+}
+
+/// The carrier code of the first flight number in `text`, either `AF1234` or `AF 1234`.
+fn flight_carrier(text: &str) -> Option<String> {
+    let is_carrier = |code: &str| {
+        code.len() == 2
+            && code
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && code.chars().any(|c| c.is_ascii_uppercase())
+    };
+    let is_number = |number: &str| {
+        (1..=4).contains(&number.len()) && number.chars().all(|c| c.is_ascii_digit())
+    };
+
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ':' || c == ',' || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.iter().enumerate().find_map(|(index, word)| {
+        if word.is_char_boundary(2) && is_carrier(&word[..2]) && is_number(&word[2..]) {
+            return Some(word[..2].to_string());
+        }
+        let next = words.get(index + 1)?;
+        (is_carrier(word) && is_number(next)).then(|| word.to_string())
+    })
+}
+
+/// The value after a line such as `Confirmation: ABC123` or `Booking reference ABC123`.
+fn confirmation_code(description: &str) -> Option<String> {
+    const LABELS: [&str; 5] = [
+        "confirmation code",
+        "confirmation",
+        "booking reference",
+        "record locator",
+        "pnr",
+    ];
+    description.lines().find_map(|line| {
+        // ASCII lowercasing keeps byte offsets, so they can index the original line.
+        let lower = line.to_ascii_lowercase();
+        let label = LABELS.iter().find(|label| lower.contains(*label))?;
+        let after = &line[lower.find(label)? + label.len()..];
+        let code = after
+            .trim_start_matches(|c: char| c.is_whitespace() || c == ':' || c == '#')
+            .split_whitespace()
+            .next()?;
+        Some(truncate(code, 50))
+    })
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    value.chars().take(max_chars).collect()
+}
+
+fn notes_for(event: &CalendarEvent) -> String {
+    let notes = match &event.location {
+        Some(location) if event.description.is_empty() => location.clone(),
+        Some(location) => format!("{}\n\n{}", location, event.description),
+        None => event.description.clone(),
+    };
+    truncate(notes.trim(), 255)
+}
+
+fn to_item(event: &CalendarEvent, item_type: CalendarEventItemType) -> Result<PlannedItem, String> {
+    let summary = match event.summary.trim() {
+        "" => "Untitled event".to_string(),
+        summary => truncate(summary, 255),
+    };
+
+    match item_type {
+        CalendarEventItemType::Flight => {
+            let (
+                EventTime::DateTime {
+                    instant: departure_time,
+                    timezone: departure_timezone,
+                },
+                EventTime::DateTime {
+                    instant: arrival_time,
+                    timezone: arrival_timezone,
+                },
+            ) = (event.start, event.end)
+            else {
+                return Err("A flight needs a start and end time, the event only has dates".into());
+            };
+
+            Ok(PlannedItem::Flight(NewFlight {
+                airline: flight_carrier(&event.summary).unwrap_or(summary),
+                confirmation_code: confirmation_code(&event.description).unwrap_or_default(),
+                departure_time,
+                arrival_time: arrival_time.max(departure_time),
+                departure_timezone: departure_timezone.map(|x| x.name().to_string()),
+                arrival_timezone: arrival_timezone.map(|x| x.name().to_string()),
+                notes: notes_for(event),
+            }))
+        }
+        CalendarEventItemType::Stay | CalendarEventItemType::Activity => {
+            let start_date = event.start.local_date();
+            // All-day events end on the day after their last day.
+            let end_date = match event.end {
+                EventTime::Date(date) => date - Duration::days(1),
+                end => end.local_date(),
+            };
+            let item = NewDatedItem {
+                summary,
+                start_date,
+                end_date: end_date.max(start_date),
+                location: event.geo,
+                notes: notes_for(event),
+            };
+
+            Ok(match item_type {
+                CalendarEventItemType::Stay => PlannedItem::Stay(item),
+                _ => PlannedItem::Activity(item),
+            })
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportCalendarError {
+    Access(AccessError),
+    MissingFile,
+    InvalidUpload(MultipartError),
+    InvalidCalendar(String),
+    InvalidMapping(String),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for ImportCalendarError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<MultipartError> for ImportCalendarError {
+    fn from(value: MultipartError) -> Self {
+        Self::InvalidUpload(value)
+    }
+}
+
+impl From<anyhow::Error> for ImportCalendarError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl From<sqlx::Error> for ImportCalendarError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for ImportCalendarError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::MissingFile => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Upload the calendar in a multipart field named file",
+            )
+                .into_response(),
+            Self::InvalidUpload(error) => error.into_response(),
+            Self::InvalidCalendar(reason) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, reason).into_response()
+            }
+            Self::InvalidMapping(reason) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("The mapping is not valid: {}", reason),
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait CalendarImportRepository {
+    /// Checks the user can view the itinerary, then tells which events would create items and
+    /// which would update ones from an earlier import, without writing anything.
+    async fn preview_calendar_events(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        events: Vec<PlannedEvent>,
+    ) -> Result<Vec<EventOutcome>, ImportCalendarError>;
+
+    /// Checks the user can edit the itinerary and writes the events in one transaction. An event
+    /// whose UID was imported before updates the item it created then, or replaces it when the
+    /// event now maps to another kind.
+    async fn import_calendar_events(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        events: Vec<PlannedEvent>,
+    ) -> Result<Vec<EventOutcome>, ImportCalendarError>;
+}
+
+impl CalendarImportRepository for PgPool {
+    async fn preview_calendar_events(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        events: Vec<PlannedEvent>,
+    ) -> Result<Vec<EventOutcome>, ImportCalendarError> {
+        let mut connection = self.acquire().await?;
+        check_can_view(&mut connection, user_id, itinerary_id).await?;
+
+        let imported: HashMap<String, i32> = sqlx::query!(
+            r#"
+                select uid, item_id
+                from itinerary_calendar_events
+                where itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .fetch_all(&mut *connection)
+        .await?
+        .into_iter()
+        .map(|x| (x.uid, x.item_id))
+        .collect();
+
+        Ok(events
+            .into_iter()
+            .map(|event| match (&event.item, imported.get(&event.uid)) {
+                (Err(_), _) => event.outcome(ImportAction::Skip, None),
+                (Ok(_), Some(item_id)) => event.outcome(ImportAction::Update, Some(*item_id)),
+                (Ok(_), None) => event.outcome(ImportAction::Create, None),
+            })
+            .collect())
+    }
+
+    async fn import_calendar_events(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        events: Vec<PlannedEvent>,
+    ) -> Result<Vec<EventOutcome>, ImportCalendarError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        let mut outcomes = Vec::with_capacity(events.len());
+        for event in events {
+            let Ok(item) = &event.item else {
+                outcomes.push(event.outcome(ImportAction::Skip, None));
+                continue;
+            };
+
+            let imported = sqlx::query!(
+                r#"
+                    select
+                        item_type as "item_type: CalendarEventItemType",
+                        item_id
+                    from itinerary_calendar_events
+                    where itinerary_id = $1
+                        and uid = $2
+                    for update
+                "#,
+                itinerary_id,
+                event.uid
+            )
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+            let updated = match imported {
+                Some(imported) if imported.item_type == item.item_type() => {
+                    update_item(&mut transaction, itinerary_id, imported.item_id, item)
+                        .await?
+                        .then_some(imported.item_id)
+                }
+                Some(imported) => {
+                    delete_item(
+                        &mut transaction,
+                        itinerary_id,
+                        imported.item_type,
+                        imported.item_id,
+                    )
+                    .await?;
+                    None
+                }
+                None => None,
+            };
+
+            let (action, item_id) = match updated {
+                Some(item_id) => (ImportAction::Update, item_id),
+                None => (
+                    ImportAction::Create,
+                    insert_item(&mut transaction, itinerary_id, item).await?,
+                ),
+            };
+
+            sqlx::query!(
+                r#"
+                    insert into itinerary_calendar_events (itinerary_id, uid, item_type, item_id)
+                    values ($1, $2, $3, $4)
+                    on conflict (itinerary_id, uid)
+                    do update set item_type = excluded.item_type,
+                        item_id = excluded.item_id,
+                        imported_at = now()
+                "#,
+                itinerary_id,
+                event.uid,
+                item.item_type() as CalendarEventItemType,
+                item_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+
+            outcomes.push(event.outcome(action, Some(item_id)));
+        }
+
+        transaction.commit().await?;
+
+        Ok(outcomes)
+    }
+}
+
+async fn insert_item(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    item: &PlannedItem,
+) -> Result<i32> {
+    let inserted = match item {
+        PlannedItem::Flight(flight) => {
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type)
+                        values ($1, 'flight')
+                        returning id
+                    )
+                    insert into flights (
+                        itinerary_item_id,
+                        airline,
+                        confirmation_code,
+                        departure_time,
+                        arrival_time,
+                        departure_timezone,
+                        arrival_timezone,
+                        notes
+                    )
+                    select item.id, $2, $3, $4, $5, $6, $7, $8
+                    from item
+                    returning id
+                "#,
+                itinerary_id,
+                flight.airline,
+                flight.confirmation_code,
+                flight.departure_time,
+                flight.arrival_time,
+                flight.departure_timezone,
+                flight.arrival_timezone,
+                flight.notes
+            )
+            .fetch_one(&mut *connection)
+            .await?
+            .id
+        }
+        PlannedItem::Stay(stay) => {
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type)
+                        values ($1, 'stay')
+                        returning id
+                    )
+                    insert into stays (
+                        itinerary_item_id,
+                        summary,
+                        start_date,
+                        end_date,
+                        location,
+                        notes
+                    )
+                    select item.id, $2, $3, $4, point($5, $6), $7
+                    from item
+                    returning id
+                "#,
+                itinerary_id,
+                stay.summary,
+                stay.start_date,
+                stay.end_date,
+                stay.location.map(|x| x.longitude),
+                stay.location.map(|x| x.latitude),
+                stay.notes
+            )
+            .fetch_one(&mut *connection)
+            .await?
+            .id
+        }
+        PlannedItem::Activity(activity) => {
+            sqlx::query!(
+                r#"
+                    with item as (
+                        insert into itinerary_items (itinerary_id, item_type)
+                        values ($1, 'activity')
+                        returning id
+                    )
+                    insert into activities (
+                        itinerary_item_id,
+                        summary,
+                        start_date,
+                        end_date,
+                        location,
+                        notes
+                    )
+                    select item.id, $2, $3, $4, point($5, $6), $7
+                    from item
+                    returning id
+                "#,
+                itinerary_id,
+                activity.summary,
+                activity.start_date,
+                activity.end_date,
+                activity.location.map(|x| x.longitude),
+                activity.location.map(|x| x.latitude),
+                activity.notes
+            )
+            .fetch_one(&mut *connection)
+            .await?
+            .id
+        }
+    };
+
+    Ok(inserted)
+}
+
+/// Overwrites an item created by an earlier import. Returns `false` when the item has been
+/// removed from the itinerary since.
+async fn update_item(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    item_id: i32,
+    item: &PlannedItem,
+) -> Result<bool> {
+    let updated = match item {
+        PlannedItem::Flight(flight) => {
+            sqlx::query!(
+                r#"
+                    update flights
+                    set airline = $3,
+                        confirmation_code = $4,
+                        departure_time = $5,
+                        arrival_time = $6,
+                        departure_timezone = $7,
+                        arrival_timezone = $8,
+                        notes = $9
+                    where id = $2
+                        and itinerary_item_id in (
+                            select id
+                            from itinerary_items
+                            where itinerary_id = $1
+                        )
+                "#,
+                itinerary_id,
+                item_id,
+                flight.airline,
+                flight.confirmation_code,
+                flight.departure_time,
+                flight.arrival_time,
+                flight.departure_timezone,
+                flight.arrival_timezone,
+                flight.notes
+            )
+            .execute(&mut *connection)
+            .await?
+        }
+        PlannedItem::Stay(stay) => {
+            sqlx::query!(
+                r#"
+                    update stays
+                    set summary = $3,
+                        start_date = $4,
+                        end_date = $5,
+                        location = point($6, $7),
+                        notes = $8
+                    where id = $2
+                        and itinerary_item_id in (
+                            select id
+                            from itinerary_items
+                            where itinerary_id = $1
+                        )
+                "#,
+                itinerary_id,
+                item_id,
+                stay.summary,
+                stay.start_date,
+                stay.end_date,
+                stay.location.map(|x| x.longitude),
+                stay.location.map(|x| x.latitude),
+                stay.notes
+            )
+            .execute(&mut *connection)
+            .await?
+        }
+        PlannedItem::Activity(activity) => {
+            sqlx::query!(
+                r#"
+                    update activities
+                    set summary = $3,
+                        start_date = $4,
+                        end_date = $5,
+                        location = point($6, $7),
+                        notes = $8
+                    where id = $2
+                        and itinerary_item_id in (
+                            select id
+                            from itinerary_items
+                            where itinerary_id = $1
+                        )
+                "#,
+                itinerary_id,
+                item_id,
+                activity.summary,
+                activity.start_date,
+                activity.end_date,
+                activity.location.map(|x| x.longitude),
+                activity.location.map(|x| x.latitude),
+                activity.notes
+            )
+            .execute(&mut *connection)
+            .await?
+        }
+    };
+
+    Ok(updated.rows_affected() > 0)
+}
+
+async fn delete_item(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    item_type: CalendarEventItemType,
+    item_id: i32,
+) -> Result<()> {
+    match item_type {
+        CalendarEventItemType::Flight => {
+            sqlx::query!(
+                r#"
+                    delete from itinerary_items i
+                    using flights f
+                    where f.itinerary_item_id = i.id
+                        and i.itinerary_id = $1
+                        and f.id = $2
+                "#,
+                itinerary_id,
+                item_id
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+        CalendarEventItemType::Stay => {
+            sqlx::query!(
+                r#"
+                    delete from itinerary_items i
+                    using stays s
+                    where s.itinerary_item_id = i.id
+                        and i.itinerary_id = $1
+                        and s.id = $2
+                "#,
+                itinerary_id,
+                item_id
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+        CalendarEventItemType::Activity => {
+            sqlx::query!(
+                r#"
+                    delete from itinerary_items i
+                    using activities a
+                    where a.itinerary_item_id = i.id
+                        and i.itinerary_id = $1
+                        and a.id = $2
+                "#,
+                itinerary_id,
+                item_id
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confirmation_code_follows_its_label() {
+        assert_eq!(
+            confirmation_code("Flight to Lisbon\nConfirmation code: XK42PL"),
+            Some("XK42PL".to_string())
+        );
+        assert_eq!(
+            confirmation_code("Record locator #AB12CD seat 14C"),
+            Some("AB12CD".to_string())
+        );
+        assert_eq!(confirmation_code("Window seat please"), None);
+    }
+
+    #[test]
+    fn confirmation_code_handles_non_ascii_lines() {
+        assert_eq!(confirmation_code("İstanbul İİİ PNR"), None);
+        assert_eq!(
+            confirmation_code("İstanbul İİİ PNR: QW7RTY"),
+            Some("QW7RTY".to_string())
+        );
+    }
+}
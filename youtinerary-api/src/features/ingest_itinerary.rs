@@ -0,0 +1,288 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::create_flight::{CreateFlightRequest, InsertFlight, InsertFlightRepository};
+use super::create_stay::{CreateStayRequest, InsertStay, InsertStayRepository};
+use crate::access::{check_can_edit, AccessError};
+use crate::flights::FlightDetails;
+use crate::ingest::{parse_bookings, read_message, Booking};
+use crate::validation::{Issue, IssuesRepository};
+use crate::User;
+
+/// Reads flight and stay bookings out of a confirmation email, sent as the raw `.eml` file or
+/// as text pasted from it, and proposes them as items of an itinerary the user owns or edits.
+///
+/// Each proposal carries the body its create endpoint takes, so it can be confirmed as is or
+/// after fixing what the parser got wrong. With `?create=true` the proposals that are valid are
+/// created right away, in one transaction.
+#[tracing::instrument(name = "Ingest Itinerary", skip(db, body))]
+pub async fn ingest_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Query(query): Query<IngestQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, IngestError> {
+    let message = read_message(&String::from_utf8_lossy(&body));
+    if message.text.trim().is_empty() && message.html.is_empty() {
+        return Err(IngestError::EmptyMessage);
+    }
+
+    let (parser, bookings) = match parse_bookings(&message) {
+        Some((parser, bookings)) => (Some(parser), bookings),
+        None => (None, Vec::new()),
+    };
+    let planned = bookings
+        .into_iter()
+        .map(|booking| plan(user.id, itinerary_id, booking))
+        .collect();
+
+    let (proposals, issues) = if query.create {
+        let proposals = db.create_proposals(user.id, itinerary_id, planned).await?;
+        (proposals, Some(db.get_issues(itinerary_id).await?))
+    } else {
+        db.check_can_ingest(user.id, itinerary_id).await?;
+        (planned.into_iter().map(|x| x.proposal).collect(), None)
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(IngestViewModel {
+            created: query.create,
+            parser,
+            proposals,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IngestQuery {
+    #[serde(default)]
+    create: bool,
+}
+
+#[derive(Serialize)]
+struct IngestViewModel {
+    created: bool,
+    /// Parser the proposals come from, missing when nothing looked like a booking.
+    parser: Option<&'static str>,
+    proposals: Vec<Proposal>,
+    /// Issues of the itinerary once the proposals are created, left out otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issues: Option<Vec<Issue>>,
+}
+
+#[derive(Serialize)]
+struct Proposal {
+    #[serde(flatten)]
+    item: ProposedItem,
+    /// Where the item was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    /// Why the proposal can't be created as it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+/// A booking as the body of the create endpoint for its kind.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "item", rename_all = "snake_case")]
+pub enum ProposedItem {
+    Flight(Box<CreateFlightRequest>),
+    Stay(CreateStayRequest),
+}
+
+impl From<Booking> for ProposedItem {
+    fn from(value: Booking) -> Self {
+        match value {
+            Booking::Flight(flight) => Self::Flight(Box::new(CreateFlightRequest {
+                airline: flight.airline,
+                confirmation_code: flight.confirmation_code,
+                departure_time: flight.departure_time.and_then(|x| x.instant()),
+                local_departure_time: flight.departure_time.and_then(|x| x.local()),
+                arrival_time: flight.arrival_time.and_then(|x| x.instant()),
+                local_arrival_time: flight.arrival_time.and_then(|x| x.local()),
+                departure_timezone: None,
+                arrival_timezone: None,
+                departure_airport: flight.departure_airport,
+                arrival_airport: flight.arrival_airport,
+                details: FlightDetails {
+                    flight_number: flight.flight_number,
+                    departure_terminal: flight.departure_terminal,
+                    departure_gate: flight.departure_gate,
+                    arrival_terminal: flight.arrival_terminal,
+                    arrival_gate: flight.arrival_gate,
+                    passengers: flight.passengers,
+                    ..FlightDetails::default()
+                },
+                notes: String::new(),
+            })),
+            Booking::Stay(stay) => Self::Stay(CreateStayRequest { stay }),
+        }
+    }
+}
+
+impl ProposedItem {
+    /// Validates the item like its create endpoint does, or tells why it can't be created.
+    pub fn to_insert(&self, user_id: i32, itinerary_id: i32) -> Result<Insert, String> {
+        match self {
+            Self::Flight(request) => {
+                InsertFlight::try_from((user_id, itinerary_id, request.as_ref().clone()))
+                    .map(|flight| Insert::Flight(Box::new(flight)))
+                    .map_err(|error| error.to_string())
+            }
+            Self::Stay(request) => InsertStay::try_from((user_id, itinerary_id, request.clone()))
+                .map(Insert::Stay)
+                .map_err(|error| error.to_string()),
+        }
+    }
+}
+
+pub enum Insert {
+    Flight(Box<InsertFlight>),
+    Stay(InsertStay),
+}
+
+struct PlannedProposal {
+    proposal: Proposal,
+    insert: Option<Insert>,
+}
+
+/// Turns a booking into the request a user would send for it, validated like one.
+fn plan(user_id: i32, itinerary_id: i32, booking: Booking) -> PlannedProposal {
+    let item = ProposedItem::from(booking);
+    let (insert, reason) = match item.to_insert(user_id, itinerary_id) {
+        Ok(insert) => (Some(insert), None),
+        Err(reason) => (None, Some(reason)),
+    };
+    PlannedProposal {
+        proposal: Proposal {
+            item,
+            location: None,
+            reason,
+        },
+        insert,
+    }
+}
+
+#[derive(Debug)]
+pub enum IngestError {
+    Access(AccessError),
+    EmptyMessage,
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for IngestError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<sqlx::Error> for IngestError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for IngestError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::EmptyMessage => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Send the email or the text of it as the request body",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait IngestRepository {
+    /// Checks the user could create the proposals, without writing anything.
+    async fn check_can_ingest(&self, user_id: i32, itinerary_id: i32) -> Result<(), IngestError>;
+
+    /// Creates the valid proposals in one transaction and tells where each one went.
+    async fn create_proposals(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        proposals: Vec<PlannedProposal>,
+    ) -> Result<Vec<Proposal>, IngestError>;
+}
+
+impl IngestRepository for PgPool {
+    async fn check_can_ingest(&self, user_id: i32, itinerary_id: i32) -> Result<(), IngestError> {
+        let mut connection = self.acquire().await?;
+        check_can_edit(&mut connection, user_id, itinerary_id).await?;
+
+        Ok(())
+    }
+
+    async fn create_proposals(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        proposals: Vec<PlannedProposal>,
+    ) -> Result<Vec<Proposal>, IngestError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        let mut created = Vec::with_capacity(proposals.len());
+        for PlannedProposal {
+            mut proposal,
+            insert,
+        } in proposals
+        {
+            if let Some(insert) = insert {
+                proposal.location = Some(transaction.insert_proposal(itinerary_id, insert).await?);
+            }
+            created.push(proposal);
+        }
+
+        transaction.commit().await?;
+
+        Ok(created)
+    }
+}
+
+pub trait InsertProposalRepository {
+    /// Creates the item and returns where it is.
+    async fn insert_proposal(&mut self, itinerary_id: i32, insert: Insert) -> Result<String>;
+}
+
+impl InsertProposalRepository for Transaction<'_, Postgres> {
+    async fn insert_proposal(&mut self, itinerary_id: i32, insert: Insert) -> Result<String> {
+        Ok(match insert {
+            Insert::Flight(flight) => format!(
+                "/itineraries/{}/flights/{}",
+                itinerary_id,
+                self.insert_flight(*flight).await?
+            ),
+            Insert::Stay(stay) => format!(
+                "/itineraries/{}/stays/{}",
+                itinerary_id,
+                self.insert_stay(stay).await?
+            ),
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use super::clone_itinerary::{CloneItineraryRepository, CopyItinerary};
+use crate::access::{find_role, ItineraryRole};
+use crate::{ItemVisibility, User};
+
+#[tracing::instrument(name = "Instantiate Template", skip(db))]
+pub async fn instantiate_template(
+    State(db): State<PgPool>,
+    user: User,
+    Path(template_id): Path<i32>,
+    Json(instantiate_template): Json<InstantiateTemplateRequest>,
+) -> Result<impl IntoResponse, InstantiateTemplateError> {
+    let template = db
+        .get_template(user.id, template_id)
+        .await?
+        .ok_or(InstantiateTemplateError::TemplateNotFound)?;
+    // Items without recorded template times, added after the template was saved, are shifted
+    // with the template's first day instead.
+    let offset_days = template
+        .start_date
+        .map(|start_date| (instantiate_template.start_date - start_date).num_days() as i32)
+        .unwrap_or(0);
+
+    let mut transaction = db.begin().await?;
+    let itinerary_id = transaction
+        .clone_itinerary(CopyItinerary {
+            source_itinerary_id: template_id,
+            user_id: user.id,
+            name: instantiate_template.name.unwrap_or(template.name),
+            offset_days,
+            is_template: false,
+            template_start_date: Some(instantiate_template.start_date),
+            copy_editor_items: template.role.can_see(ItemVisibility::Editors),
+        })
+        .await?;
+    transaction.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        format!("/itineraries/{}", itinerary_id),
+    ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    name: Option<String>,
+    /// Date the first day of the template lands on.
+    start_date: NaiveDate,
+}
+
+#[derive(Debug)]
+pub enum InstantiateTemplateError {
+    TemplateNotFound,
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for InstantiateTemplateError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for InstantiateTemplateError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::TemplateNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+struct Template {
+    name: String,
+    start_date: Option<NaiveDate>,
+    role: ItineraryRole,
+}
+
+trait TemplateRepository {
+    /// Name and first day of a live template the user owns or that was shared with them, and
+    /// their role on it.
+    async fn get_template(&self, user_id: i32, template_id: i32) -> Result<Option<Template>>;
+}
+
+impl TemplateRepository for PgPool {
+    async fn get_template(&self, user_id: i32, template_id: i32) -> Result<Option<Template>> {
+        let mut connection = self.acquire().await?;
+        let Some(role) = find_role(&mut connection, user_id, template_id).await? else {
+            return Ok(None);
+        };
+
+        let template = sqlx::query!(
+            r#"
+                select i.name, d.start_date
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                where i.itinerary_id = $1
+                    and i.is_template
+                    and i.status <> 'archived'
+            "#,
+            template_id
+        )
+        .fetch_optional(&mut *connection)
+        .await?;
+
+        Ok(template.map(|x| Template {
+            name: x.name,
+            start_date: x.start_date,
+            role,
+        }))
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use sqlx::types::Json;
+use sqlx::{Connection, PgConnection, PgPool};
+use tokio::sync::Notify;
+use tokio::time::timeout;
+
+use super::ingest_itinerary::ProposedItem;
+use crate::ingest::{parse_bookings, read_message};
+use crate::InboxMessageStatus;
+
+/// How long the worker waits before looking for messages queued by other instances.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Parses the messages the SMTP receiver queues into bookings of their user's inbox, as they come
+/// in. Whatever was queued while the service was down is parsed when it starts.
+pub async fn process_inbox(db: PgPool, queued: Arc<Notify>) {
+    loop {
+        match db.parse_next_message().await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(error) => tracing::error!("failed to parse an inbox message: {:?}", error),
+        }
+        let _ = timeout(POLL_INTERVAL, queued.notified()).await;
+    }
+}
+
+trait ProcessInboxRepository {
+    /// Parses the oldest queued message, returning `false` when there is none. A message that
+    /// can't be stored once parsed is marked as failed, so it isn't picked up again.
+    async fn parse_next_message(&self) -> Result<bool>;
+}
+
+impl ProcessInboxRepository for PgPool {
+    async fn parse_next_message(&self) -> Result<bool> {
+        let mut transaction = self.begin().await?;
+
+        // Skipping locked rows lets every instance of the service work through the queue.
+        let Some(queued) = sqlx::query!(
+            r#"
+                select id, raw
+                from inbox_messages
+                where status = 'queued'
+                order by received_at
+                limit 1
+                for update skip locked
+            "#
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        else {
+            return Ok(false);
+        };
+
+        // The savepoint keeps the message locked while a failed attempt is rolled back.
+        let mut parsing = transaction.begin().await?;
+        match store_parsed(&mut parsing, queued.id, &queued.raw).await {
+            Ok(()) => parsing.commit().await?,
+            Err(error) => {
+                parsing.rollback().await?;
+                tracing::warn!("failed to store inbox message {}: {:?}", queued.id, error);
+                sqlx::query!(
+                    r#"
+                        update inbox_messages
+                        set status = 'failed',
+                            error = $2,
+                            parsed_at = now()
+                        where id = $1
+                    "#,
+                    queued.id,
+                    error.to_string()
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(true)
+    }
+}
+
+/// Reads a queued message and stores what was found in it.
+async fn store_parsed(connection: &mut PgConnection, message_id: i32, raw: &str) -> Result<()> {
+    let message = read_message(raw);
+    let (status, parser, bookings, error) = if message.text.trim().is_empty()
+        && message.html.is_empty()
+    {
+        let error = Some("The message has no text".to_string());
+        (InboxMessageStatus::Failed, None, Vec::new(), error)
+    } else {
+        match parse_bookings(&message) {
+            Some((parser, bookings)) => (InboxMessageStatus::Parsed, Some(parser), bookings, None),
+            None => (InboxMessageStatus::Parsed, None, Vec::new(), None),
+        }
+    };
+
+    sqlx::query!(
+        r#"
+            update inbox_messages
+            set status = $2,
+                subject = $3,
+                parser = $4,
+                error = $5,
+                parsed_at = now()
+            where id = $1
+        "#,
+        message_id,
+        status as InboxMessageStatus,
+        message.subject,
+        parser,
+        error
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    for booking in bookings {
+        sqlx::query!(
+            r#"
+                insert into inbox_bookings (message_id, proposal)
+                values ($1, $2)
+            "#,
+            message_id,
+            Json(ProposedItem::from(booking)) as _
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    Ok(())
+}
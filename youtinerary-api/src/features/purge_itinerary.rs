@@ -0,0 +1,103 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use sqlx::PgPool;
+
+use crate::{ItineraryStatus, User};
+
+/// Permanently removes an archived itinerary and everything linked to it.
+#[tracing::instrument(name = "Purge Itinerary", skip(db))]
+pub async fn purge_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, PurgeItineraryError> {
+    db.purge_itinerary(user.id, itinerary_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug)]
+pub enum PurgeItineraryError {
+    ItineraryNotFound,
+    NotArchived,
+    Unexpected(anyhow::Error),
+}
+
+impl From<sqlx::Error> for PurgeItineraryError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for PurgeItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::NotArchived => (
+                StatusCode::CONFLICT,
+                "Archive the itinerary before purging it",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait PurgeItineraryRespository {
+    async fn purge_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<(), PurgeItineraryError>;
+}
+
+impl PurgeItineraryRespository for PgPool {
+    async fn purge_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<(), PurgeItineraryError> {
+        let mut transaction = self.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+                select status as "status: ItineraryStatus"
+                from itineraries
+                where user_id = $1
+                    and itinerary_id = $2
+                for update
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(PurgeItineraryError::ItineraryNotFound)?;
+
+        if current.status != ItineraryStatus::Archived {
+            return Err(PurgeItineraryError::NotArchived);
+        }
+
+        // Every item cascades with the itinerary, and the booking behind it with the item.
+        sqlx::query!(
+            r#"
+                delete from itineraries
+                where itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use sqlx::PgPool;
+
+use crate::{ItineraryStatus, User};
+
+/// Brings an archived itinerary back in the status it had when it was archived.
+#[tracing::instrument(name = "Restore Itinerary", skip(db))]
+pub async fn restore_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+) -> Result<impl IntoResponse, RestoreItineraryError> {
+    db.restore_itinerary(user.id, itinerary_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug)]
+pub enum RestoreItineraryError {
+    ItineraryNotFound,
+    NotArchived,
+    Unexpected(anyhow::Error),
+}
+
+impl From<sqlx::Error> for RestoreItineraryError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for RestoreItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::NotArchived => (
+                StatusCode::CONFLICT,
+                "Only archived itineraries can be restored",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait RestoreItineraryRespository {
+    async fn restore_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<(), RestoreItineraryError>;
+}
+
+impl RestoreItineraryRespository for PgPool {
+    async fn restore_itinerary(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+    ) -> Result<(), RestoreItineraryError> {
+        let mut transaction = self.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+                select status as "status: ItineraryStatus"
+                from itineraries
+                where user_id = $1
+                    and itinerary_id = $2
+                for update
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(RestoreItineraryError::ItineraryNotFound)?;
+
+        if current.status != ItineraryStatus::Archived {
+            return Err(RestoreItineraryError::NotArchived);
+        }
+
+        sqlx::query!(
+            r#"
+                update itineraries
+                set status = coalesce(archived_from, 'draft'),
+                    archived_from = null,
+                    updated_at = clock_timestamp()
+                where itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::User;
+
+#[tracing::instrument(name = "Revoke Itinerary Feed", skip(db))]
+pub async fn revoke_itinerary_feed(
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    State(db): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    if !db.revoke_itinerary_feed(user.id, itinerary_id).await? {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+trait RevokeItineraryFeedRepository {
+    /// Returns `false` when the itinerary had no feed to revoke.
+    async fn revoke_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<bool>;
+}
+
+impl RevokeItineraryFeedRepository for PgPool {
+    async fn revoke_itinerary_feed(&self, user_id: i32, itinerary_id: i32) -> Result<bool> {
+        let revoked = sqlx::query!(
+            r#"
+                delete from itinerary_feeds f
+                using itineraries i
+                where i.itinerary_id = f.itinerary_id
+                    and i.user_id = $1
+                    and i.itinerary_id = $2
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .execute(self)
+        .await?;
+
+        Ok(revoked.rows_affected() > 0)
+    }
+}
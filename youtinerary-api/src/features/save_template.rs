@@ -0,0 +1,180 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::clone_itinerary::{CloneItineraryRepository, CloneSourceRepository, CopyItinerary};
+use crate::{ItemVisibility, User};
+
+#[tracing::instrument(name = "Save Template", skip(db))]
+pub async fn save_template(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    Json(save_template): Json<SaveTemplateRequest>,
+) -> Result<impl IntoResponse, SaveTemplateError> {
+    let source = db
+        .get_clone_source(user.id, itinerary_id)
+        .await?
+        .ok_or(SaveTemplateError::ItineraryNotFound)?;
+    if source.start_date.is_none() {
+        return Err(SaveTemplateError::ItineraryHasNoStartDate);
+    }
+
+    let mut transaction = db.begin().await?;
+    let template_id = transaction
+        .clone_itinerary(CopyItinerary {
+            source_itinerary_id: itinerary_id,
+            user_id: user.id,
+            name: save_template.name.unwrap_or(source.name),
+            offset_days: 0,
+            is_template: true,
+            template_start_date: None,
+            copy_editor_items: source.role.can_see(ItemVisibility::Editors),
+        })
+        .await?;
+    transaction.record_template_times(template_id).await?;
+    transaction.commit().await?;
+
+    Ok((StatusCode::CREATED, format!("/templates/{}", template_id)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveTemplateRequest {
+    name: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum SaveTemplateError {
+    ItineraryNotFound,
+    ItineraryHasNoStartDate,
+    Unexpected(anyhow::Error),
+}
+
+impl<E> From<E> for SaveTemplateError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(value: E) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for SaveTemplateError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::ItineraryHasNoStartDate => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The itinerary needs a start date before it can be saved as a template",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+pub trait TemplateTimesRepository {
+    /// Records where every item of a template falls relative to the template's first day: the
+    /// number of days after it, and the local wall clock times of flights and travel legs.
+    async fn record_template_times(&mut self, template_id: i32) -> Result<()>;
+}
+
+impl TemplateTimesRepository for Transaction<'_, Postgres> {
+    async fn record_template_times(&mut self, template_id: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+                insert into template_item_times (
+                    itinerary_item_id,
+                    start_day,
+                    start_time,
+                    end_day,
+                    end_time
+                )
+                select
+                    f.itinerary_item_id,
+                    (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::date
+                        - d.start_date,
+                    (f.departure_time at time zone coalesce(f.departure_timezone, 'UTC'))::time,
+                    (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::date
+                        - d.start_date,
+                    (f.arrival_time at time zone coalesce(f.arrival_timezone, 'UTC'))::time
+                from flights f
+                    join itinerary_items x on x.id = f.itinerary_item_id
+                    join itinerary_dates d on d.itinerary_id = x.itinerary_id
+                where x.itinerary_id = $1
+                    and d.start_date is not null
+            "#,
+            template_id
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                insert into template_item_times (itinerary_item_id, start_day, end_day)
+                select s.itinerary_item_id, s.start_date - d.start_date, s.end_date - d.start_date
+                from stays s
+                    join itinerary_items x on x.id = s.itinerary_item_id
+                    join itinerary_dates d on d.itinerary_id = x.itinerary_id
+                where x.itinerary_id = $1
+                    and d.start_date is not null
+            "#,
+            template_id
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                insert into template_item_times (itinerary_item_id, start_day, end_day)
+                select a.itinerary_item_id, a.start_date - d.start_date, a.end_date - d.start_date
+                from activities a
+                    join itinerary_items x on x.id = a.itinerary_item_id
+                    join itinerary_dates d on d.itinerary_id = x.itinerary_id
+                where x.itinerary_id = $1
+                    and d.start_date is not null
+            "#,
+            template_id
+        )
+        .execute(&mut **self)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                insert into template_item_times (
+                    itinerary_item_id,
+                    start_day,
+                    start_time,
+                    end_day,
+                    end_time
+                )
+                select
+                    t.itinerary_item_id,
+                    t.start_date - d.start_date,
+                    (t.start_time at time zone coalesce(t.start_timezone, 'UTC'))::time,
+                    t.end_date - d.start_date,
+                    (t.end_time at time zone coalesce(t.end_timezone, 'UTC'))::time
+                from travel_legs t
+                    join itinerary_items x on x.id = t.itinerary_item_id
+                    join itinerary_dates d on d.itinerary_id = x.itinerary_id
+                where x.itinerary_id = $1
+                    and d.start_date is not null
+            "#,
+            template_id
+        )
+        .execute(&mut **self)
+        .await?;
+
+        Ok(())
+    }
+}
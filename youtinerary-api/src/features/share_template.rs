@@ -0,0 +1,155 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{ItineraryShareType, User};
+
+#[tracing::instrument(name = "Share Template", skip(db))]
+pub async fn share_template(
+    State(db): State<PgPool>,
+    user: User,
+    Path(template_id): Path<i32>,
+    Json(share_template): Json<ShareTemplateRequest>,
+) -> Result<impl IntoResponse, ShareTemplateError> {
+    let shared_with = db
+        .share_template((user.id, template_id, share_template).into())
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        format!("/templates/{}/shares/{}", template_id, shared_with),
+    ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareTemplateRequest {
+    email: String,
+    share_type: ItineraryShareType,
+    #[serde(default)]
+    share_message: String,
+}
+
+impl From<(i32, i32, ShareTemplateRequest)> for InsertShare {
+    fn from(val: (i32, i32, ShareTemplateRequest)) -> Self {
+        InsertShare {
+            owner_id: val.0,
+            template_id: val.1,
+            email: val.2.email,
+            share_type: val.2.share_type,
+            share_message: val.2.share_message,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShareTemplateError {
+    TemplateNotFound,
+    UserNotFound,
+    SharedWithOwner,
+    Unexpected(anyhow::Error),
+}
+
+impl From<sqlx::Error> for ShareTemplateError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for ShareTemplateError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::TemplateNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::UserNotFound => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "There is no user with that email",
+            )
+                .into_response(),
+            Self::SharedWithOwner => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "A template cannot be shared with its owner",
+            )
+                .into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+struct InsertShare {
+    owner_id: i32,
+    template_id: i32,
+    email: String,
+    share_type: ItineraryShareType,
+    share_message: String,
+}
+
+trait ShareTemplateRepository {
+    /// Shares a template with the user behind `email`, replacing any earlier share with them.
+    async fn share_template(&self, share: InsertShare) -> Result<i32, ShareTemplateError>;
+}
+
+impl ShareTemplateRepository for PgPool {
+    async fn share_template(&self, share: InsertShare) -> Result<i32, ShareTemplateError> {
+        let mut transaction = self.begin().await?;
+
+        sqlx::query!(
+            r#"
+                select itinerary_id
+                from itineraries
+                where user_id = $1
+                    and itinerary_id = $2
+                    and is_template
+                for update
+            "#,
+            share.owner_id,
+            share.template_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(ShareTemplateError::TemplateNotFound)?;
+
+        let user = sqlx::query!(
+            r#"
+                select user_id
+                from users
+                where email = $1
+            "#,
+            share.email
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(ShareTemplateError::UserNotFound)?;
+
+        if user.user_id == share.owner_id {
+            return Err(ShareTemplateError::SharedWithOwner);
+        }
+
+        sqlx::query!(
+            r#"
+                insert into itinerary_shares (itinerary_id, user_id, share_type, share_message)
+                values ($1, $2, $3, $4)
+                on conflict (itinerary_id, user_id)
+                do update set share_type = excluded.share_type,
+                    share_message = excluded.share_message
+            "#,
+            share.template_id,
+            user.user_id,
+            share.share_type as ItineraryShareType,
+            share.share_message
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(user.user_id)
+    }
+}
@@ -0,0 +1,76 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use sqlx::PgPool;
+
+use crate::error_handling::AppError;
+use crate::User;
+
+#[tracing::instrument(name = "Unshare Template", skip(db))]
+pub async fn unshare_template(
+    State(db): State<PgPool>,
+    user: User,
+    Path((template_id, shared_with)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, AppError> {
+    if !db
+        .unshare_template(user.id, template_id, shared_with)
+        .await?
+    {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+trait UnshareTemplateRepository {
+    /// Removes a user's access to a template. Returns `false` when the owner has no such template,
+    /// removing a share that does not exist is not an error.
+    async fn unshare_template(
+        &self,
+        owner_id: i32,
+        template_id: i32,
+        shared_with: i32,
+    ) -> Result<bool>;
+}
+
+impl UnshareTemplateRepository for PgPool {
+    async fn unshare_template(
+        &self,
+        owner_id: i32,
+        template_id: i32,
+        shared_with: i32,
+    ) -> Result<bool> {
+        let template = sqlx::query!(
+            r#"
+                select itinerary_id
+                from itineraries
+                where user_id = $1
+                    and itinerary_id = $2
+                    and is_template
+            "#,
+            owner_id,
+            template_id
+        )
+        .fetch_optional(self)
+        .await?;
+        if template.is_none() {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+                delete from itinerary_shares
+                where itinerary_id = $1
+                    and user_id = $2
+            "#,
+            template_id,
+            shared_with
+        )
+        .execute(self)
+        .await?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,215 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::activities::{get_activities, ActivityFields, Cost};
+use crate::fields::{nullable, InvalidField};
+use crate::timeline::{ActivityEntry, Coordinates};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{ActivityCategory, User};
+
+/// Changes the fields given in the body and keeps the others.
+#[tracing::instrument(name = "Update Activity", skip(db))]
+pub async fn update_activity(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, activity_id)): Path<(i32, i32)>,
+    Json(update_activity): Json<UpdateActivityRequest>,
+) -> Result<impl IntoResponse, UpdateActivityError> {
+    db.update_activity(user.id, itinerary_id, activity_id, update_activity)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/activities/{}",
+                itinerary_id, activity_id
+            )),
+            issues,
+        }),
+    ))
+}
+
+/// Every field is optional and missing ones are left as they are. Text fields are cleared with
+/// an empty string, times, the location and the cost with `null`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateActivityRequest {
+    pub(crate) summary: Option<String>,
+    pub(crate) category: Option<ActivityCategory>,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) start_time: Option<Option<NaiveTime>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) end_time: Option<Option<NaiveTime>>,
+    pub(crate) address: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) location: Option<Option<Coordinates>>,
+    pub(crate) booking_reference: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) cost: Option<Option<Cost>>,
+    pub(crate) notes: Option<String>,
+}
+
+impl UpdateActivityRequest {
+    /// Applies the request to the stored activity, validating the result like a new activity.
+    fn apply(self, activity: ActivityEntry) -> Result<ActivityFields, InvalidField> {
+        let activity = ActivityFields::from(activity);
+
+        ActivityFields {
+            summary: self.summary.unwrap_or(activity.summary),
+            category: self.category.unwrap_or(activity.category),
+            start_date: self.start_date.unwrap_or(activity.start_date),
+            end_date: self.end_date.unwrap_or(activity.end_date),
+            start_time: self.start_time.unwrap_or(activity.start_time),
+            end_time: self.end_time.unwrap_or(activity.end_time),
+            address: self.address.or(activity.address),
+            location: self.location.unwrap_or(activity.location),
+            booking_reference: self.booking_reference.or(activity.booking_reference),
+            cost: self.cost.unwrap_or(activity.cost),
+            notes: self.notes.unwrap_or(activity.notes),
+        }
+        .validated()
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateActivityError {
+    Access(AccessError),
+    InvalidField(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for UpdateActivityError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<InvalidField> for UpdateActivityError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateActivityError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for UpdateActivityError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for UpdateActivityError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::InvalidField(error) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()).into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateActivityRespository {
+    async fn update_activity(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: i32,
+        update_activity: UpdateActivityRequest,
+    ) -> Result<(), UpdateActivityError>;
+}
+
+impl UpdateActivityRespository for PgPool {
+    async fn update_activity(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        activity_id: i32,
+        update_activity: UpdateActivityRequest,
+    ) -> Result<(), UpdateActivityError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        sqlx::query!(
+            r#"
+                select a.id
+                from activities a
+                    join itinerary_items i on i.id = a.itinerary_item_id
+                where i.itinerary_id = $1
+                    and a.id = $2
+                for update of a
+            "#,
+            itinerary_id,
+            activity_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        let current = get_activities(&mut transaction, itinerary_id, Some(activity_id))
+            .await?
+            .pop()
+            .ok_or(AccessError::NotFound)?;
+        let activity = update_activity.apply(current)?;
+
+        sqlx::query!(
+            r#"
+                update activities
+                set summary = $2,
+                    category = $3,
+                    start_date = $4,
+                    end_date = $5,
+                    start_time = $6,
+                    end_time = $7,
+                    address = $8,
+                    location = point($9, $10),
+                    booking_reference = $11,
+                    cost = $12::text::numeric,
+                    cost_currency = $13,
+                    notes = $14
+                where id = $1
+            "#,
+            activity_id,
+            activity.summary,
+            activity.category as ActivityCategory,
+            activity.start_date,
+            activity.end_date,
+            activity.start_time,
+            activity.end_time,
+            activity.address,
+            activity.location.map(|x| x.longitude),
+            activity.location.map(|x| x.latitude),
+            activity.booking_reference,
+            activity.cost.as_ref().map(|x| &x.amount),
+            activity.cost.as_ref().map(|x| &x.currency),
+            activity.notes
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
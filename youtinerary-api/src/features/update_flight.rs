@@ -0,0 +1,349 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::{optional_text, required_text, InvalidField};
+use crate::flights::{
+    get_flights, replace_passengers, resolve_airport, FlightDetails, FlightInputError, Passenger,
+};
+use crate::local_time::resolve_instant;
+use crate::timeline::{Coordinates, FlightEntry};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{CabinClass, User};
+
+/// Changes the fields given in the body and keeps the others.
+#[tracing::instrument(name = "Update Flight", skip(db))]
+pub async fn update_flight(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, flight_id)): Path<(i32, i32)>,
+    Json(update_flight): Json<UpdateFlightRequest>,
+) -> Result<impl IntoResponse, UpdateFlightError> {
+    db.update_flight(user.id, itinerary_id, flight_id, update_flight)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/flights/{}",
+                itinerary_id, flight_id
+            )),
+            issues,
+        }),
+    ))
+}
+
+/// Every field is optional and missing ones are left as they are. Text fields are cleared with
+/// an empty string, and `passengers` replaces the whole list when given.
+///
+/// A new time is read like when creating a flight. Changing an airport without giving a time
+/// keeps the instants, so the local times follow the new airport's zone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateFlightRequest {
+    pub(crate) airline: Option<String>,
+    pub(crate) confirmation_code: Option<String>,
+    pub(crate) departure_time: Option<DateTime<Utc>>,
+    pub(crate) local_departure_time: Option<NaiveDateTime>,
+    pub(crate) arrival_time: Option<DateTime<Utc>>,
+    pub(crate) local_arrival_time: Option<NaiveDateTime>,
+    pub(crate) departure_timezone: Option<Tz>,
+    pub(crate) arrival_timezone: Option<Tz>,
+    pub(crate) departure_airport: Option<String>,
+    pub(crate) arrival_airport: Option<String>,
+    pub(crate) flight_number: Option<String>,
+    pub(crate) operating_carrier: Option<String>,
+    pub(crate) departure_terminal: Option<String>,
+    pub(crate) departure_gate: Option<String>,
+    pub(crate) arrival_terminal: Option<String>,
+    pub(crate) arrival_gate: Option<String>,
+    pub(crate) cabin_class: Option<CabinClass>,
+    pub(crate) aircraft_type: Option<String>,
+    pub(crate) passengers: Option<Vec<Passenger>>,
+    pub(crate) notes: Option<String>,
+}
+
+type Endpoint = (Option<String>, Option<Coordinates>, Option<Tz>);
+
+/// Resolves one end of the flight. A new airport or zone is checked against the other half,
+/// taken from the request or else from what is stored.
+fn update_endpoint(
+    airport: Option<String>,
+    timezone: Option<Tz>,
+    current: Endpoint,
+) -> Result<Endpoint, FlightInputError> {
+    if airport.is_none() && timezone.is_none() {
+        return Ok(current);
+    }
+
+    let code = airport.or(current.0);
+    let (airport, timezone) = resolve_airport(code.as_deref(), timezone)?;
+
+    Ok(match airport {
+        Some(airport) => (Some(airport.code), airport.location, timezone),
+        None => (None, None, timezone),
+    })
+}
+
+fn update_instant(
+    field: &'static str,
+    instant: Option<DateTime<Utc>>,
+    local: Option<NaiveDateTime>,
+    timezone: Option<Tz>,
+    current: DateTime<Utc>,
+) -> Result<DateTime<Utc>, FlightInputError> {
+    if instant.is_none() && local.is_none() {
+        return Ok(current);
+    }
+
+    resolve_instant(instant, local, timezone)
+        .map_err(|error| FlightInputError::InvalidTime(field, error))
+}
+
+fn update_text(
+    field: &'static str,
+    value: Option<String>,
+    current: Option<String>,
+    max: usize,
+) -> Result<Option<String>, InvalidField> {
+    match value {
+        Some(value) => optional_text(field, Some(value), max),
+        None => Ok(current),
+    }
+}
+
+impl UpdateFlightRequest {
+    /// Applies the request to the stored flight, validating the result like a new flight.
+    fn apply(self, flight: FlightEntry) -> Result<FlightEntry, FlightInputError> {
+        let (departure_airport, departure_location, departure_timezone) = update_endpoint(
+            self.departure_airport,
+            self.departure_timezone,
+            (
+                flight.departure_airport,
+                flight.departure_location,
+                flight.departure_timezone,
+            ),
+        )?;
+        let (arrival_airport, arrival_location, arrival_timezone) = update_endpoint(
+            self.arrival_airport,
+            self.arrival_timezone,
+            (
+                flight.arrival_airport,
+                flight.arrival_location,
+                flight.arrival_timezone,
+            ),
+        )?;
+
+        let departure_time = update_instant(
+            "departure",
+            self.departure_time,
+            self.local_departure_time,
+            departure_timezone,
+            flight.departure_time,
+        )?;
+        let arrival_time = update_instant(
+            "arrival",
+            self.arrival_time,
+            self.local_arrival_time,
+            arrival_timezone,
+            flight.arrival_time,
+        )?;
+
+        let details = *flight.details;
+        let details = FlightDetails {
+            flight_number: self.flight_number.or(details.flight_number),
+            operating_carrier: self.operating_carrier.or(details.operating_carrier),
+            departure_terminal: self.departure_terminal.or(details.departure_terminal),
+            departure_gate: self.departure_gate.or(details.departure_gate),
+            arrival_terminal: self.arrival_terminal.or(details.arrival_terminal),
+            arrival_gate: self.arrival_gate.or(details.arrival_gate),
+            cabin_class: self.cabin_class.or(details.cabin_class),
+            aircraft_type: self.aircraft_type.or(details.aircraft_type),
+            passengers: self.passengers.unwrap_or(details.passengers),
+        }
+        .validated()?;
+
+        Ok(FlightEntry {
+            id: flight.id,
+            airline: match self.airline {
+                Some(airline) => required_text("airline", airline, 255)?,
+                None => flight.airline,
+            },
+            confirmation_code: match self.confirmation_code {
+                Some(code) => required_text("confirmation_code", code, 50)?,
+                None => flight.confirmation_code,
+            },
+            departure_time,
+            arrival_time,
+            departure_timezone,
+            arrival_timezone,
+            departure_airport,
+            arrival_airport,
+            departure_location,
+            arrival_location,
+            details: Box::new(details),
+            item: flight.item,
+            notes: update_text("notes", self.notes, Some(flight.notes), 255)?.unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateFlightError {
+    Access(AccessError),
+    Invalid(FlightInputError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for UpdateFlightError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<FlightInputError> for UpdateFlightError {
+    fn from(value: FlightInputError) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateFlightError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for UpdateFlightError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for UpdateFlightError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Invalid(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateFlightRespository {
+    async fn update_flight(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: i32,
+        update_flight: UpdateFlightRequest,
+    ) -> Result<(), UpdateFlightError>;
+}
+
+impl UpdateFlightRespository for PgPool {
+    async fn update_flight(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        flight_id: i32,
+        update_flight: UpdateFlightRequest,
+    ) -> Result<(), UpdateFlightError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        sqlx::query!(
+            r#"
+                select f.id
+                from flights f
+                    join itinerary_items i on i.id = f.itinerary_item_id
+                where i.itinerary_id = $1
+                    and f.id = $2
+                for update of f
+            "#,
+            itinerary_id,
+            flight_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        let current = get_flights(&mut transaction, itinerary_id, Some(flight_id))
+            .await?
+            .pop()
+            .ok_or(AccessError::NotFound)?;
+        let passengers_changed = update_flight.passengers.is_some();
+        let flight = update_flight.apply(current)?;
+        let details = &flight.details;
+
+        sqlx::query!(
+            r#"
+                update flights
+                set airline = $2,
+                    confirmation_code = $3,
+                    departure_time = $4,
+                    arrival_time = $5,
+                    departure_timezone = $6,
+                    arrival_timezone = $7,
+                    departure_airport = $8,
+                    arrival_airport = $9,
+                    departure_location = point($10, $11),
+                    arrival_location = point($12, $13),
+                    flight_number = $14,
+                    operating_carrier = $15,
+                    departure_terminal = $16,
+                    departure_gate = $17,
+                    arrival_terminal = $18,
+                    arrival_gate = $19,
+                    cabin_class = $20,
+                    aircraft_type = $21,
+                    notes = $22
+                where id = $1
+            "#,
+            flight.id,
+            flight.airline,
+            flight.confirmation_code,
+            flight.departure_time,
+            flight.arrival_time,
+            flight.departure_timezone.map(|tz| tz.name()),
+            flight.arrival_timezone.map(|tz| tz.name()),
+            flight.departure_airport,
+            flight.arrival_airport,
+            flight.departure_location.map(|x| x.longitude),
+            flight.departure_location.map(|x| x.latitude),
+            flight.arrival_location.map(|x| x.longitude),
+            flight.arrival_location.map(|x| x.latitude),
+            details.flight_number,
+            details.operating_carrier,
+            details.departure_terminal,
+            details.departure_gate,
+            details.arrival_terminal,
+            details.arrival_gate,
+            details.cabin_class as Option<CabinClass>,
+            details.aircraft_type,
+            flight.notes,
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        if passengers_changed {
+            replace_passengers(&mut transaction, flight.id, &details.passengers).await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::{invalid, nullable, InvalidField};
+use crate::items::Item;
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{ItemVisibility, User};
+
+/// Moves an item within the items starting at the same time, or changes who sees it, whatever
+/// its kind.
+#[tracing::instrument(name = "Update Item", skip(db))]
+pub async fn update_item(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, item_id)): Path<(i32, i32)>,
+    Json(update_item): Json<UpdateItemRequest>,
+) -> Result<impl IntoResponse, UpdateItemError> {
+    db.update_item(user.id, itinerary_id, item_id, update_item)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: Some(format!("/itineraries/{}/items/{}", itinerary_id, item_id)),
+            issues,
+        }),
+    ))
+}
+
+/// Missing fields are left as they are, and `position` is cleared with `null`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateItemRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) position: Option<Option<i32>>,
+    pub(crate) visibility: Option<ItemVisibility>,
+}
+
+impl UpdateItemRequest {
+    fn apply(self, item: Item) -> Result<Item, InvalidField> {
+        let position = self.position.unwrap_or(item.position);
+        if position.is_some_and(|x| x < 0) {
+            return Err(invalid("position", "must not be negative"));
+        }
+
+        Ok(Item {
+            id: item.id,
+            position,
+            visibility: self.visibility.unwrap_or(item.visibility),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateItemError {
+    Access(AccessError),
+    InvalidField(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for UpdateItemError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<InvalidField> for UpdateItemError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateItemError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for UpdateItemError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for UpdateItemError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::InvalidField(error) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()).into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateItemRespository {
+    async fn update_item(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: i32,
+        update_item: UpdateItemRequest,
+    ) -> Result<(), UpdateItemError>;
+}
+
+impl UpdateItemRespository for PgPool {
+    async fn update_item(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        item_id: i32,
+        update_item: UpdateItemRequest,
+    ) -> Result<(), UpdateItemError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        let current = sqlx::query_as!(
+            Item,
+            r#"
+                select
+                    id,
+                    position,
+                    visibility as "visibility: ItemVisibility"
+                from itinerary_items
+                where itinerary_id = $1
+                    and id = $2
+                for update
+            "#,
+            itinerary_id,
+            item_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+        let item = update_item.apply(current)?;
+
+        sqlx::query!(
+            r#"
+                update itinerary_items
+                set position = $2,
+                    visibility = $3
+                where id = $1
+            "#,
+            item.id,
+            item.position,
+            item.visibility as ItemVisibility
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
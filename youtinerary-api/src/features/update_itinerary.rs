@@ -0,0 +1,256 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::TypedHeader;
+use chrono::{NaiveDate, NaiveDateTime};
+use headers::IfMatch;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::ItineraryRole;
+use crate::etag::{etag_from_updated_at, precondition_passes};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{check_publishable, ItineraryShareType, ItineraryStatus, PublishError, User};
+
+#[tracing::instrument(name = "Update Itinerary", skip(db))]
+pub async fn update_itinerary(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    if_match: Option<TypedHeader<IfMatch>>,
+    Json(update_itinerary): Json<UpdateItineraryRequest>,
+) -> Result<impl IntoResponse, UpdateItineraryError> {
+    let updated_at = db
+        .update_itinerary(
+            (user.id, itinerary_id, update_itinerary).into(),
+            if_match.as_ref().map(|TypedHeader(if_match)| if_match),
+        )
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        TypedHeader(etag_from_updated_at(updated_at)),
+        Json(WriteResponse {
+            location: None,
+            issues,
+        }),
+    ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateItineraryRequest {
+    pub name: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+impl From<(i32, i32, UpdateItineraryRequest)> for UpdateItinerary {
+    fn from(val: (i32, i32, UpdateItineraryRequest)) -> Self {
+        UpdateItinerary {
+            user_id: val.0,
+            itinerary_id: val.1,
+            name: val.2.name,
+            start_date: val.2.start_date,
+            end_date: val.2.end_date,
+        }
+    }
+}
+
+struct UpdateItinerary {
+    user_id: i32,
+    itinerary_id: i32,
+    name: Option<String>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug)]
+pub enum UpdateItineraryError {
+    ItineraryNotFound,
+    ReadOnly,
+    PreconditionFailed,
+    EndBeforeStart,
+    ItineraryArchived,
+    Unpublishable(PublishError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<anyhow::Error> for UpdateItineraryError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateItineraryError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for UpdateItineraryError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::ReadOnly => (
+                StatusCode::FORBIDDEN,
+                "The itinerary is shared with you as a viewer",
+            )
+                .into_response(),
+            Self::PreconditionFailed => (
+                StatusCode::PRECONDITION_FAILED,
+                "The itinerary was changed by someone else, reload it and try again",
+            )
+                .into_response(),
+            Self::EndBeforeStart => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "end_date must not be before start_date",
+            )
+                .into_response(),
+            Self::ItineraryArchived => (
+                StatusCode::CONFLICT,
+                "Restore the itinerary before editing it",
+            )
+                .into_response(),
+            Self::Unpublishable(error) => (StatusCode::CONFLICT, error.message()).into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateItineraryRespository {
+    async fn update_itinerary(
+        &self,
+        update_itinerary: UpdateItinerary,
+        if_match: Option<&IfMatch>,
+    ) -> Result<NaiveDateTime, UpdateItineraryError>;
+}
+
+impl UpdateItineraryRespository for PgPool {
+    async fn update_itinerary(
+        &self,
+        update_itinerary: UpdateItinerary,
+        if_match: Option<&IfMatch>,
+    ) -> Result<NaiveDateTime, UpdateItineraryError> {
+        let mut transaction = self.begin().await?;
+
+        // The row is locked for update before anything is compared, so of two editors saving
+        // the same version only the first passes the If-Match check.
+        let current = sqlx::query!(
+            r#"
+                select
+                    i.user_id = $1 as "is_owner!",
+                    sh.share_type as "share_type?: ItineraryShareType",
+                    i.name,
+                    i.status as "status: ItineraryStatus",
+                    i.updated_at,
+                    s.start_date as "start_date?",
+                    e.end_date as "end_date?",
+                    d.start_date as effective_start_date,
+                    d.end_date as effective_end_date
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                    left join itinerary_shares sh
+                        on sh.itinerary_id = i.itinerary_id
+                        and sh.user_id = $1
+                    left join itinerary_start_date s on s.itinerary_id = i.itinerary_id
+                    left join itinerary_end_date e on e.itinerary_id = i.itinerary_id
+                where i.itinerary_id = $2
+                    and (i.user_id = $1 or sh.user_id is not null)
+                for update of i
+            "#,
+            update_itinerary.user_id,
+            update_itinerary.itinerary_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(UpdateItineraryError::ItineraryNotFound)?;
+
+        if !ItineraryRole::new(current.is_owner, current.share_type).can_edit() {
+            return Err(UpdateItineraryError::ReadOnly);
+        }
+
+        if !precondition_passes(if_match, current.updated_at) {
+            return Err(UpdateItineraryError::PreconditionFailed);
+        }
+
+        if current.status == ItineraryStatus::Archived {
+            return Err(UpdateItineraryError::ItineraryArchived);
+        }
+
+        let start_date = update_itinerary.start_date.or(current.start_date);
+        let end_date = update_itinerary.end_date.or(current.end_date);
+        if let (Some(start_date), Some(end_date)) = (start_date, end_date) {
+            if end_date < start_date {
+                return Err(UpdateItineraryError::EndBeforeStart);
+            }
+        }
+
+        // A published itinerary stays publishable. Dates it doesn't set come from its items, so
+        // the check runs on the dates it will have after this change.
+        if current.status == ItineraryStatus::Published {
+            check_publishable(
+                update_itinerary.name.as_deref().unwrap_or(&current.name),
+                update_itinerary.start_date.or(current.effective_start_date),
+                update_itinerary.end_date.or(current.effective_end_date),
+            )
+            .map_err(UpdateItineraryError::Unpublishable)?;
+        }
+
+        if let Some(start_date) = update_itinerary.start_date {
+            sqlx::query!(
+                r#"
+                    insert into itinerary_start_date (itinerary_id, start_date)
+                    values ($1, $2)
+                    on conflict (itinerary_id) do update
+                        set start_date = excluded.start_date
+                "#,
+                update_itinerary.itinerary_id,
+                start_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if let Some(end_date) = update_itinerary.end_date {
+            sqlx::query!(
+                r#"
+                    insert into itinerary_end_date (itinerary_id, end_date)
+                    values ($1, $2)
+                    on conflict (itinerary_id) do update
+                        set end_date = excluded.end_date
+                "#,
+                update_itinerary.itinerary_id,
+                end_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        let updated = sqlx::query!(
+            r#"
+                update itineraries
+                set name = coalesce($2, name),
+                    updated_at = clock_timestamp()
+                where itinerary_id = $1
+                returning updated_at
+            "#,
+            update_itinerary.itinerary_id,
+            update_itinerary.name
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(updated.updated_at)
+    }
+}
@@ -0,0 +1,219 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::TypedHeader;
+use chrono::NaiveDateTime;
+use headers::IfMatch;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::ItineraryRole;
+use crate::etag::{etag_from_updated_at, precondition_passes};
+use crate::{check_publishable, ItineraryShareType, ItineraryStatus, PublishError, User};
+
+#[tracing::instrument(name = "Update Itinerary Status", skip(db))]
+pub async fn update_itinerary_status(
+    State(db): State<PgPool>,
+    user: User,
+    Path(itinerary_id): Path<i32>,
+    if_match: Option<TypedHeader<IfMatch>>,
+    Json(update_status): Json<UpdateItineraryStatusRequest>,
+) -> Result<impl IntoResponse, UpdateItineraryStatusError> {
+    let updated_at = db
+        .update_itinerary_status(
+            user.id,
+            itinerary_id,
+            update_status.status,
+            if_match.as_ref().map(|TypedHeader(if_match)| if_match),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::NO_CONTENT,
+        TypedHeader(etag_from_updated_at(updated_at)),
+    ))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateItineraryStatusRequest {
+    pub status: ItineraryStatus,
+}
+
+#[derive(Debug)]
+pub enum UpdateItineraryStatusError {
+    ItineraryNotFound,
+    ReadOnly,
+    OwnerOnly,
+    PreconditionFailed,
+    IllegalTransition {
+        from: ItineraryStatus,
+        to: ItineraryStatus,
+    },
+    Unpublishable(PublishError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<sqlx::Error> for UpdateItineraryStatusError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl IntoResponse for UpdateItineraryStatusError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ItineraryNotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::ReadOnly => (
+                StatusCode::FORBIDDEN,
+                "The itinerary is shared with you as a viewer",
+            )
+                .into_response(),
+            Self::OwnerOnly => (
+                StatusCode::FORBIDDEN,
+                "Only the owner can archive or restore the itinerary",
+            )
+                .into_response(),
+            Self::PreconditionFailed => (
+                StatusCode::PRECONDITION_FAILED,
+                "The itinerary was changed by someone else, reload it and try again",
+            )
+                .into_response(),
+            Self::IllegalTransition { from, to } => (
+                StatusCode::CONFLICT,
+                format!("An itinerary cannot move from {:?} to {:?}", from, to),
+            )
+                .into_response(),
+            Self::Unpublishable(error) => (StatusCode::CONFLICT, error.message()).into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateItineraryStatusRespository {
+    async fn update_itinerary_status(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        status: ItineraryStatus,
+        if_match: Option<&IfMatch>,
+    ) -> Result<NaiveDateTime, UpdateItineraryStatusError>;
+}
+
+impl UpdateItineraryStatusRespository for PgPool {
+    async fn update_itinerary_status(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        status: ItineraryStatus,
+        if_match: Option<&IfMatch>,
+    ) -> Result<NaiveDateTime, UpdateItineraryStatusError> {
+        let mut transaction = self.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+                select
+                    i.user_id = $1 as "is_owner!",
+                    sh.share_type as "share_type?: ItineraryShareType",
+                    i.name,
+                    i.status as "status: ItineraryStatus",
+                    i.updated_at,
+                    d.start_date,
+                    d.end_date
+                from itineraries i
+                    join itinerary_dates d on d.itinerary_id = i.itinerary_id
+                    left join itinerary_shares sh
+                        on sh.itinerary_id = i.itinerary_id
+                        and sh.user_id = $1
+                where i.itinerary_id = $2
+                    and (i.user_id = $1 or sh.user_id is not null)
+                for update of i
+            "#,
+            user_id,
+            itinerary_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(UpdateItineraryStatusError::ItineraryNotFound)?;
+
+        // Editors publish and unpublish, archiving stays with the owner like DELETE does.
+        match ItineraryRole::new(current.is_owner, current.share_type) {
+            ItineraryRole::Viewer => return Err(UpdateItineraryStatusError::ReadOnly),
+            ItineraryRole::Editor
+                if current.status == ItineraryStatus::Archived
+                    || status == ItineraryStatus::Archived =>
+            {
+                return Err(UpdateItineraryStatusError::OwnerOnly)
+            }
+            _ => {}
+        }
+
+        if !precondition_passes(if_match, current.updated_at) {
+            return Err(UpdateItineraryStatusError::PreconditionFailed);
+        }
+
+        if !current.status.can_transition_to(status) {
+            return Err(UpdateItineraryStatusError::IllegalTransition {
+                from: current.status,
+                to: status,
+            });
+        }
+
+        if status == ItineraryStatus::Published {
+            check_publishable(&current.name, current.start_date, current.end_date)
+                .map_err(UpdateItineraryStatusError::Unpublishable)?;
+
+            // Dates derived from the items are stored on publish, so later item changes can't
+            // leave a published itinerary without dates or with them out of order.
+            sqlx::query!(
+                r#"
+                    insert into itinerary_start_date (itinerary_id, start_date)
+                    values ($1, $2)
+                    on conflict (itinerary_id) do nothing
+                "#,
+                itinerary_id,
+                current.start_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                    insert into itinerary_end_date (itinerary_id, end_date)
+                    values ($1, $2)
+                    on conflict (itinerary_id) do nothing
+                "#,
+                itinerary_id,
+                current.end_date
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        let updated = sqlx::query!(
+            r#"
+                update itineraries
+                set archived_from = case when $2::itinerary_status = 'archived' then status end,
+                    status = $2,
+                    updated_at = clock_timestamp()
+                where itinerary_id = $1
+                returning updated_at
+            "#,
+            itinerary_id,
+            status as ItineraryStatus
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(updated.updated_at)
+    }
+}
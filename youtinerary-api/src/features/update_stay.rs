@@ -0,0 +1,201 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::{nullable, InvalidField};
+use crate::stays::{get_stays, StayFields};
+use crate::timeline::{Coordinates, StayEntry};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::User;
+
+/// Changes the fields given in the body and keeps the others.
+#[tracing::instrument(name = "Update Stay", skip(db))]
+pub async fn update_stay(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, stay_id)): Path<(i32, i32)>,
+    Json(update_stay): Json<UpdateStayRequest>,
+) -> Result<impl IntoResponse, UpdateStayError> {
+    db.update_stay(user.id, itinerary_id, stay_id, update_stay)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: Some(format!("/itineraries/{}/stays/{}", itinerary_id, stay_id)),
+            issues,
+        }),
+    ))
+}
+
+/// Every field is optional and missing ones are left as they are. Text fields are cleared with
+/// an empty string, times and the location with `null`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateStayRequest {
+    pub(crate) summary: Option<String>,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) check_in_time: Option<Option<NaiveTime>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) check_out_time: Option<Option<NaiveTime>>,
+    pub(crate) address: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) location: Option<Option<Coordinates>>,
+    pub(crate) confirmation_number: Option<String>,
+    pub(crate) notes: Option<String>,
+}
+
+impl UpdateStayRequest {
+    /// Applies the request to the stored stay, validating the result like a new stay.
+    fn apply(self, stay: StayEntry) -> Result<StayFields, InvalidField> {
+        let stay = StayFields::from(stay);
+
+        StayFields {
+            summary: self.summary.unwrap_or(stay.summary),
+            start_date: self.start_date.unwrap_or(stay.start_date),
+            end_date: self.end_date.unwrap_or(stay.end_date),
+            check_in_time: self.check_in_time.unwrap_or(stay.check_in_time),
+            check_out_time: self.check_out_time.unwrap_or(stay.check_out_time),
+            address: self.address.or(stay.address),
+            location: self.location.unwrap_or(stay.location),
+            confirmation_number: self.confirmation_number.or(stay.confirmation_number),
+            notes: self.notes.unwrap_or(stay.notes),
+        }
+        .validated()
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateStayError {
+    Access(AccessError),
+    InvalidField(InvalidField),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for UpdateStayError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<InvalidField> for UpdateStayError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateStayError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for UpdateStayError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for UpdateStayError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::InvalidField(error) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()).into_response()
+            }
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateStayRespository {
+    async fn update_stay(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: i32,
+        update_stay: UpdateStayRequest,
+    ) -> Result<(), UpdateStayError>;
+}
+
+impl UpdateStayRespository for PgPool {
+    async fn update_stay(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        stay_id: i32,
+        update_stay: UpdateStayRequest,
+    ) -> Result<(), UpdateStayError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        sqlx::query!(
+            r#"
+                select s.id
+                from stays s
+                    join itinerary_items i on i.id = s.itinerary_item_id
+                where i.itinerary_id = $1
+                    and s.id = $2
+                for update of s
+            "#,
+            itinerary_id,
+            stay_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        let current = get_stays(&mut transaction, itinerary_id, Some(stay_id))
+            .await?
+            .pop()
+            .ok_or(AccessError::NotFound)?;
+        let stay = update_stay.apply(current)?;
+
+        sqlx::query!(
+            r#"
+                update stays
+                set summary = $2,
+                    start_date = $3,
+                    end_date = $4,
+                    check_in_time = $5,
+                    check_out_time = $6,
+                    address = $7,
+                    location = point($8, $9),
+                    confirmation_number = $10,
+                    notes = $11
+                where id = $1
+            "#,
+            stay_id,
+            stay.summary,
+            stay.start_date,
+            stay.end_date,
+            stay.check_in_time,
+            stay.check_out_time,
+            stay.address,
+            stay.location.map(|x| x.longitude),
+            stay.location.map(|x| x.latitude),
+            stay.confirmation_number,
+            stay.notes
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use axum::extract::{Path, State};
+
+use anyhow::Result;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::access::{check_can_edit, AccessError};
+use crate::fields::nullable;
+use crate::local_time::to_local;
+use crate::timeline::{Coordinates, TravelLegEntry};
+use crate::travel_legs::{
+    get_travel_legs, resolve_end, LegDetails, TravelLegFields, TravelLegInputError,
+};
+use crate::validation::{IssuesRepository, WriteResponse};
+use crate::{TravelLegType, User};
+
+/// Changes the fields given in the body and keeps the others.
+#[tracing::instrument(name = "Update Travel Leg", skip(db))]
+pub async fn update_travel_leg(
+    State(db): State<PgPool>,
+    user: User,
+    Path((itinerary_id, leg_id)): Path<(i32, i32)>,
+    Json(update_travel_leg): Json<UpdateTravelLegRequest>,
+) -> Result<impl IntoResponse, UpdateTravelLegError> {
+    db.update_travel_leg(user.id, itinerary_id, leg_id, update_travel_leg)
+        .await?;
+    let issues = db.get_issues(itinerary_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(WriteResponse {
+            location: Some(format!(
+                "/itineraries/{}/travel_legs/{}",
+                itinerary_id, leg_id
+            )),
+            issues,
+        }),
+    ))
+}
+
+/// Every field is optional and missing ones are left as they are. Text fields are cleared with
+/// an empty string, times and locations with `null`.
+///
+/// A new time is read like when creating a leg. A new date alone moves a timed end to that day
+/// at the same local time, and a new timezone alone keeps the instants.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTravelLegRequest {
+    pub(crate) name: Option<String>,
+    pub(crate) travel_leg_type: Option<TravelLegType>,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) start_time: Option<Option<DateTime<Utc>>>,
+    pub(crate) local_start_time: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) end_time: Option<Option<DateTime<Utc>>>,
+    pub(crate) local_end_time: Option<NaiveDateTime>,
+    pub(crate) start_timezone: Option<Tz>,
+    pub(crate) end_timezone: Option<Tz>,
+    pub(crate) start_place: Option<String>,
+    pub(crate) end_place: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) start_location: Option<Option<Coordinates>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) end_location: Option<Option<Coordinates>>,
+    pub(crate) operator: Option<String>,
+    pub(crate) booking_reference: Option<String>,
+    #[serde(flatten)]
+    pub(crate) details: LegDetails,
+    pub(crate) notes: Option<String>,
+}
+
+/// Resolves one end of the leg from what the request changes about it and what is stored.
+fn update_end(
+    end: &'static str,
+    date_field: &'static str,
+    date: Option<NaiveDate>,
+    instant: Option<Option<DateTime<Utc>>>,
+    local: Option<NaiveDateTime>,
+    timezone: Option<Tz>,
+    current: (NaiveDate, Option<DateTime<Utc>>),
+) -> Result<(NaiveDate, Option<DateTime<Utc>>), TravelLegInputError> {
+    if local.is_some() || matches!(instant, Some(Some(_))) {
+        return resolve_end(end, date_field, date, instant.flatten(), local, timezone);
+    }
+    if instant.is_some() {
+        return Ok((date.unwrap_or(current.0), None));
+    }
+
+    match (date, current.1) {
+        (Some(date), Some(current)) => {
+            let local = date.and_time(to_local(current, timezone).time());
+            resolve_end(
+                end,
+                date_field,
+                None,
+                None,
+                Some(local),
+                Some(timezone.unwrap_or(Tz::UTC)),
+            )
+        }
+        (Some(date), None) => Ok((date, None)),
+        (None, Some(current)) => Ok((to_local(current, timezone).date(), Some(current))),
+        (None, None) => Ok(current),
+    }
+}
+
+impl UpdateTravelLegRequest {
+    /// Applies the request to the stored leg, validating the result like a new leg.
+    fn apply(self, leg: TravelLegEntry) -> Result<TravelLegFields, TravelLegInputError> {
+        let leg = TravelLegFields::from(leg);
+        let start_timezone = self.start_timezone.or(leg.start_timezone);
+        let end_timezone = self.end_timezone.or(leg.end_timezone);
+
+        let (start_date, start_time) = update_end(
+            "start",
+            "start_date",
+            self.start_date,
+            self.start_time,
+            self.local_start_time,
+            start_timezone,
+            (leg.start_date, leg.start_time),
+        )?;
+        let (end_date, end_time) = update_end(
+            "end",
+            "end_date",
+            self.end_date,
+            self.end_time,
+            self.local_end_time,
+            end_timezone,
+            (leg.end_date, leg.end_time),
+        )?;
+
+        let details = leg.details;
+        Ok(TravelLegFields {
+            name: self.name.unwrap_or(leg.name),
+            travel_leg_type: self.travel_leg_type.unwrap_or(leg.travel_leg_type),
+            start_date,
+            end_date,
+            start_time,
+            end_time,
+            start_timezone,
+            end_timezone,
+            start_place: self.start_place.or(leg.start_place),
+            end_place: self.end_place.or(leg.end_place),
+            start_location: self.start_location.unwrap_or(leg.start_location),
+            end_location: self.end_location.unwrap_or(leg.end_location),
+            operator: self.operator.or(leg.operator),
+            booking_reference: self.booking_reference.or(leg.booking_reference),
+            details: LegDetails {
+                service_number: self.details.service_number.or(details.service_number),
+                coach: self.details.coach.or(details.coach),
+                seat: self.details.seat.or(details.seat),
+                platform: self.details.platform.or(details.platform),
+                vehicle: self.details.vehicle.or(details.vehicle),
+                cabin: self.details.cabin.or(details.cabin),
+            },
+            notes: self.notes.unwrap_or(leg.notes),
+        }
+        .validated()?)
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateTravelLegError {
+    Access(AccessError),
+    Invalid(TravelLegInputError),
+    Unexpected(anyhow::Error),
+}
+
+impl From<AccessError> for UpdateTravelLegError {
+    fn from(value: AccessError) -> Self {
+        Self::Access(value)
+    }
+}
+
+impl From<TravelLegInputError> for UpdateTravelLegError {
+    fn from(value: TravelLegInputError) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<sqlx::Error> for UpdateTravelLegError {
+    fn from(value: sqlx::Error) -> Self {
+        Self::Unexpected(value.into())
+    }
+}
+
+impl From<anyhow::Error> for UpdateTravelLegError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Unexpected(value)
+    }
+}
+
+impl IntoResponse for UpdateTravelLegError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Access(error) => error.into_response(),
+            Self::Invalid(error) => error.into_response(),
+            Self::Unexpected(error) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", error),
+            )
+                .into_response(),
+        }
+    }
+}
+
+trait UpdateTravelLegRespository {
+    async fn update_travel_leg(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: i32,
+        update_travel_leg: UpdateTravelLegRequest,
+    ) -> Result<(), UpdateTravelLegError>;
+}
+
+impl UpdateTravelLegRespository for PgPool {
+    async fn update_travel_leg(
+        &self,
+        user_id: i32,
+        itinerary_id: i32,
+        leg_id: i32,
+        update_travel_leg: UpdateTravelLegRequest,
+    ) -> Result<(), UpdateTravelLegError> {
+        let mut transaction = self.begin().await?;
+        check_can_edit(&mut transaction, user_id, itinerary_id).await?;
+
+        sqlx::query!(
+            r#"
+                select t.id
+                from travel_legs t
+                    join itinerary_items i on i.id = t.itinerary_item_id
+                where i.itinerary_id = $1
+                    and t.id = $2
+                for update of t
+            "#,
+            itinerary_id,
+            leg_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(AccessError::NotFound)?;
+
+        let current = get_travel_legs(&mut transaction, itinerary_id, Some(leg_id))
+            .await?
+            .pop()
+            .ok_or(AccessError::NotFound)?;
+        let leg = update_travel_leg.apply(current)?;
+        let details = &leg.details;
+
+        sqlx::query!(
+            r#"
+                update travel_legs
+                set name = $24,
+                    travel_leg_type = $2,
+                    start_date = $3,
+                    end_date = $4,
+                    start_time = $5,
+                    end_time = $6,
+                    start_timezone = $7,
+                    end_timezone = $8,
+                    start_place = $9,
+                    end_place = $10,
+                    start_location = point($11, $12),
+                    end_location = point($13, $14),
+                    operator = $15,
+                    booking_reference = $16,
+                    service_number = $17,
+                    coach = $18,
+                    seat = $19,
+                    platform = $20,
+                    vehicle = $21,
+                    cabin = $22,
+                    notes = $23
+                where id = $1
+            "#,
+            leg_id,
+            leg.travel_leg_type as TravelLegType,
+            leg.start_date,
+            leg.end_date,
+            leg.start_time,
+            leg.end_time,
+            leg.start_timezone.map(|tz| tz.name()),
+            leg.end_timezone.map(|tz| tz.name()),
+            leg.start_place,
+            leg.end_place,
+            leg.start_location.map(|x| x.longitude),
+            leg.start_location.map(|x| x.latitude),
+            leg.end_location.map(|x| x.longitude),
+            leg.end_location.map(|x| x.latitude),
+            leg.operator,
+            leg.booking_reference,
+            details.service_number,
+            details.coach,
+            details.seat,
+            details.platform,
+            details.vehicle,
+            details.cabin,
+            leg.notes,
+            leg.name
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::timeline::Coordinates;
+
+/// A field of a request that can't be stored as given.
+#[derive(Debug)]
+pub struct InvalidField {
+    pub field: &'static str,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
+    }
+}
+
+pub fn invalid(field: &'static str, reason: &'static str) -> InvalidField {
+    InvalidField { field, reason }
+}
+
+/// Trims free text, dropping it when blank. `max` is the length of the column it goes in.
+pub fn optional_text(
+    field: &'static str,
+    value: Option<String>,
+    max: usize,
+) -> Result<Option<String>, InvalidField> {
+    let Some(value) = value
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+    else {
+        return Ok(None);
+    };
+    if value.chars().count() > max {
+        return Err(invalid(field, "is too long"));
+    }
+
+    Ok(Some(value))
+}
+
+pub fn required_text(
+    field: &'static str,
+    value: String,
+    max: usize,
+) -> Result<String, InvalidField> {
+    optional_text(field, Some(value), max)?.ok_or(invalid(field, "must not be empty"))
+}
+
+/// Reads a field of a partial update that can be cleared, telling a missing field (`None`)
+/// apart from an explicit `null` (`Some(None)`). Use it with `#[serde(default)]`.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Checks that a position is on the globe, so a swapped latitude and longitude gets caught
+/// whenever the latitude ends up out of range.
+pub fn coordinates(
+    field: &'static str,
+    value: Option<Coordinates>,
+) -> Result<Option<Coordinates>, InvalidField> {
+    match value {
+        Some(value)
+            if !(-90.0..=90.0).contains(&value.latitude)
+                || !(-180.0..=180.0).contains(&value.longitude) =>
+        {
+            Err(invalid(
+                field,
+                "must have a latitude within ±90 and a longitude within ±180",
+            ))
+        }
+        _ => Ok(value),
+    }
+}
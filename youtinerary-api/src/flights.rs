@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::airports::find_airport;
+use crate::fields::{invalid, optional_text, required_text, InvalidField};
+use crate::items::Item;
+use crate::local_time::{parse_stored_timezone, TimeInputError};
+use crate::timeline::{Coordinates, FlightEntry};
+use crate::{CabinClass, ItemVisibility};
+
+/// What a boarding pass says about a flight beyond its airline, times and airports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlightDetails {
+    /// Marketing flight number, such as `AF1234`.
+    pub flight_number: Option<String>,
+    /// Airline flying the plane when it differs from the one the ticket was bought from.
+    pub operating_carrier: Option<String>,
+    pub departure_terminal: Option<String>,
+    pub departure_gate: Option<String>,
+    pub arrival_terminal: Option<String>,
+    pub arrival_gate: Option<String>,
+    pub cabin_class: Option<CabinClass>,
+    pub aircraft_type: Option<String>,
+    #[serde(default)]
+    pub passengers: Vec<Passenger>,
+}
+
+/// A traveller on a flight, with the booking reference and seat printed on their pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passenger {
+    pub name: String,
+    pub booking_reference: Option<String>,
+    pub seat: Option<String>,
+}
+
+/// Why the fields of a flight being created or updated were rejected.
+#[derive(Debug)]
+pub enum FlightInputError {
+    InvalidField(InvalidField),
+    InvalidTime(&'static str, TimeInputError),
+    InvalidAirport(String),
+    TimezoneMismatch(&'static str, Tz, Tz),
+}
+
+impl From<InvalidField> for FlightInputError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl fmt::Display for FlightInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidField(error) => write!(f, "{}", error),
+            Self::InvalidTime(field, error) => write!(f, "Invalid {} time: {}", field, error),
+            Self::InvalidAirport(code) => write!(
+                f,
+                "{} is not an airport code, use its three letter IATA or four letter ICAO code",
+                code
+            ),
+            Self::TimezoneMismatch(airport, expected, given) => write!(
+                f,
+                "{} is in {}, not {}",
+                airport,
+                expected.name(),
+                given.name()
+            ),
+        }
+    }
+}
+
+impl IntoResponse for FlightInputError {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, self.to_string()).into_response()
+    }
+}
+
+/// An airport as stored on a flight, with its position when it is one of the bundled airports.
+#[derive(Debug, Clone)]
+pub struct FlightAirport {
+    pub code: String,
+    pub location: Option<Coordinates>,
+}
+
+/// Looks up an airport, taking the timezone from it unless one was given. A given timezone
+/// must match the airport's, so typos in either get caught.
+///
+/// The bundled list only covers the larger airports, so other well-formed codes are kept as
+/// they are, without a position, and their timezone is left to the caller.
+pub fn resolve_airport(
+    code: Option<&str>,
+    timezone: Option<Tz>,
+) -> Result<(Option<FlightAirport>, Option<Tz>), FlightInputError> {
+    let Some(code) = code else {
+        return Ok((None, timezone));
+    };
+    let Some(airport) = find_airport(code) else {
+        let code = code.trim();
+        if !matches!(code.len(), 3 | 4) || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(FlightInputError::InvalidAirport(code.to_string()));
+        }
+        let airport = FlightAirport {
+            code: code.to_ascii_uppercase(),
+            location: None,
+        };
+        return Ok((Some(airport), timezone));
+    };
+
+    match timezone {
+        Some(timezone) if timezone != airport.timezone => Err(FlightInputError::TimezoneMismatch(
+            airport.iata,
+            airport.timezone,
+            timezone,
+        )),
+        _ => Ok((
+            Some(FlightAirport {
+                code: airport.iata.to_string(),
+                location: Some(airport.location),
+            }),
+            Some(airport.timezone),
+        )),
+    }
+}
+
+/// Uppercases a code and drops the spaces people type in the middle of it.
+fn code(value: Option<String>) -> Option<String> {
+    value
+        .map(|x| x.split_whitespace().collect::<String>().to_uppercase())
+        .filter(|x| !x.is_empty())
+}
+
+/// A two character airline designator followed by one to four digits and an optional suffix.
+fn flight_number(value: Option<String>) -> Result<Option<String>, InvalidField> {
+    let Some(value) = code(value) else {
+        return Ok(None);
+    };
+    if !value.is_ascii() {
+        return Err(invalid("flight_number", "must look like AF1234"));
+    }
+
+    let (designator, number) = value.split_at(value.len().min(2));
+    let number = number
+        .strip_suffix(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(number);
+    let valid = designator.len() == 2
+        && designator.chars().all(|c| c.is_ascii_alphanumeric())
+        && !designator.chars().all(|c| c.is_ascii_digit())
+        && (1..=4).contains(&number.len())
+        && number.chars().all(|c| c.is_ascii_digit());
+
+    if !valid {
+        return Err(invalid("flight_number", "must look like AF1234"));
+    }
+
+    Ok(Some(value))
+}
+
+/// A row number followed by a seat letter, such as `12A`.
+fn seat(value: Option<String>) -> Result<Option<String>, InvalidField> {
+    let Some(value) = code(value) else {
+        return Ok(None);
+    };
+
+    let row = value
+        .strip_suffix(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or("");
+    if !(1..=3).contains(&row.len()) || !row.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid("seat", "must look like 12A"));
+    }
+
+    Ok(Some(value))
+}
+
+/// Record locators are six characters, but some airlines print longer ticket numbers instead.
+fn booking_reference(value: Option<String>) -> Result<Option<String>, InvalidField> {
+    let Some(value) = code(value) else {
+        return Ok(None);
+    };
+
+    if !(5..=16).contains(&value.len()) || !value.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid(
+            "booking_reference",
+            "must be 5 to 16 letters and digits",
+        ));
+    }
+
+    Ok(Some(value))
+}
+
+impl Passenger {
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        Ok(Passenger {
+            name: required_text("name", self.name, 255)?,
+            booking_reference: booking_reference(self.booking_reference)?,
+            seat: seat(self.seat)?,
+        })
+    }
+}
+
+impl FlightDetails {
+    /// Normalizes codes to their printed form and checks every field fits its column.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        Ok(FlightDetails {
+            flight_number: flight_number(self.flight_number)?,
+            operating_carrier: optional_text("operating_carrier", self.operating_carrier, 255)?,
+            departure_terminal: optional_text("departure_terminal", self.departure_terminal, 16)?,
+            departure_gate: optional_text("departure_gate", self.departure_gate, 16)?,
+            arrival_terminal: optional_text("arrival_terminal", self.arrival_terminal, 16)?,
+            arrival_gate: optional_text("arrival_gate", self.arrival_gate, 16)?,
+            cabin_class: self.cabin_class,
+            aircraft_type: optional_text("aircraft_type", self.aircraft_type, 64)?,
+            passengers: self
+                .passengers
+                .into_iter()
+                .map(Passenger::validated)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Stores the passengers of a flight in the order they were given.
+pub async fn insert_passengers(
+    connection: &mut PgConnection,
+    flight_id: i32,
+    passengers: &[Passenger],
+) -> sqlx::Result<()> {
+    for (position, passenger) in passengers.iter().enumerate() {
+        sqlx::query!(
+            r#"
+                insert into flight_passengers (flight_id, position, name, booking_reference, seat)
+                values ($1, $2, $3, $4, $5)
+            "#,
+            flight_id,
+            position as i32,
+            passenger.name,
+            passenger.booking_reference,
+            passenger.seat
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Loads the passengers of several flights at once, keyed by flight id.
+pub async fn get_passengers(
+    connection: &mut PgConnection,
+    flight_ids: &[i32],
+) -> sqlx::Result<HashMap<i32, Vec<Passenger>>> {
+    let rows = sqlx::query!(
+        r#"
+            select flight_id, name, booking_reference, seat
+            from flight_passengers
+            where flight_id = any($1)
+            order by flight_id, position
+        "#,
+        flight_ids
+    )
+    .fetch_all(connection)
+    .await?;
+
+    let mut passengers: HashMap<i32, Vec<Passenger>> = HashMap::new();
+    for row in rows {
+        passengers
+            .entry(row.flight_id)
+            .or_default()
+            .push(Passenger {
+                name: row.name,
+                booking_reference: row.booking_reference,
+                seat: row.seat,
+            });
+    }
+
+    Ok(passengers)
+}
+
+/// Replaces the passengers of a flight with `passengers`.
+pub async fn replace_passengers(
+    connection: &mut PgConnection,
+    flight_id: i32,
+    passengers: &[Passenger],
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "delete from flight_passengers where flight_id = $1",
+        flight_id
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    insert_passengers(connection, flight_id, passengers).await
+}
+
+/// Loads the flights of an itinerary in departure order, or only `flight_id` when given.
+pub async fn get_flights(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    flight_id: Option<i32>,
+) -> sqlx::Result<Vec<FlightEntry>> {
+    let flights = sqlx::query!(
+        r#"
+            select
+                f.id,
+                f.airline,
+                f.confirmation_code,
+                f.departure_time,
+                f.arrival_time,
+                f.departure_timezone,
+                f.arrival_timezone,
+                f.departure_airport,
+                f.arrival_airport,
+                f.departure_location[0] as departure_longitude,
+                f.departure_location[1] as departure_latitude,
+                f.arrival_location[0] as arrival_longitude,
+                f.arrival_location[1] as arrival_latitude,
+                f.flight_number,
+                f.operating_carrier,
+                f.departure_terminal,
+                f.departure_gate,
+                f.arrival_terminal,
+                f.arrival_gate,
+                f.cabin_class as "cabin_class: CabinClass",
+                f.aircraft_type,
+                f.notes,
+                i.id as item_id,
+                i.position,
+                i.visibility as "visibility: ItemVisibility"
+            from flights f
+                join itinerary_items i on i.id = f.itinerary_item_id
+            where i.itinerary_id = $1
+                and ($2::integer is null or f.id = $2)
+            order by f.departure_time, i.position nulls last, f.id
+        "#,
+        itinerary_id,
+        flight_id
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    let flight_ids: Vec<i32> = flights.iter().map(|x| x.id).collect();
+    let mut passengers = get_passengers(connection, &flight_ids).await?;
+
+    Ok(flights
+        .into_iter()
+        .map(|flight| FlightEntry {
+            id: flight.id,
+            airline: flight.airline,
+            confirmation_code: flight.confirmation_code,
+            departure_time: flight.departure_time,
+            arrival_time: flight.arrival_time,
+            departure_timezone: parse_stored_timezone(flight.departure_timezone),
+            arrival_timezone: parse_stored_timezone(flight.arrival_timezone),
+            departure_airport: flight.departure_airport,
+            arrival_airport: flight.arrival_airport,
+            departure_location: Coordinates::from_point(
+                flight.departure_longitude,
+                flight.departure_latitude,
+            ),
+            arrival_location: Coordinates::from_point(
+                flight.arrival_longitude,
+                flight.arrival_latitude,
+            ),
+            details: Box::new(FlightDetails {
+                flight_number: flight.flight_number,
+                operating_carrier: flight.operating_carrier,
+                departure_terminal: flight.departure_terminal,
+                departure_gate: flight.departure_gate,
+                arrival_terminal: flight.arrival_terminal,
+                arrival_gate: flight.arrival_gate,
+                cabin_class: flight.cabin_class,
+                aircraft_type: flight.aircraft_type,
+                passengers: passengers.remove(&flight.id).unwrap_or_default(),
+            }),
+            item: Item {
+                id: flight.item_id,
+                position: flight.position,
+                visibility: flight.visibility,
+            },
+            notes: flight.notes,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_airports_fill_in_their_timezone_and_position() {
+        let (airport, timezone) = resolve_airport(Some("lis"), None).unwrap();
+        let airport = airport.unwrap();
+        assert_eq!(airport.code, "LIS");
+        assert!(airport.location.is_some());
+        assert_eq!(timezone, Some(Tz::Europe__Lisbon));
+    }
+
+    #[test]
+    fn known_airports_reject_another_timezone() {
+        assert!(matches!(
+            resolve_airport(Some("LIS"), Some(Tz::Europe__Madrid)),
+            Err(FlightInputError::TimezoneMismatch(
+                "LIS",
+                Tz::Europe__Lisbon,
+                Tz::Europe__Madrid
+            ))
+        ));
+    }
+
+    #[test]
+    fn unknown_airports_are_kept_without_autofill() {
+        let (airport, timezone) = resolve_airport(Some("zzv"), None).unwrap();
+        let airport = airport.unwrap();
+        assert_eq!(airport.code, "ZZV");
+        assert!(airport.location.is_none());
+        assert_eq!(timezone, None);
+
+        let (_, timezone) = resolve_airport(Some("ZZVX"), Some(Tz::Pacific__Fiji)).unwrap();
+        assert_eq!(timezone, Some(Tz::Pacific__Fiji));
+    }
+
+    #[test]
+    fn malformed_airport_codes_are_rejected() {
+        for code in ["", "ZZ", "ZZVXY", "Z-V", "ZŻV"] {
+            assert!(
+                matches!(
+                    resolve_airport(Some(code), None),
+                    Err(FlightInputError::InvalidAirport(_))
+                ),
+                "{:?} should be rejected",
+                code
+            );
+        }
+    }
+
+    fn number(value: &str) -> Result<Option<String>, InvalidField> {
+        flight_number(Some(value.to_string()))
+    }
+
+    #[test]
+    fn flight_numbers_are_normalized() {
+        assert_eq!(number("af 1234").unwrap().as_deref(), Some("AF1234"));
+        assert_eq!(number("U2 81").unwrap().as_deref(), Some("U281"));
+        assert_eq!(number("9W7a").unwrap().as_deref(), Some("9W7A"));
+        assert_eq!(number("  ").unwrap(), None);
+        assert_eq!(flight_number(None).unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_flight_numbers_are_rejected() {
+        for value in ["A", "AF", "12345", "AF12345", "AF12B3", "A-123", "AF1234BC"] {
+            assert!(number(value).is_err(), "{:?} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn non_ascii_flight_numbers_are_rejected_without_panicking() {
+        for value in ["É1234", "AÉ123", "ÅÅ", "Ä", "AF１２３"] {
+            assert!(number(value).is_err(), "{:?} should be rejected", value);
+        }
+    }
+}
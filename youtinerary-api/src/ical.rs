@@ -0,0 +1,1018 @@
+use std::collections::BTreeMap;
+
+use std::fmt;
+
+use chrono::{
+    DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+use crate::timeline::{ActivityEntry, Coordinates, FlightEntry, StayEntry, TimelineEntry};
+
+const PRODID: &str = "-//youtinerary//youtinerary-api//EN";
+
+/// Renders an RFC 5545 calendar with one event per flight, stay and activity of the timeline.
+///
+/// Flights keep their stored timezones as TZIDs, each described by a VTIMEZONE covering the
+/// flights that use it. Stays and untimed activities become all-day events, activities with a
+/// start time use floating local times.
+pub fn itinerary_calendar(name: &str, timeline: &[TimelineEntry], now: DateTime<Utc>) -> String {
+    let mut calendar = ContentLines::default();
+    calendar.line("BEGIN:VCALENDAR");
+    calendar.line("VERSION:2.0");
+    calendar.property("PRODID", PRODID);
+    calendar.line("CALSCALE:GREGORIAN");
+    calendar.line("METHOD:PUBLISH");
+    calendar.text("X-WR-CALNAME", name);
+    calendar.line("REFRESH-INTERVAL;VALUE=DURATION:PT1H");
+    calendar.line("X-PUBLISHED-TTL:PT1H");
+
+    let mut zones: BTreeMap<String, (Tz, Vec<DateTime<Utc>>)> = BTreeMap::new();
+    for entry in timeline {
+        if let TimelineEntry::Flight(flight) = entry {
+            for (instant, timezone) in [
+                (flight.departure_time, flight.departure_timezone),
+                (flight.arrival_time, flight.arrival_timezone),
+            ] {
+                if let Some(timezone) = zone_for_tzid(timezone) {
+                    zones
+                        .entry(timezone.name().to_string())
+                        .or_insert_with(|| (timezone, Vec::new()))
+                        .1
+                        .push(instant);
+                }
+            }
+        }
+    }
+    for (timezone, instants) in zones.values() {
+        let from = instants.iter().min().copied().unwrap_or(now);
+        let to = instants.iter().max().copied().unwrap_or(now);
+        write_timezone(&mut calendar, *timezone, from, to);
+    }
+
+    let stamp = utc_stamp(now);
+    for entry in timeline {
+        match entry {
+            TimelineEntry::Flight(flight) => {
+                calendar.line("BEGIN:VEVENT");
+                calendar.property("UID", &format!("flight-{}@youtinerary", flight.id));
+                calendar.property("DTSTAMP", &stamp);
+                calendar.line(&date_time_property(
+                    "DTSTART",
+                    flight.departure_time,
+                    flight.departure_timezone,
+                ));
+                calendar.line(&date_time_property(
+                    "DTEND",
+                    flight.arrival_time,
+                    flight.arrival_timezone,
+                ));
+                calendar.text("SUMMARY", &format!("Flight with {}", flight.title()));
+                calendar.text(
+                    "DESCRIPTION",
+                    &describe(&boarding_details(flight), &flight.notes),
+                );
+                calendar.line("END:VEVENT");
+            }
+            TimelineEntry::Stay(stay) => {
+                calendar.line("BEGIN:VEVENT");
+                calendar.property("UID", &format!("stay-{}@youtinerary", stay.id));
+                calendar.property("DTSTAMP", &stamp);
+                write_all_day(&mut calendar, stay.start_date, stay.end_date);
+                calendar.text("SUMMARY", &stay.summary);
+                if let Some(address) = &stay.address {
+                    calendar.text("LOCATION", address);
+                }
+                if let Some(location) = stay.location {
+                    calendar.property(
+                        "GEO",
+                        &format!("{};{}", location.latitude, location.longitude),
+                    );
+                }
+                let description = match booking_details(stay) {
+                    details if details.is_empty() => stay.notes.clone(),
+                    details => describe(&details, &stay.notes),
+                };
+                if !description.is_empty() {
+                    calendar.text("DESCRIPTION", &description);
+                }
+                calendar.line("TRANSP:TRANSPARENT");
+                calendar.line("END:VEVENT");
+            }
+            TimelineEntry::Activity(activity) => {
+                calendar.line("BEGIN:VEVENT");
+                calendar.property("UID", &format!("activity-{}@youtinerary", activity.id));
+                calendar.property("DTSTAMP", &stamp);
+                match activity.start_time {
+                    Some(start_time) => write_floating(&mut calendar, activity, start_time),
+                    None => write_all_day(&mut calendar, activity.start_date, activity.end_date),
+                }
+                calendar.text("SUMMARY", &activity.summary);
+                calendar.text("CATEGORIES", activity.category.label());
+                if let Some(address) = &activity.address {
+                    calendar.text("LOCATION", address);
+                }
+                if let Some(location) = activity.location {
+                    calendar.property(
+                        "GEO",
+                        &format!("{};{}", location.latitude, location.longitude),
+                    );
+                }
+                let description = match activity_booking(activity) {
+                    details if details.is_empty() => activity.notes.clone(),
+                    details => describe(&details, &activity.notes),
+                };
+                if !description.is_empty() {
+                    calendar.text("DESCRIPTION", &description);
+                }
+                calendar.line("END:VEVENT");
+            }
+            TimelineEntry::TravelLeg(_) => {}
+        }
+    }
+
+    calendar.line("END:VCALENDAR");
+    calendar.0
+}
+
+/// Content lines joined with CRLF and folded at 75 octets, as RFC 5545 section 3.1 requires.
+#[derive(Default)]
+struct ContentLines(String);
+
+impl ContentLines {
+    fn line(&mut self, line: &str) {
+        let mut rest = line;
+        let mut limit = 75;
+        loop {
+            let mut split = rest.len().min(limit);
+            while !rest.is_char_boundary(split) {
+                split -= 1;
+            }
+            self.0.push_str(&rest[..split]);
+            self.0.push_str("\r\n");
+            rest = &rest[split..];
+            if rest.is_empty() {
+                break;
+            }
+            self.0.push(' ');
+            limit = 74;
+        }
+    }
+
+    fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.property(name, &escape_text(value));
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn describe(summary: &str, notes: &str) -> String {
+    if notes.is_empty() {
+        summary.to_string()
+    } else {
+        format!("{}\n\n{}", summary, notes)
+    }
+}
+
+/// The parts of a boarding pass worth having at hand in a calendar, one per line.
+fn boarding_details(flight: &FlightEntry) -> String {
+    let details = &flight.details;
+    let mut lines = vec![format!("Confirmation code: {}", flight.confirmation_code)];
+    for (label, value) in [
+        ("Operated by", &details.operating_carrier),
+        ("Departure terminal", &details.departure_terminal),
+        ("Departure gate", &details.departure_gate),
+        ("Arrival terminal", &details.arrival_terminal),
+        ("Arrival gate", &details.arrival_gate),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("{}: {}", label, value));
+        }
+    }
+    for passenger in &details.passengers {
+        let mut line = passenger.name.clone();
+        if let Some(seat) = &passenger.seat {
+            line.push_str(&format!(", seat {}", seat));
+        }
+        if let Some(booking_reference) = &passenger.booking_reference {
+            line.push_str(&format!(", booking {}", booking_reference));
+        }
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+/// Check-in and check-out times and the confirmation number of a stay, one per line.
+fn booking_details(stay: &StayEntry) -> String {
+    let mut lines = Vec::new();
+    for (label, time) in [
+        ("Check-in", stay.check_in_time),
+        ("Check-out", stay.check_out_time),
+    ] {
+        if let Some(time) = time {
+            lines.push(format!("{}: {}", label, time.format("%H:%M")));
+        }
+    }
+    if let Some(confirmation_number) = &stay.confirmation_number {
+        lines.push(format!("Confirmation number: {}", confirmation_number));
+    }
+
+    lines.join("\n")
+}
+
+/// The booking reference and cost of an activity, one per line.
+fn activity_booking(activity: &ActivityEntry) -> String {
+    let mut lines = Vec::new();
+    if let Some(booking_reference) = &activity.booking_reference {
+        lines.push(format!("Booking reference: {}", booking_reference));
+    }
+    if let Some(cost) = &activity.cost {
+        lines.push(format!("Cost: {} {}", cost.amount, cost.currency));
+    }
+
+    lines.join("\n")
+}
+
+/// Timezones other than UTC get a TZID, UTC times are written in the `Z` form instead.
+fn zone_for_tzid(timezone: Option<Tz>) -> Option<Tz> {
+    timezone.filter(|timezone| *timezone != Tz::UTC)
+}
+
+fn utc_stamp(instant: DateTime<Utc>) -> String {
+    instant.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn date_time_property(name: &str, instant: DateTime<Utc>, timezone: Option<Tz>) -> String {
+    match zone_for_tzid(timezone) {
+        Some(timezone) => format!(
+            "{};TZID={}:{}",
+            name,
+            timezone.name(),
+            instant.with_timezone(&timezone).format("%Y%m%dT%H%M%S")
+        ),
+        None => format!("{}:{}", name, utc_stamp(instant)),
+    }
+}
+
+/// All-day events end on the day after their last day.
+fn write_all_day(calendar: &mut ContentLines, start_date: NaiveDate, end_date: NaiveDate) {
+    let end_date = end_date.max(start_date) + Duration::days(1);
+    calendar.property(
+        "DTSTART;VALUE=DATE",
+        &start_date.format("%Y%m%d").to_string(),
+    );
+    calendar.property("DTEND;VALUE=DATE", &end_date.format("%Y%m%d").to_string());
+}
+
+/// Activities only know their local times, so they are written as floating times that calendar
+/// apps show as is in any zone. Without an end time the event is just its start.
+fn write_floating(calendar: &mut ContentLines, activity: &ActivityEntry, start_time: NaiveTime) {
+    let floating =
+        |date: NaiveDate, time: NaiveTime| date.and_time(time).format("%Y%m%dT%H%M%S").to_string();
+
+    calendar.property("DTSTART", &floating(activity.start_date, start_time));
+    if let Some(end_time) = activity.end_time {
+        calendar.property("DTEND", &floating(activity.end_date, end_time));
+    }
+}
+
+fn format_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+/// Writes a VTIMEZONE for `timezone` with every offset change between `from` and `to`.
+///
+/// chrono-tz does not expose its transition table, so the offset is sampled once a day and
+/// each change is narrowed down to the second it happens at.
+fn write_timezone(
+    calendar: &mut ContentLines,
+    timezone: Tz,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) {
+    let offset_at =
+        |instant: DateTime<Utc>| timezone.offset_from_utc_datetime(&instant.naive_utc());
+
+    let mut at = from - Duration::days(1);
+    let mut current = offset_at(at);
+    let mut observances = vec![(at, current, current)];
+    while at < to {
+        let next = at + Duration::days(1);
+        let offset = offset_at(next);
+        if offset != current {
+            let (mut before, mut after) = (at, next);
+            while after - before > Duration::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if offset_at(middle) == current {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            let changed_to = offset_at(after);
+            observances.push((after, current, changed_to));
+            current = changed_to;
+        }
+        at = next;
+    }
+
+    calendar.line("BEGIN:VTIMEZONE");
+    calendar.property("TZID", timezone.name());
+    for (starts_at, offset_from, offset_to) in observances {
+        let kind = if offset_to.dst_offset() == Duration::zero() {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        calendar.property("BEGIN", kind);
+        calendar.property(
+            "DTSTART",
+            &starts_at
+                .with_timezone(&offset_from.fix())
+                .format("%Y%m%dT%H%M%S")
+                .to_string(),
+        );
+        calendar.property("TZOFFSETFROM", &format_offset(offset_from.fix()));
+        calendar.property("TZOFFSETTO", &format_offset(offset_to.fix()));
+        calendar.text("TZNAME", offset_to.abbreviation());
+        calendar.property("END", kind);
+    }
+    calendar.line("END:VTIMEZONE");
+}
+
+/// A VEVENT read from an uploaded calendar, reduced to the properties itineraries can use.
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub location: Option<String>,
+    pub geo: Option<Coordinates>,
+    pub categories: Vec<String>,
+    pub start: EventTime,
+    pub end: EventTime,
+    /// Whether the event repeats. Only its first occurrence is read.
+    pub recurring: bool,
+    /// Whether the event overrides one occurrence of a recurring event with the same UID.
+    pub is_override: bool,
+    /// Things that were read with a fallback, such as a TZID that is not an IANA name.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTime {
+    Date(NaiveDate),
+    /// An instant, with the timezone it was written in when that zone is known.
+    DateTime {
+        instant: DateTime<Utc>,
+        timezone: Option<Tz>,
+    },
+}
+
+impl EventTime {
+    /// The calendar date in the event's own timezone, or UTC when it has none.
+    pub fn local_date(&self) -> NaiveDate {
+        match self {
+            Self::Date(date) => *date,
+            Self::DateTime { instant, timezone } => instant
+                .with_timezone(&timezone.unwrap_or(Tz::UTC))
+                .date_naive(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseCalendarError(String);
+
+impl fmt::Display for ParseCalendarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseCalendarError {}
+
+/// Reads the VEVENTs of an RFC 5545 calendar.
+///
+/// Properties of nested components such as VALARM are ignored, and a missing UID is derived
+/// from the event's start and summary so that re-importing the same file stays stable.
+pub fn parse_events(input: &str) -> Result<Vec<CalendarEvent>, ParseCalendarError> {
+    let lines = unfold(input);
+    let mut lines = lines.iter().filter(|line| !line.trim().is_empty());
+
+    match lines.next() {
+        Some(line) if line.trim().eq_ignore_ascii_case("BEGIN:VCALENDAR") => {}
+        _ => {
+            return Err(ParseCalendarError(
+                "The file is not an iCalendar file, it must start with BEGIN:VCALENDAR".into(),
+            ))
+        }
+    }
+
+    let mut components = vec!["VCALENDAR".to_string()];
+    let mut event: Option<EventBuilder> = None;
+    let mut events = Vec::new();
+    for line in lines {
+        let property = ContentLine::parse(line)
+            .ok_or_else(|| ParseCalendarError(format!("Malformed line: {}", line)))?;
+
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if component == "VEVENT" && components.len() == 1 {
+                    event = Some(EventBuilder::default());
+                }
+                components.push(component);
+            }
+            "END" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if components.pop().as_deref() != Some(component.as_str()) {
+                    return Err(ParseCalendarError(format!(
+                        "END:{} does not close the component it is in",
+                        component
+                    )));
+                }
+                if component == "VEVENT" {
+                    if let Some(builder) = event.take() {
+                        events.push(builder.build(events.len() + 1)?);
+                    }
+                }
+            }
+            _ if components.len() == 2 => {
+                if let Some(builder) = event.as_mut() {
+                    builder.set(property);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => last.push_str(continued),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+struct ContentLine<'a> {
+    name: String,
+    params: Vec<(String, String)>,
+    value: &'a str,
+}
+
+impl<'a> ContentLine<'a> {
+    /// Splits `NAME;PARAM=value;PARAM="quoted:value":VALUE`, ignoring separators inside quotes.
+    fn parse(line: &'a str) -> Option<Self> {
+        let mut quoted = false;
+        let mut separators = Vec::new();
+        let mut colon = None;
+        for (index, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => separators.push(index),
+                ':' if !quoted => {
+                    colon = Some(index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let colon = colon?;
+
+        let mut bounds = separators;
+        bounds.push(colon);
+        let name = line[..bounds[0]].trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        let params = bounds
+            .windows(2)
+            .filter_map(|window| {
+                let (key, value) = line[window[0] + 1..window[1]].split_once('=')?;
+                Some((
+                    key.trim().to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+
+        Some(Self {
+            name,
+            params,
+            value: &line[colon + 1..],
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Default)]
+struct EventBuilder {
+    uid: Option<String>,
+    summary: String,
+    description: String,
+    location: Option<String>,
+    geo: Option<Coordinates>,
+    categories: Vec<String>,
+    start: Option<EventTime>,
+    end: Option<EventTime>,
+    duration: Option<Duration>,
+    recurring: bool,
+    is_override: bool,
+    warnings: Vec<String>,
+}
+
+impl EventBuilder {
+    fn set(&mut self, property: ContentLine) {
+        match property.name.as_str() {
+            "UID" => self.uid = Some(property.value.trim().to_string()),
+            "SUMMARY" => self.summary = unescape_text(property.value),
+            "DESCRIPTION" => self.description = unescape_text(property.value),
+            "LOCATION" => self.location = Some(unescape_text(property.value)),
+            "GEO" => {
+                self.geo = property
+                    .value
+                    .split_once(';')
+                    .and_then(|(latitude, longitude)| {
+                        Some(Coordinates {
+                            latitude: latitude.trim().parse().ok()?,
+                            longitude: longitude.trim().parse().ok()?,
+                        })
+                    })
+            }
+            "CATEGORIES" => self.categories.extend(
+                split_unescaped(property.value, ',')
+                    .into_iter()
+                    .map(unescape_text),
+            ),
+            "DTSTART" => self.start = self.parse_time(&property),
+            "DTEND" => self.end = self.parse_time(&property),
+            "DURATION" => self.duration = parse_duration(property.value.trim()),
+            "RRULE" | "RDATE" => self.recurring = true,
+            "RECURRENCE-ID" => self.is_override = true,
+            _ => {}
+        }
+    }
+
+    fn parse_time(&mut self, property: &ContentLine) -> Option<EventTime> {
+        let value = property.value.trim();
+        if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(EventTime::Date);
+        }
+
+        if let Some(value) = value.strip_suffix(['Z', 'z']) {
+            let instant = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+            return Some(EventTime::DateTime {
+                instant: instant.and_utc(),
+                timezone: None,
+            });
+        }
+
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        let timezone = match property.param("TZID") {
+            Some(tzid) => match tzid.trim_start_matches('/').parse::<Tz>() {
+                Ok(timezone) => Some(timezone),
+                Err(_) => {
+                    self.warnings.push(format!(
+                        "Unknown timezone {}, the time was read as UTC",
+                        tzid
+                    ));
+                    None
+                }
+            },
+            None => None,
+        };
+        let Some(timezone) = timezone else {
+            return Some(EventTime::DateTime {
+                instant: local.and_utc(),
+                timezone: None,
+            });
+        };
+
+        // A wall clock time skipped by a daylight saving change is read an hour later.
+        let instant = timezone
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                timezone
+                    .from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })?;
+        Some(EventTime::DateTime {
+            instant: instant.with_timezone(&Utc),
+            timezone: Some(timezone),
+        })
+    }
+
+    fn build(mut self, position: usize) -> Result<CalendarEvent, ParseCalendarError> {
+        let start = self.start.ok_or_else(|| {
+            ParseCalendarError(format!(
+                "Event {} has no readable DTSTART",
+                self.uid.as_deref().unwrap_or(&position.to_string())
+            ))
+        })?;
+
+        let end = match (self.end, self.duration, start) {
+            (Some(end), _, _) => end,
+            (None, Some(duration), EventTime::Date(date)) => {
+                EventTime::Date(date + Duration::days(duration.num_days()))
+            }
+            (None, Some(duration), EventTime::DateTime { instant, timezone }) => {
+                EventTime::DateTime {
+                    instant: instant + duration,
+                    timezone,
+                }
+            }
+            // Without an end, an all-day event lasts its one day and a timed one is a moment.
+            (None, None, EventTime::Date(date)) => EventTime::Date(date + Duration::days(1)),
+            (None, None, start) => start,
+        };
+
+        let uid = match self.uid.take().filter(|uid| !uid.is_empty()) {
+            Some(uid) => uid,
+            None => {
+                self.warnings.push(
+                    "The event has no UID, one was made up from its start and summary".into(),
+                );
+                format!("{:?}-{}", start, self.summary)
+            }
+        };
+
+        Ok(CalendarEvent {
+            uid,
+            summary: self.summary,
+            description: self.description,
+            location: self.location.filter(|location| !location.is_empty()),
+            geo: self.geo,
+            categories: self.categories,
+            start,
+            end,
+            recurring: self.recurring,
+            is_override: self.is_override,
+            warnings: self.warnings,
+        })
+    }
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => {
+                parts.push(&value[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Reads durations such as `P1W`, `PT1H30M` or `-P2DT12H`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(amount),
+                    ('D', false) => Duration::days(amount),
+                    ('H', true) => Duration::hours(amount),
+                    ('M', true) => Duration::minutes(amount),
+                    ('S', true) => Duration::seconds(amount),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(if negative { -total } else { total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instant(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn long_lines_are_folded_at_75_octets_on_character_boundaries() {
+        let value = "Überfahrt nach Ærø über Svendborg, dann weiter mit dem Rad ".repeat(4);
+        let mut calendar = ContentLines::default();
+        calendar.text("SUMMARY", &value);
+
+        let lines: Vec<&str> = calendar.0.split_terminator("\r\n").collect();
+        assert!(lines.len() > 1);
+        for (index, line) in lines.iter().enumerate() {
+            assert!(line.len() <= 75, "line {} is {} octets", index, line.len());
+            assert_eq!(index > 0, line.starts_with(' '));
+        }
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| if index == 0 { *line } else { &line[1..] })
+            .collect();
+        assert_eq!(unfolded, format!("SUMMARY:{}", escape_text(&value)));
+    }
+
+    #[test]
+    fn folding_moves_a_character_straddling_the_limit_to_the_next_line() {
+        let mut calendar = ContentLines::default();
+        calendar.text("SUMMARY", &format!("{}é", "a".repeat(66)));
+
+        assert_eq!(calendar.0, format!("SUMMARY:{}\r\n é\r\n", "a".repeat(66)));
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        let mut calendar = ContentLines::default();
+        calendar.line(&"X".repeat(75));
+        assert_eq!(calendar.0, format!("{}\r\n", "X".repeat(75)));
+    }
+
+    #[test]
+    fn text_values_are_escaped() {
+        assert_eq!(
+            escape_text("Gate B; row 3, seat\\window\r\nBring snacks"),
+            r"Gate B\; row 3\, seat\\window\nBring snacks"
+        );
+    }
+
+    #[test]
+    fn timezones_describe_each_transition_in_range() {
+        let mut calendar = ContentLines::default();
+        write_timezone(
+            &mut calendar,
+            Tz::Europe__London,
+            instant("2026-03-01T12:00:00Z"),
+            instant("2026-11-01T12:00:00Z"),
+        );
+
+        assert_eq!(
+            calendar.0.split_terminator("\r\n").collect::<Vec<_>>(),
+            [
+                "BEGIN:VTIMEZONE",
+                "TZID:Europe/London",
+                "BEGIN:STANDARD",
+                "DTSTART:20260228T120000",
+                "TZOFFSETFROM:+0000",
+                "TZOFFSETTO:+0000",
+                "TZNAME:GMT",
+                "END:STANDARD",
+                "BEGIN:DAYLIGHT",
+                "DTSTART:20260329T010000",
+                "TZOFFSETFROM:+0000",
+                "TZOFFSETTO:+0100",
+                "TZNAME:BST",
+                "END:DAYLIGHT",
+                "BEGIN:STANDARD",
+                "DTSTART:20261025T020000",
+                "TZOFFSETFROM:+0100",
+                "TZOFFSETTO:+0000",
+                "TZNAME:GMT",
+                "END:STANDARD",
+                "END:VTIMEZONE",
+            ]
+        );
+    }
+
+    #[test]
+    fn timezones_without_transitions_have_one_observance() {
+        let mut calendar = ContentLines::default();
+        write_timezone(
+            &mut calendar,
+            Tz::Asia__Kolkata,
+            instant("2026-03-01T12:00:00Z"),
+            instant("2026-11-01T12:00:00Z"),
+        );
+
+        assert_eq!(calendar.0.matches("BEGIN:STANDARD").count(), 1);
+        assert!(!calendar.0.contains("DAYLIGHT"));
+        assert!(calendar.0.contains("TZOFFSETTO:+0530\r\n"));
+    }
+
+    fn calendar(events: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n",
+            events
+        )
+    }
+
+    fn parse_one(event: &str) -> CalendarEvent {
+        let mut events = parse_events(&calendar(&format!(
+            "BEGIN:VEVENT\r\n{}END:VEVENT\r\n",
+            event
+        )))
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        events.remove(0)
+    }
+
+    #[test]
+    fn continuation_lines_are_unfolded() {
+        assert_eq!(
+            unfold("SUMMARY:Flight to\r\n  Lisbon\r\n\tvia Porto\r\nUID:1\n"),
+            ["SUMMARY:Flight to Lisbonvia Porto", "UID:1", ""]
+        );
+    }
+
+    #[test]
+    fn folded_properties_are_read_whole() {
+        let event =
+            parse_one("UID:folded\r\nDTSTART:20260501T080000Z\r\nSUMMARY:Ferry to\r\n  Ærø\r\n");
+        assert_eq!(event.summary, "Ferry to Ærø");
+    }
+
+    #[test]
+    fn escaped_text_is_unescaped() {
+        let event = parse_one(concat!(
+            "UID:escaped\r\n",
+            "DTSTART:20260501T080000Z\r\n",
+            r"SUMMARY:Dinner\, drinks\; dancing",
+            "\r\n",
+            r"DESCRIPTION:Table 4\nAsk for Ana\\Bea",
+            "\r\n",
+            r"CATEGORIES:Food\, local,Evening",
+            "\r\n",
+        ));
+        assert_eq!(event.summary, "Dinner, drinks; dancing");
+        assert_eq!(event.description, "Table 4\nAsk for Ana\\Bea");
+        assert_eq!(event.categories, ["Food, local", "Evening"]);
+    }
+
+    #[test]
+    fn tzid_parameters_resolve_local_times() {
+        let event = parse_one(concat!(
+            "UID:tz\r\n",
+            "DTSTART;TZID=\"Europe/Lisbon\":20260701T093000\r\n",
+            "DTEND;TZID=America/New_York:20260701T123000\r\n",
+        ));
+        assert_eq!(
+            event.start,
+            EventTime::DateTime {
+                instant: instant("2026-07-01T08:30:00Z"),
+                timezone: Some(Tz::Europe__Lisbon),
+            }
+        );
+        assert_eq!(
+            event.end,
+            EventTime::DateTime {
+                instant: instant("2026-07-01T16:30:00Z"),
+                timezone: Some(Tz::America__New_York),
+            }
+        );
+        assert!(event.warnings.is_empty());
+    }
+
+    #[test]
+    fn unknown_tzids_are_read_as_utc_with_a_warning() {
+        let event = parse_one("UID:tz\r\nDTSTART;TZID=Lisbon Time:20260701T093000\r\n");
+        assert_eq!(
+            event.start,
+            EventTime::DateTime {
+                instant: instant("2026-07-01T09:30:00Z"),
+                timezone: None,
+            }
+        );
+        assert_eq!(event.warnings.len(), 1);
+    }
+
+    #[test]
+    fn all_day_events_keep_their_dates() {
+        let event = parse_one(concat!(
+            "UID:stay\r\n",
+            "DTSTART;VALUE=DATE:20260701\r\n",
+            "DTEND;VALUE=DATE:20260704\r\n",
+        ));
+        assert_eq!(
+            event.start,
+            EventTime::Date(NaiveDate::from_ymd_opt(2026, 7, 1).unwrap())
+        );
+        assert_eq!(
+            event.end,
+            EventTime::Date(NaiveDate::from_ymd_opt(2026, 7, 4).unwrap())
+        );
+    }
+
+    #[test]
+    fn a_missing_dtend_falls_back_to_duration_or_start() {
+        let all_day = parse_one("UID:a\r\nDTSTART;VALUE=DATE:20260701\r\n");
+        assert_eq!(
+            all_day.end,
+            EventTime::Date(NaiveDate::from_ymd_opt(2026, 7, 2).unwrap())
+        );
+
+        let moment = parse_one("UID:b\r\nDTSTART:20260701T093000Z\r\n");
+        assert_eq!(moment.end, moment.start);
+
+        let with_duration = parse_one("UID:c\r\nDTSTART:20260701T093000Z\r\nDURATION:PT1H30M\r\n");
+        assert_eq!(
+            with_duration.end,
+            EventTime::DateTime {
+                instant: instant("2026-07-01T11:00:00Z"),
+                timezone: None,
+            }
+        );
+    }
+
+    #[test]
+    fn nested_components_do_not_leak_into_events() {
+        let event = parse_one(concat!(
+            "UID:alarm\r\n",
+            "DTSTART:20260701T093000Z\r\n",
+            "SUMMARY:Museum\r\n",
+            "BEGIN:VALARM\r\n",
+            "DESCRIPTION:Reminder\r\n",
+            "END:VALARM\r\n",
+        ));
+        assert_eq!(event.summary, "Museum");
+        assert_eq!(event.description, "");
+    }
+
+    #[test]
+    fn malformed_calendars_are_rejected() {
+        assert!(parse_events("BEGIN:VEVENT\r\nEND:VEVENT\r\n").is_err());
+        assert!(parse_events(&calendar("BEGIN:VEVENT\r\nUID:x\r\nEND:VEVENT\r\n")).is_err());
+        assert!(parse_events(&calendar("BEGIN:VEVENT\r\nEND:VTODO\r\n")).is_err());
+        assert!(parse_events(&calendar("not a content line\r\n")).is_err());
+    }
+}
@@ -0,0 +1,1217 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use serde_json::Value;
+
+use crate::airports::find_airport;
+use crate::flights::Passenger;
+use crate::stays::StayFields;
+use crate::timeline::Coordinates;
+
+/// A confirmation email, reduced to what the parsers read.
+#[derive(Debug, Default)]
+pub struct Message {
+    pub subject: Option<String>,
+    /// Text of the message, read from its HTML when it has no plain text part.
+    pub text: String,
+    /// Every HTML part, where senders put their schema.org markup.
+    pub html: Vec<String>,
+}
+
+/// A booking found in a message, before it is checked like one a user enters.
+#[derive(Debug, Clone)]
+pub enum Booking {
+    Flight(FlightBooking),
+    Stay(StayFields),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FlightBooking {
+    pub airline: String,
+    pub confirmation_code: String,
+    pub flight_number: Option<String>,
+    /// IATA code of the departure airport.
+    pub departure_airport: Option<String>,
+    pub arrival_airport: Option<String>,
+    pub departure_time: Option<BookingTime>,
+    pub arrival_time: Option<BookingTime>,
+    pub departure_terminal: Option<String>,
+    pub departure_gate: Option<String>,
+    pub arrival_terminal: Option<String>,
+    pub arrival_gate: Option<String>,
+    pub passengers: Vec<Passenger>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookingTime {
+    /// A time written with its offset.
+    Instant(DateTime<Utc>),
+    /// A wall clock time, in the timezone of the airport it is printed next to.
+    Local(NaiveDateTime),
+}
+
+impl BookingTime {
+    pub fn instant(self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Instant(instant) => Some(instant),
+            Self::Local(_) => None,
+        }
+    }
+
+    pub fn local(self) -> Option<NaiveDateTime> {
+        match self {
+            Self::Instant(_) => None,
+            Self::Local(local) => Some(local),
+        }
+    }
+}
+
+/// Reads bookings out of a message. Parsers work offline, they never follow links or call the
+/// airline.
+pub trait BookingParser {
+    /// Name the bookings are reported with, so clients can tell how much to trust them.
+    fn name(&self) -> &'static str;
+
+    fn parse(&self, message: &Message) -> Vec<Booking>;
+}
+
+/// The parsers in the order they are tried. Markup is more reliable than text layouts, so the
+/// text is only read when a message has none.
+const PARSERS: &[&dyn BookingParser] = &[&JsonLdParser, &TextLayoutParser];
+
+/// The bookings of the first parser that finds any, with its name.
+pub fn parse_bookings(message: &Message) -> Option<(&'static str, Vec<Booking>)> {
+    PARSERS.iter().find_map(|parser| {
+        let bookings = parser.parse(message);
+        (!bookings.is_empty()).then(|| (parser.name(), bookings))
+    })
+}
+
+/// Reads a raw RFC 5322 message, decoding its MIME parts, or takes the input as pasted text
+/// when it doesn't start with mail headers.
+pub fn read_message(input: &str) -> Message {
+    let input = input.replace("\r\n", "\n");
+    let (headers, body) = split_headers(&input);
+    // Pasted layouts have `From: Paris (CDG)` lines too, so a sender needs an address.
+    let is_mail = headers.iter().any(|(name, value)| match name.as_str() {
+        "from" => value.contains('@'),
+        "mime-version" | "content-type" | "received" | "message-id" | "delivered-to" => true,
+        _ => false,
+    });
+
+    let mut message = Message::default();
+    if is_mail {
+        message.subject = header(&headers, "subject").map(decode_words);
+        read_part(&headers, body, &mut message);
+    } else if looks_like_html(&input) {
+        message.html.push(input.clone());
+    } else {
+        message.text = input.clone();
+    }
+
+    if message.text.trim().is_empty() {
+        message.text = message
+            .html
+            .iter()
+            .map(|html| html_text(html))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    message
+}
+
+type Headers = Vec<(String, String)>;
+
+/// Splits the headers of a message or MIME part from its body. Header names are lowercased and
+/// folded lines joined. Input that doesn't start with a header has no headers.
+fn split_headers(input: &str) -> (Headers, &str) {
+    let mut headers: Headers = Vec::new();
+    let mut rest = input;
+    loop {
+        let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+        if line.trim().is_empty() {
+            return (headers, next);
+        }
+        match (line.strip_prefix([' ', '\t']), headers.last_mut()) {
+            (Some(continued), Some((_, value))) => {
+                value.push(' ');
+                value.push_str(continued.trim());
+            }
+            _ => {
+                let Some((name, value)) = line.split_once(':') else {
+                    return (Vec::new(), input);
+                };
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+                    return (Vec::new(), input);
+                }
+                headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        if next.is_empty() {
+            return (headers, "");
+        }
+        rest = next;
+    }
+}
+
+fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The lowercased value of a header such as `text/html; charset=utf-8`, and its parameters.
+fn header_parameters(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = value.split(';');
+    let value = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let parameters = parts
+        .filter_map(|part| {
+            let (name, value) = part.split_once('=')?;
+            Some((
+                name.trim().to_ascii_lowercase(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+
+    (value, parameters)
+}
+
+fn read_part(headers: &Headers, body: &str, message: &mut Message) {
+    let (content_type, parameters) =
+        header_parameters(header(headers, "content-type").unwrap_or("text/plain"));
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let is_attachment = header(headers, "content-disposition")
+        .is_some_and(|value| header_parameters(value).0 == "attachment");
+
+    if content_type.starts_with("multipart/") {
+        let Some(boundary) = parameter("boundary") else {
+            return;
+        };
+        for part in split_multipart(body, boundary) {
+            let (headers, body) = split_headers(part);
+            read_part(&headers, body, message);
+        }
+    } else if content_type == "message/rfc822" {
+        let (headers, body) = split_headers(body);
+        read_part(&headers, body, message);
+    } else if (content_type == "text/plain" || content_type == "text/html") && !is_attachment {
+        let bytes = match header(headers, "content-transfer-encoding")
+            .map(|x| x.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("quoted-printable") => decode_quoted_printable(body),
+            Some("base64") => decode_base64(body),
+            _ => body.as_bytes().to_vec(),
+        };
+        let text = decode_charset(&bytes, parameter("charset"));
+        if content_type == "text/html" {
+            message.html.push(text);
+        } else {
+            if !message.text.is_empty() {
+                message.text.push('\n');
+            }
+            message.text.push_str(&text);
+        }
+    }
+}
+
+/// The parts between `--boundary` lines, up to the closing `--boundary--`.
+fn split_multipart<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        // Only whole delimiter lines count, so a boundary `b` does not match a `--bc` line.
+        let rest = line.trim_end().strip_prefix(&delimiter);
+        if let Some(rest) = rest.filter(|rest| rest.is_empty() || rest.starts_with("--")) {
+            if let Some(start) = start {
+                parts.push(&body[start..offset]);
+            }
+            if !rest.is_empty() {
+                return parts;
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+
+    parts
+}
+
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'=' if bytes.get(index + 1) == Some(&b'\n') => index += 2,
+            b'=' => {
+                let hex = bytes
+                    .get(index + 1..index + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 3;
+                    }
+                    None => {
+                        decoded.push(b'=');
+                        index += 1;
+                    }
+                }
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    decoded
+}
+
+fn decode_base64(body: &str) -> Vec<u8> {
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD
+        .decode(compact)
+        .unwrap_or_else(|_| body.as_bytes().to_vec())
+}
+
+/// Decodes UTF-8, reading the Latin-1 family byte for byte. Other charsets are rare in
+/// confirmation emails and read as UTF-8.
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    match charset.map(|x| x.to_ascii_lowercase()).as_deref() {
+        Some("iso-8859-1" | "iso-8859-15" | "latin1" | "windows-1252" | "us-ascii") => {
+            bytes.iter().map(|&byte| byte as char).collect()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Decodes the RFC 2047 encoded words of a header, such as `=?UTF-8?Q?Votre_r=C3=A9servation?=`.
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let word = rest[start + 2..].splitn(3, '?').collect::<Vec<_>>();
+        let [charset, encoding, tail] = word[..] else {
+            break;
+        };
+        let Some(end) = tail.find("?=") else {
+            break;
+        };
+        let text = &tail[..end];
+        let bytes = match encoding.to_ascii_lowercase().as_str() {
+            "b" => decode_base64(text),
+            "q" => decode_quoted_printable(&text.replace('_', " ")),
+            _ => break,
+        };
+
+        let before = &rest[..start];
+        // Whitespace between two encoded words is not part of the text.
+        if !before.trim().is_empty() || decoded.is_empty() {
+            decoded.push_str(before);
+        }
+        decoded.push_str(&decode_charset(&bytes, Some(charset)));
+        rest = &tail[end + 2..];
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+fn looks_like_html(input: &str) -> bool {
+    let lower = input.to_ascii_lowercase();
+    ["<html", "<body", "<script", "<div", "<table", "<p>"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+}
+
+/// The text of an HTML document, one line per block. Scripts and styles are dropped.
+fn html_text(html: &str) -> String {
+    const BLOCKS: [&str; 12] = [
+        "br", "p", "div", "tr", "li", "h1", "h2", "h3", "h4", "table", "section", "hr",
+    ];
+
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if (name == "script" || name == "style") && !tag.starts_with('/') {
+            let closing = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&closing) {
+                Some(index) => &rest[index..],
+                None => "",
+            };
+        } else if BLOCKS.contains(&name.as_str()) {
+            text.push('\n');
+        } else if name == "td" || name == "th" {
+            text.push(' ');
+        }
+    }
+    text.push_str(rest);
+
+    decode_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 8)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|x| x.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+/// Reads the schema.org `FlightReservation` and `LodgingReservation` markup that airlines and
+/// hotels embed in their emails for mail clients.
+pub struct JsonLdParser;
+
+impl BookingParser for JsonLdParser {
+    fn name(&self) -> &'static str {
+        "json_ld"
+    }
+
+    fn parse(&self, message: &Message) -> Vec<Booking> {
+        let mut nodes = Vec::new();
+        for html in &message.html {
+            for script in json_ld_scripts(html) {
+                if let Ok(value) = serde_json::from_str(script.trim()) {
+                    collect_nodes(value, &mut nodes);
+                }
+            }
+        }
+
+        let mut bookings: Vec<Booking> = Vec::new();
+        for node in &nodes {
+            if has_type(node, "FlightReservation") {
+                let Some(flight) = flight_reservation(node) else {
+                    continue;
+                };
+                // Reservations for several travellers repeat the flight once per passenger.
+                let same_flight = bookings.iter_mut().find_map(|booking| match booking {
+                    Booking::Flight(booked)
+                        if booked.flight_number == flight.flight_number
+                            && booked.departure_airport == flight.departure_airport
+                            && booked.departure_time == flight.departure_time =>
+                    {
+                        Some(booked)
+                    }
+                    _ => None,
+                });
+                match same_flight {
+                    Some(booked) => booked.passengers.extend(flight.passengers),
+                    None => bookings.push(Booking::Flight(flight)),
+                }
+            } else if has_type(node, "LodgingReservation") {
+                bookings.extend(lodging_reservation(node).map(Booking::Stay));
+            }
+        }
+
+        bookings
+    }
+}
+
+/// The contents of the `<script type="application/ld+json">` elements of a document.
+fn json_ld_scripts(html: &str) -> Vec<&str> {
+    let lower = html.to_ascii_lowercase();
+    let mut scripts = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = lower[offset..].find("<script") {
+        let start = offset + start;
+        let Some(tag_end) = lower[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let Some(end) = lower[tag_end..].find("</script").map(|end| tag_end + end) else {
+            break;
+        };
+        if lower[start..tag_end].contains("application/ld+json") {
+            scripts.push(&html[tag_end + 1..end]);
+        }
+        offset = end;
+    }
+
+    scripts
+}
+
+/// Flattens arrays and `@graph` lists into the objects they hold.
+fn collect_nodes(value: Value, nodes: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_nodes(value, nodes);
+            }
+        }
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => collect_nodes(graph, nodes),
+            None => nodes.push(Value::Object(object)),
+        },
+        _ => {}
+    }
+}
+
+fn has_type(node: &Value, name: &str) -> bool {
+    let matches = |value: &Value| {
+        value.as_str().is_some_and(|value| {
+            value.rsplit('/').next() == Some(name) || value.strip_prefix("schema:") == Some(name)
+        })
+    };
+    match node.get("@type") {
+        Some(Value::Array(types)) => types.iter().any(matches),
+        Some(value) => matches(value),
+        None => false,
+    }
+}
+
+/// The first object of a property that may also be a list.
+fn object<'a>(node: &'a Value, key: &str) -> Option<&'a Value> {
+    match node.get(key)? {
+        Value::Array(values) => values.iter().find(|value| value.is_object()),
+        value if value.is_object() => Some(value),
+        _ => None,
+    }
+}
+
+/// A text property, or the `name` of an object in its place.
+fn text(node: &Value, key: &str) -> Option<String> {
+    let text = match node.get(key)? {
+        Value::String(text) => text.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Object(_) => return text(node.get(key)?, "name"),
+        _ => return None,
+    };
+
+    (!text.is_empty()).then_some(text)
+}
+
+fn number(node: &Value, key: &str) -> Option<f64> {
+    match node.get(key)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn flight_reservation(node: &Value) -> Option<FlightBooking> {
+    let flight = object(node, "reservationFor")?;
+    let airline = object(flight, "airline");
+    let airline_code = airline.and_then(|airline| text(airline, "iataCode"));
+    let flight_number = text(flight, "flightNumber").map(|number| {
+        let number = number.replace(' ', "");
+        match &airline_code {
+            Some(code) if number.starts_with(|c: char| c.is_ascii_digit()) => {
+                format!("{}{}", code, number)
+            }
+            _ => number,
+        }
+    });
+    let airport = |key: &str| object(flight, key).and_then(|airport| text(airport, "iataCode"));
+    let seat = text(node, "airplaneSeat").or_else(|| {
+        let seat = object(object(node, "reservedTicket")?, "ticketedSeat")?;
+        text(seat, "seatNumber")
+    });
+
+    Some(FlightBooking {
+        airline: airline
+            .and_then(|airline| text(airline, "name"))
+            .or(airline_code)
+            .or(flight_number.clone())?,
+        confirmation_code: text(node, "reservationNumber").unwrap_or_default(),
+        departure_airport: airport("departureAirport"),
+        arrival_airport: airport("arrivalAirport"),
+        departure_time: text(flight, "departureTime").and_then(|x| booking_time(&x)),
+        arrival_time: text(flight, "arrivalTime").and_then(|x| booking_time(&x)),
+        departure_terminal: text(flight, "departureTerminal"),
+        departure_gate: text(flight, "departureGate"),
+        arrival_terminal: text(flight, "arrivalTerminal"),
+        arrival_gate: text(flight, "arrivalGate"),
+        passengers: text(node, "underName")
+            .map(|name| Passenger {
+                name,
+                booking_reference: None,
+                seat,
+            })
+            .into_iter()
+            .collect(),
+        flight_number,
+    })
+}
+
+fn booking_time(value: &str) -> Option<BookingTime> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Some(BookingTime::Instant(instant.with_timezone(&Utc)));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(BookingTime::Local)
+}
+
+/// The local date and time of a check-in or check-out, which may be a date alone. Hotels write
+/// local times, so an offset is dropped rather than applied.
+fn stay_time(value: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let local = match booking_time(value) {
+        Some(BookingTime::Local(local)) => Some(local),
+        Some(BookingTime::Instant(_)) => DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|time| time.naive_local()),
+        None => None,
+    };
+
+    match local {
+        Some(local) => Some((local.date(), Some(local.time()))),
+        None => NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d")
+            .ok()
+            .map(|date| (date, None)),
+    }
+}
+
+fn lodging_reservation(node: &Value) -> Option<StayFields> {
+    let place = object(node, "reservationFor")?;
+    let (start_date, check_in_time) = stay_time(&text(node, "checkinTime")?)?;
+    let (end_date, check_out_time) = stay_time(&text(node, "checkoutTime")?)?;
+    let address = match place.get("address") {
+        Some(Value::String(address)) => Some(address.trim().to_string()),
+        Some(address) => {
+            let locality = ["postalCode", "addressLocality"]
+                .iter()
+                .filter_map(|key| text(address, key))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let parts: Vec<String> = [
+                text(address, "streetAddress"),
+                Some(locality).filter(|x| !x.is_empty()),
+                text(address, "addressRegion"),
+                text(address, "addressCountry"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            Some(parts.join(", "))
+        }
+        None => None,
+    };
+    let location = object(place, "geo").and_then(|geo| {
+        Some(Coordinates {
+            latitude: number(geo, "latitude")?,
+            longitude: number(geo, "longitude")?,
+        })
+    });
+
+    Some(StayFields {
+        summary: text(place, "name").unwrap_or_else(|| "Stay".to_string()),
+        start_date,
+        end_date,
+        check_in_time,
+        check_out_time,
+        address: address.filter(|x| !x.is_empty()),
+        location,
+        confirmation_number: text(node, "reservationNumber"),
+        notes: String::new(),
+    })
+}
+
+/// Reads the `Label: value` lines most airline and hotel emails print their bookings as, such
+/// as `Flight: AF 1234` followed by `Departure: 12 May 2024 10:35 Paris (CDG)`.
+pub struct TextLayoutParser;
+
+const CONFIRMATION_LABELS: [&str; 8] = [
+    "confirmation number",
+    "confirmation code",
+    "confirmation",
+    "booking reference",
+    "booking number",
+    "reservation number",
+    "record locator",
+    "pnr",
+];
+
+impl BookingParser for TextLayoutParser {
+    fn name(&self) -> &'static str {
+        "text_layout"
+    }
+
+    fn parse(&self, message: &Message) -> Vec<Booking> {
+        let lines: Vec<&str> = message
+            .text
+            .lines()
+            .map(|line| line.trim_start_matches(['-', '*', '\u{2022}', '>']).trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let confirmation = lines
+            .iter()
+            .find_map(|line| labelled(line, &CONFIRMATION_LABELS))
+            .and_then(|value| value.split_whitespace().next())
+            .map(|code| {
+                code.trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_string()
+            });
+
+        let mut bookings: Vec<Booking> = text_flights(&lines, confirmation.clone())
+            .into_iter()
+            .map(Booking::Flight)
+            .collect();
+        bookings
+            .extend(text_stay(&lines, message.subject.as_deref(), confirmation).map(Booking::Stay));
+
+        bookings
+    }
+}
+
+/// The value after `label` at the start of a line, which the label must end a word of.
+fn labelled<'a>(line: &'a str, labels: &[&str]) -> Option<&'a str> {
+    let lower = line.to_lowercase();
+    labels.iter().find_map(|label| {
+        if !lower.starts_with(label) {
+            return None;
+        }
+        let rest = line.get(label.len()..)?;
+        if rest.starts_with(|c: char| c.is_alphanumeric()) {
+            return None;
+        }
+        Some(rest.trim_start_matches(|c: char| c == ':' || c == '#' || c.is_whitespace()))
+    })
+}
+
+/// Whether a line starts with a label of its own, such as `Arrival:`.
+fn is_label(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(label, _)| {
+        !label.is_empty()
+            && label
+                .chars()
+                .all(|c| c.is_alphabetic() || c == ' ' || c == '-')
+    })
+}
+
+/// The value of the line labelled with one of `labels` that `read` gets something from, also
+/// trying the line after the label for layouts that print values below them.
+fn find_labelled<T>(
+    lines: &[&str],
+    labels: &[&str],
+    read: impl Fn(&str) -> Option<T>,
+) -> Option<T> {
+    lines.iter().enumerate().find_map(|(index, line)| {
+        let value = labelled(line, labels)?;
+        read(value).or_else(|| {
+            let next = lines.get(index + 1).filter(|next| !is_label(next))?;
+            read(next)
+        })
+    })
+}
+
+fn text_flights(lines: &[&str], confirmation: Option<String>) -> Vec<FlightBooking> {
+    let airline = lines
+        .iter()
+        .find_map(|line| labelled(line, &["airline", "carrier"]))
+        .map(str::to_string);
+
+    // Each flight number starts a segment, and the lines up to the next one describe it.
+    let mut starts: Vec<(usize, String)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(number) = labelled(line, &["flight number", "flight"]).and_then(flight_number) {
+            starts.push((index, number));
+        }
+    }
+
+    let mut flights = Vec::new();
+    for (position, (start, number)) in starts.iter().enumerate() {
+        let end = starts
+            .get(position + 1)
+            .map_or(lines.len(), |(next, _)| *next);
+        let segment = &lines[*start..end];
+        let departure = ["departure", "departs", "depart", "from"];
+        let arrival = ["arrival", "arrives", "arrive", "to"];
+
+        let (Some(departure_time), Some(arrival_time)) = (
+            find_labelled(segment, &departure, local_date_time),
+            find_labelled(segment, &arrival, local_date_time),
+        ) else {
+            continue;
+        };
+        let seat = find_labelled(segment, &["seat"], |value| {
+            value.split_whitespace().next().map(str::to_string)
+        });
+
+        flights.push(FlightBooking {
+            airline: airline.clone().unwrap_or_else(|| number[..2].to_string()),
+            confirmation_code: confirmation.clone().unwrap_or_default(),
+            flight_number: Some(number.clone()),
+            departure_airport: find_labelled(segment, &departure, airport_code),
+            arrival_airport: find_labelled(segment, &arrival, airport_code),
+            departure_time: Some(BookingTime::Local(departure_time)),
+            arrival_time: Some(BookingTime::Local(arrival_time)),
+            passengers: find_labelled(segment, &["passenger", "traveller", "traveler"], |name| {
+                (!name.is_empty()).then(|| name.to_string())
+            })
+            .map(|name| Passenger {
+                name,
+                booking_reference: None,
+                seat: seat.clone(),
+            })
+            .into_iter()
+            .collect(),
+            ..FlightBooking::default()
+        });
+    }
+
+    flights
+}
+
+fn text_stay(
+    lines: &[&str],
+    subject: Option<&str>,
+    confirmation: Option<String>,
+) -> Option<StayFields> {
+    let check_in = [
+        "check-in date",
+        "check-in",
+        "check in",
+        "checkin",
+        "arrival date",
+    ];
+    let check_out = [
+        "check-out date",
+        "check-out",
+        "check out",
+        "checkout",
+        "departure date",
+    ];
+    let start_date = find_labelled(lines, &check_in, find_date)?;
+    let end_date = find_labelled(lines, &check_out, find_date)?;
+    let name = lines
+        .iter()
+        .find_map(|line| labelled(line, &["hotel name", "hotel", "property", "accommodation"]))
+        .filter(|name| !name.is_empty())
+        .or(subject)
+        .unwrap_or("Stay");
+
+    Some(StayFields {
+        summary: name.to_string(),
+        start_date,
+        end_date,
+        check_in_time: find_labelled(lines, &check_in, find_time),
+        check_out_time: find_labelled(lines, &check_out, find_time),
+        address: lines
+            .iter()
+            .find_map(|line| labelled(line, &["address"]))
+            .filter(|address| !address.is_empty())
+            .map(str::to_string),
+        location: None,
+        confirmation_number: confirmation,
+        notes: String::new(),
+    })
+}
+
+/// The first flight number in `text`, either `AF1234` or `AF 1234`, written without the space.
+fn flight_number(text: &str) -> Option<String> {
+    let is_carrier = |code: &str| {
+        code.len() == 2
+            && code
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && code.chars().any(|c| c.is_ascii_uppercase())
+    };
+    let is_number = |number: &str| {
+        (1..=4).contains(&number.len()) && number.chars().all(|c| c.is_ascii_digit())
+    };
+
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.iter().enumerate().find_map(|(index, word)| {
+        if word.is_char_boundary(2) && is_carrier(&word[..2]) && is_number(&word[2..]) {
+            return Some(word.to_string());
+        }
+        let next = words.get(index + 1)?;
+        (is_carrier(word) && is_number(next)).then(|| format!("{}{}", word, next))
+    })
+}
+
+/// A known airport's IATA code in `text`, preferring one in parentheses as in `Paris (CDG)`.
+fn airport_code(text: &str) -> Option<String> {
+    let codes: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '(' && c != ')')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let known = |word: &str| {
+        (word.len() == 3
+            && word.chars().all(|c| c.is_ascii_uppercase())
+            && find_airport(word).is_some())
+        .then(|| word.to_string())
+    };
+
+    codes
+        .iter()
+        .find_map(|word| known(word.strip_prefix('(')?.strip_suffix(')')?))
+        .or_else(|| codes.iter().find_map(|word| known(word)))
+}
+
+fn local_date_time(text: &str) -> Option<NaiveDateTime> {
+    Some(find_date(text)?.and_time(find_time(text)?))
+}
+
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .map(|word| word.trim_end_matches('.'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    if word.len() < 3 || !word.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let prefix = word[..3].to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| *month == prefix)
+        .map(|index| index as u32 + 1)
+}
+
+/// The first date in `text`, written as `2024-05-12`, `12.05.2024`, `12 May 2024` or
+/// `May 12, 2024`. Dates without a year are ambiguous and left out.
+fn find_date(text: &str) -> Option<NaiveDate> {
+    let words = words(text);
+    let day = |word: &str| -> Option<u32> {
+        word.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .ok()
+    };
+    let year =
+        |word: &str| -> Option<i32> { (word.len() == 4).then(|| word.parse().ok()).flatten() };
+
+    words.iter().enumerate().find_map(|(index, word)| {
+        if let Some(date) = word
+            .get(..10)
+            .and_then(|x| NaiveDate::parse_from_str(x, "%Y-%m-%d").ok())
+            .or_else(|| NaiveDate::parse_from_str(word, "%d.%m.%Y").ok())
+        {
+            return Some(date);
+        }
+        let next = words.get(index + 1..index + 3)?;
+        if let (Some(day), Some(month), Some(year)) = (day(word), month(next[0]), year(next[1])) {
+            return NaiveDate::from_ymd_opt(year, month, day);
+        }
+        let (month, day, year) = (month(word)?, day(next[0])?, year(next[1])?);
+        NaiveDate::from_ymd_opt(year, month, day)
+    })
+}
+
+/// The first time of day in `text`, such as `10:35`, `10:35 PM` or the time of
+/// `2024-05-12T10:35`.
+fn find_time(text: &str) -> Option<NaiveTime> {
+    let words = words(text);
+    words.iter().enumerate().find_map(|(index, word)| {
+        let word = word.rsplit('T').next()?;
+        let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let time = NaiveTime::parse_from_str(digits, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(digits, "%H:%M:%S"))
+            .ok()?;
+
+        let suffix = match &word[digits.len()..] {
+            "" => words.get(index + 1).copied().unwrap_or_default(),
+            suffix => suffix,
+        };
+        match suffix.to_ascii_lowercase().as_str() {
+            "pm" | "p.m" if time.hour() < 12 => Some(time + Duration::hours(12)),
+            "am" | "a.m" if time.hour() == 12 => Some(time - Duration::hours(12)),
+            _ => Some(time),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An airline confirmation with a quoted-printable text part and the schema.org markup in a
+    /// base64 HTML part.
+    const FLIGHT_EMAIL: &str = "\
+From: Air France <noreply@airfrance.example>\r
+To: plans+abc@youtinerary.example\r
+Subject: =?UTF-8?Q?Votre_r=C3=A9servation?= =?UTF-8?B?IFJYSjM0UA==?=\r
+MIME-Version: 1.0\r
+Content-Type: multipart/alternative;\r
+ boundary=\"outer-boundary\"\r
+\r
+This is a multi-part message in MIME format.\r
+--outer-boundary\r
+Content-Type: text/plain; charset=\"utf-8\"\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Bonjour Ana, votre r=C3=A9servation est confirm=C3=A9e. Ce texte est coup=\r
+=C3=A9 sur deux lignes.\r
+--outer-boundary\r
+Content-Type: text/html; charset=utf-8\r
+Content-Transfer-Encoding: base64\r
+\r
+PGh0bWw+PGhlYWQ+PHNjcmlwdCB0eXBlPSJhcHBsaWNhdGlvbi9sZCtqc29uIj4KeyJAY29udGV4\r
+dCI6Imh0dHA6Ly9zY2hlbWEub3JnIiwiQHR5cGUiOiJGbGlnaHRSZXNlcnZhdGlvbiIsInJlc2Vy\r
+dmF0aW9uTnVtYmVyIjoiUlhKMzRQIiwKInVuZGVyTmFtZSI6eyJAdHlwZSI6IlBlcnNvbiIsIm5h\r
+bWUiOiJBbmEgU2lsdmEifSwiYWlycGxhbmVTZWF0IjoiMTRDIiwKInJlc2VydmF0aW9uRm9yIjp7\r
+IkB0eXBlIjoiRmxpZ2h0IiwiZmxpZ2h0TnVtYmVyIjoiMTIzNCIsImFpcmxpbmUiOnsiQHR5cGUi\r
+OiJBaXJsaW5lIiwibmFtZSI6IkFpciBGcmFuY2UiLCJpYXRhQ29kZSI6IkFGIn0sCiJkZXBhcnR1\r
+cmVBaXJwb3J0Ijp7IkB0eXBlIjoiQWlycG9ydCIsImlhdGFDb2RlIjoiQ0RHIn0sImRlcGFydHVy\r
+ZVRpbWUiOiIyMDI2LTA1LTEyVDEwOjM1OjAwKzAyOjAwIiwKImFycml2YWxBaXJwb3J0Ijp7IkB0\r
+eXBlIjoiQWlycG9ydCIsImlhdGFDb2RlIjoiTElTIn0sImFycml2YWxUaW1lIjoiMjAyNi0wNS0x\r
+MlQxMjoxNTowMCswMTowMCIsImRlcGFydHVyZVRlcm1pbmFsIjoiMkYifX0KPC9zY3JpcHQ+PC9o\r
+ZWFkPjxib2R5PjxwPllvdXIgdHJpcCB0byBMaXNib24gJmFtcDsgYmV5b25kPC9wPjwvYm9keT48\r
+L2h0bWw+\r
+--outer-boundary--\r
+Trailing text after the closing boundary is ignored.\r
+";
+
+    /// A hotel confirmation that only prints its booking as labelled lines.
+    const STAY_EMAIL: &str = "\
+From: Hotel Avenida <reservations@avenida.example>\r
+Subject: Your stay at Hotel Avenida\r
+Content-Type: text/plain; charset=iso-8859-1\r
+Content-Transfer-Encoding: quoted-printable\r
+\r
+Dear guest,\r
+\r
+Hotel: Hotel Avenida Palace\r
+Confirmation number: 88213-B\r
+Check-in: 14 May 2026, from 3:00 PM\r
+Check-out:\r
+16 May 2026 11:00\r
+Address: Rua 1=BA de Dezembro 123, Lisboa\r
+";
+
+    fn instant(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn json_ld_flights_are_read_from_a_base64_html_part() {
+        let message = read_message(FLIGHT_EMAIL);
+        assert_eq!(message.html.len(), 1);
+
+        let (parser, bookings) = parse_bookings(&message).unwrap();
+        assert_eq!(parser, "json_ld");
+        let [Booking::Flight(flight)] = &bookings[..] else {
+            panic!("expected one flight, got {:?}", bookings);
+        };
+        assert_eq!(flight.airline, "Air France");
+        assert_eq!(flight.confirmation_code, "RXJ34P");
+        assert_eq!(flight.flight_number.as_deref(), Some("AF1234"));
+        assert_eq!(flight.departure_airport.as_deref(), Some("CDG"));
+        assert_eq!(flight.arrival_airport.as_deref(), Some("LIS"));
+        assert_eq!(
+            flight.departure_time,
+            Some(BookingTime::Instant(instant("2026-05-12T08:35:00Z")))
+        );
+        assert_eq!(
+            flight.arrival_time,
+            Some(BookingTime::Instant(instant("2026-05-12T11:15:00Z")))
+        );
+        assert_eq!(flight.departure_terminal.as_deref(), Some("2F"));
+        assert_eq!(flight.passengers.len(), 1);
+        assert_eq!(flight.passengers[0].name, "Ana Silva");
+        assert_eq!(flight.passengers[0].seat.as_deref(), Some("14C"));
+    }
+
+    #[test]
+    fn quoted_printable_parts_are_decoded() {
+        let message = read_message(FLIGHT_EMAIL);
+        assert_eq!(
+            message.text.trim_end(),
+            "Bonjour Ana, votre réservation est confirmée. Ce texte est coupé sur deux lignes."
+        );
+    }
+
+    #[test]
+    fn encoded_subjects_are_decoded() {
+        let message = read_message(FLIGHT_EMAIL);
+        assert_eq!(message.subject.as_deref(), Some("Votre réservation RXJ34P"));
+    }
+
+    #[test]
+    fn text_layout_stays_are_read() {
+        let message = read_message(STAY_EMAIL);
+        let (parser, bookings) = parse_bookings(&message).unwrap();
+        assert_eq!(parser, "text_layout");
+        let [Booking::Stay(stay)] = &bookings[..] else {
+            panic!("expected one stay, got {:?}", bookings);
+        };
+        assert_eq!(stay.summary, "Hotel Avenida Palace");
+        assert_eq!(
+            stay.start_date,
+            NaiveDate::from_ymd_opt(2026, 5, 14).unwrap()
+        );
+        assert_eq!(stay.end_date, NaiveDate::from_ymd_opt(2026, 5, 16).unwrap());
+        assert_eq!(stay.check_in_time, NaiveTime::from_hms_opt(15, 0, 0));
+        assert_eq!(stay.check_out_time, NaiveTime::from_hms_opt(11, 0, 0));
+        assert_eq!(stay.confirmation_number.as_deref(), Some("88213-B"));
+        assert_eq!(
+            stay.address.as_deref(),
+            Some("Rua 1º de Dezembro 123, Lisboa")
+        );
+    }
+
+    #[test]
+    fn text_layout_flights_are_read_from_pasted_text() {
+        let message = read_message(
+            "Booking reference: QX7P2M\n\
+             Airline: TAP Air Portugal\n\
+             Flight: TP 1351\n\
+             Departure: 12 May 2026 07:05 Lisbon (LIS)\n\
+             Arrival: 12 May 2026 08:25 Porto (OPO)\n\
+             Passenger: Bea Costa\n\
+             Seat: 3A\n",
+        );
+        let (parser, bookings) = parse_bookings(&message).unwrap();
+        assert_eq!(parser, "text_layout");
+        let [Booking::Flight(flight)] = &bookings[..] else {
+            panic!("expected one flight, got {:?}", bookings);
+        };
+        assert_eq!(flight.airline, "TAP Air Portugal");
+        assert_eq!(flight.confirmation_code, "QX7P2M");
+        assert_eq!(flight.flight_number.as_deref(), Some("TP1351"));
+        assert_eq!(flight.departure_airport.as_deref(), Some("LIS"));
+        assert_eq!(
+            flight.departure_time,
+            NaiveDate::from_ymd_opt(2026, 5, 12)
+                .and_then(|date| date.and_hms_opt(7, 5, 0))
+                .map(BookingTime::Local)
+        );
+        assert_eq!(flight.passengers[0].seat.as_deref(), Some("3A"));
+    }
+
+    #[test]
+    fn quoted_printable_soft_breaks_and_stray_equals_signs() {
+        assert_eq!(
+            decode_quoted_printable("caf=C3=A9=\nbar"),
+            "cafébar".as_bytes()
+        );
+        assert_eq!(decode_quoted_printable("1+1=2 =ZZ"), b"1+1=2 =ZZ");
+        assert_eq!(decode_quoted_printable("end="), b"end=");
+    }
+
+    #[test]
+    fn base64_bodies_ignore_line_breaks() {
+        assert_eq!(decode_base64("SGVs\nbG8g\r\nd29y bGQ="), b"Hello world");
+        // A body that is not base64 after all is kept as it is.
+        assert_eq!(decode_base64("not base64!"), b"not base64!");
+    }
+
+    #[test]
+    fn encoded_words_are_decoded() {
+        assert_eq!(decode_words("=?utf-8?q?Caf=C3=A9_Lisboa?="), "Café Lisboa");
+        assert_eq!(decode_words("=?UTF-8?B?w4ly?= vo"), "Ér vo");
+        assert_eq!(
+            decode_words("Re: =?ISO-8859-1?Q?R=E9servation?= confirmed"),
+            "Re: Réservation confirmed"
+        );
+        // Whitespace between adjacent encoded words is dropped.
+        assert_eq!(decode_words("=?UTF-8?Q?a?=  =?UTF-8?Q?b?="), "ab");
+    }
+
+    #[test]
+    fn malformed_encoded_words_are_left_as_they_are() {
+        for value in [
+            "=?UTF-8?Q?unterminated",
+            "=?UTF-8?X?unknown?=",
+            "=?UTF-8?",
+            "=?",
+            "plain = ? text",
+        ] {
+            assert_eq!(decode_words(value), value);
+        }
+    }
+
+    #[test]
+    fn multipart_bodies_split_on_boundary_lines() {
+        let body = "preamble\n--b\nfirst\n--b \nsecond\n--bc\nnot a boundary\n--b--\nepilogue\n";
+        assert_eq!(
+            split_multipart(body, "b"),
+            ["first\n", "second\n--bc\nnot a boundary\n"]
+        );
+    }
+
+    #[test]
+    fn multipart_bodies_without_a_closing_boundary_keep_their_last_part() {
+        assert_eq!(
+            split_multipart("--b\nfirst\n--b\nlast", "b"),
+            ["first\n", "last"]
+        );
+        assert!(split_multipart("no boundary here", "b").is_empty());
+    }
+
+    #[test]
+    fn json_ld_scripts_are_found_whatever_their_case_and_attributes() {
+        let html = r#"<script>var x = 1;</script>
+            <SCRIPT type="application/ld+json" nonce="x">{"@type":"A"}</SCRIPT>
+            <script data-x="1" type='application/ld+json'>[{"@type":"B"}]</script>
+            <script type="application/ld+json">{"unterminated": true}"#;
+        assert_eq!(
+            json_ld_scripts(html),
+            [r#"{"@type":"A"}"#, r#"[{"@type":"B"}]"#]
+        );
+    }
+}
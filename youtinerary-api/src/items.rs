@@ -0,0 +1,119 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::{ItemVisibility, ItineraryItemType};
+
+/// The itinerary item a flight, stay, activity or travel leg belongs to, written next to the
+/// booking's own fields.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Item {
+    #[serde(rename = "item_id")]
+    pub id: i32,
+    pub position: Option<i32>,
+    pub visibility: ItemVisibility,
+}
+
+impl Item {
+    /// Orders items starting at the same time by position, unpositioned ones last.
+    pub fn sort_key(&self) -> (bool, Option<i32>, i32) {
+        (self.position.is_none(), self.position, self.id)
+    }
+}
+
+/// An item of any kind, with the fields they all share.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemSummary {
+    pub id: i32,
+    pub item_type: ItineraryItemType,
+    pub title: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Wall clock time the item starts at where it starts, which items are ordered by.
+    pub starts_at: NaiveDateTime,
+    pub position: Option<i32>,
+    pub visibility: ItemVisibility,
+    pub notes: String,
+    /// Where the booking behind the item is read and changed.
+    pub location: String,
+}
+
+/// Path of the endpoints of one kind of item, below its itinerary.
+pub fn item_path(itinerary_id: i32, item_type: ItineraryItemType, kind_id: i32) -> String {
+    let kind = match item_type {
+        ItineraryItemType::Flight => "flights",
+        ItineraryItemType::Stay => "stays",
+        ItineraryItemType::Activity => "activities",
+        ItineraryItemType::TravelLeg => "travel_legs",
+    };
+
+    format!("/itineraries/{}/{}/{}", itinerary_id, kind, kind_id)
+}
+
+/// Registers a new item of the itinerary, which the booking of that kind is then inserted with.
+pub async fn insert_item(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    item_type: ItineraryItemType,
+) -> sqlx::Result<i32> {
+    let item = sqlx::query!(
+        r#"
+            insert into itinerary_items (itinerary_id, item_type)
+            values ($1, $2)
+            returning id
+        "#,
+        itinerary_id,
+        item_type as ItineraryItemType
+    )
+    .fetch_one(connection)
+    .await?;
+
+    Ok(item.id)
+}
+
+/// Loads the items of an itinerary in timeline order, or only `item_id` when given.
+pub async fn get_items(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    item_id: Option<i32>,
+) -> sqlx::Result<Vec<ItemSummary>> {
+    let items = sqlx::query!(
+        r#"
+            select
+                id as "id!",
+                item_type as "item_type!: ItineraryItemType",
+                kind_id as "kind_id!",
+                title as "title!",
+                start_date as "start_date!",
+                end_date as "end_date!",
+                starts_at as "starts_at!",
+                position,
+                visibility as "visibility!: ItemVisibility",
+                notes as "notes!"
+            from itinerary_item_summaries
+            where itinerary_id = $1
+                and ($2::integer is null or id = $2)
+            order by starts_at, position nulls last, id
+        "#,
+        itinerary_id,
+        item_id
+    )
+    .fetch_all(connection)
+    .await?;
+
+    Ok(items
+        .into_iter()
+        .map(|item| ItemSummary {
+            id: item.id,
+            item_type: item.item_type,
+            title: item.title,
+            start_date: item.start_date,
+            end_date: item.end_date,
+            starts_at: item.starts_at,
+            position: item.position,
+            visibility: item.visibility,
+            notes: item.notes,
+            location: item_path(itinerary_id, item.item_type, item.kind_id),
+        })
+        .collect())
+}
@@ -0,0 +1,401 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::activities::{ActivityFields, Cost};
+use crate::fields::{optional_text, required_text, InvalidField};
+use crate::flights::FlightDetails;
+use crate::stays::StayFields;
+use crate::timeline::{ActivityEntry, Coordinates, FlightEntry, StayEntry, TravelLegEntry};
+use crate::travel_legs::{LegDetails, TravelLegFields};
+use crate::{ActivityCategory, ItemVisibility, ItineraryShareType, ItineraryStatus, TravelLegType};
+
+/// Version written by the export. Bump it whenever a field changes meaning or becomes required,
+/// and keep the import accepting every older version.
+pub const ITINERARY_DOCUMENT_VERSION: u32 = 1;
+
+/// A whole itinerary as a standalone JSON document, used for backups and for moving trips
+/// between environments.
+///
+/// Ids are only informative: the import assigns new ones and ignores them, so they may be
+/// left out of hand written documents.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItineraryDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: Option<DateTime<Utc>>,
+    pub itinerary: ItineraryRecord,
+    #[serde(default)]
+    pub flights: Vec<FlightRecord>,
+    #[serde(default)]
+    pub stays: Vec<StayRecord>,
+    #[serde(default)]
+    pub activities: Vec<ActivityRecord>,
+    #[serde(default)]
+    pub travel_legs: Vec<TravelLegRecord>,
+    #[serde(default)]
+    pub shares: Vec<ShareRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItineraryRecord {
+    pub id: Option<i32>,
+    pub name: String,
+    /// Informational only, an import always creates a draft.
+    pub status: ItineraryStatus,
+    #[serde(default)]
+    pub is_template: bool,
+    /// Only dates set on the itinerary itself, derived ones are recomputed after an import.
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlightRecord {
+    pub id: Option<i32>,
+    pub airline: String,
+    pub confirmation_code: String,
+    pub departure_time: DateTime<Utc>,
+    pub arrival_time: DateTime<Utc>,
+    pub departure_timezone: Option<Tz>,
+    pub arrival_timezone: Option<Tz>,
+    pub departure_airport: Option<String>,
+    pub arrival_airport: Option<String>,
+    pub departure_location: Option<Coordinates>,
+    pub arrival_location: Option<Coordinates>,
+    #[serde(flatten)]
+    pub details: FlightDetails,
+    pub position: Option<i32>,
+    #[serde(default)]
+    pub visibility: ItemVisibility,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StayRecord {
+    pub id: Option<i32>,
+    pub summary: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub check_in_time: Option<NaiveTime>,
+    pub check_out_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub confirmation_number: Option<String>,
+    pub position: Option<i32>,
+    #[serde(default)]
+    pub visibility: ItemVisibility,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityRecord {
+    pub id: Option<i32>,
+    pub summary: String,
+    #[serde(default)]
+    pub category: ActivityCategory,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub booking_reference: Option<String>,
+    pub cost: Option<Cost>,
+    pub position: Option<i32>,
+    #[serde(default)]
+    pub visibility: ItemVisibility,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TravelLegRecord {
+    pub id: Option<i32>,
+    pub name: String,
+    pub travel_leg_type: TravelLegType,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub start_place: Option<String>,
+    pub end_place: Option<String>,
+    pub start_location: Option<Coordinates>,
+    pub end_location: Option<Coordinates>,
+    pub start_timezone: Option<Tz>,
+    pub end_timezone: Option<Tz>,
+    pub operator: Option<String>,
+    pub booking_reference: Option<String>,
+    #[serde(flatten)]
+    pub details: LegDetails,
+    pub position: Option<i32>,
+    #[serde(default)]
+    pub visibility: ItemVisibility,
+    pub notes: String,
+}
+
+impl ItineraryRecord {
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        Ok(Self {
+            name: required_text("name", self.name, 255)?,
+            ..self
+        })
+    }
+}
+
+impl FlightRecord {
+    /// Checks the fields a new flight is checked for, airports being stored as given.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        Ok(Self {
+            airline: required_text("airline", self.airline, 255)?,
+            confirmation_code: required_text("confirmation_code", self.confirmation_code, 50)?,
+            departure_airport: optional_text("departure_airport", self.departure_airport, 3)?,
+            arrival_airport: optional_text("arrival_airport", self.arrival_airport, 3)?,
+            details: self.details.validated()?,
+            notes: optional_text("notes", Some(self.notes), 255)?.unwrap_or_default(),
+            ..self
+        })
+    }
+}
+
+impl From<FlightEntry> for FlightRecord {
+    fn from(value: FlightEntry) -> Self {
+        Self {
+            id: Some(value.id),
+            airline: value.airline,
+            confirmation_code: value.confirmation_code,
+            departure_time: value.departure_time,
+            arrival_time: value.arrival_time,
+            departure_timezone: value.departure_timezone,
+            arrival_timezone: value.arrival_timezone,
+            departure_airport: value.departure_airport,
+            arrival_airport: value.arrival_airport,
+            departure_location: value.departure_location,
+            arrival_location: value.arrival_location,
+            details: *value.details,
+            position: value.item.position,
+            visibility: value.item.visibility,
+            notes: value.notes,
+        }
+    }
+}
+
+impl StayRecord {
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        let stay = StayFields {
+            summary: self.summary,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            check_in_time: self.check_in_time,
+            check_out_time: self.check_out_time,
+            address: self.address,
+            location: self.location,
+            confirmation_number: self.confirmation_number,
+            notes: self.notes,
+        }
+        .validated()?;
+
+        Ok(Self {
+            id: self.id,
+            summary: stay.summary,
+            start_date: stay.start_date,
+            end_date: stay.end_date,
+            check_in_time: stay.check_in_time,
+            check_out_time: stay.check_out_time,
+            address: stay.address,
+            location: stay.location,
+            confirmation_number: stay.confirmation_number,
+            position: self.position,
+            visibility: self.visibility,
+            notes: stay.notes,
+        })
+    }
+}
+
+impl From<StayEntry> for StayRecord {
+    fn from(value: StayEntry) -> Self {
+        Self {
+            id: Some(value.id),
+            summary: value.summary,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            check_in_time: value.check_in_time,
+            check_out_time: value.check_out_time,
+            address: value.address,
+            location: value.location,
+            confirmation_number: value.confirmation_number,
+            position: value.item.position,
+            visibility: value.item.visibility,
+            notes: value.notes,
+        }
+    }
+}
+
+impl ActivityRecord {
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        let activity = ActivityFields {
+            summary: self.summary,
+            category: self.category,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            address: self.address,
+            location: self.location,
+            booking_reference: self.booking_reference,
+            cost: self.cost,
+            notes: self.notes,
+        }
+        .validated()?;
+
+        Ok(Self {
+            id: self.id,
+            summary: activity.summary,
+            category: activity.category,
+            start_date: activity.start_date,
+            end_date: activity.end_date,
+            start_time: activity.start_time,
+            end_time: activity.end_time,
+            address: activity.address,
+            location: activity.location,
+            booking_reference: activity.booking_reference,
+            cost: activity.cost,
+            position: self.position,
+            visibility: self.visibility,
+            notes: activity.notes,
+        })
+    }
+}
+
+impl From<ActivityEntry> for ActivityRecord {
+    fn from(value: ActivityEntry) -> Self {
+        Self {
+            id: Some(value.id),
+            summary: value.summary,
+            category: value.category,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            address: value.address,
+            location: value.location,
+            booking_reference: value.booking_reference,
+            cost: value.cost,
+            position: value.item.position,
+            visibility: value.item.visibility,
+            notes: value.notes,
+        }
+    }
+}
+
+impl TravelLegRecord {
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        let leg = TravelLegFields {
+            name: self.name,
+            travel_leg_type: self.travel_leg_type,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            start_timezone: self.start_timezone,
+            end_timezone: self.end_timezone,
+            start_place: self.start_place,
+            end_place: self.end_place,
+            start_location: self.start_location,
+            end_location: self.end_location,
+            operator: self.operator,
+            booking_reference: self.booking_reference,
+            details: self.details,
+            notes: self.notes,
+        }
+        .validated()?;
+
+        Ok(Self {
+            id: self.id,
+            name: leg.name,
+            travel_leg_type: leg.travel_leg_type,
+            start_date: leg.start_date,
+            end_date: leg.end_date,
+            start_time: leg.start_time,
+            end_time: leg.end_time,
+            start_place: leg.start_place,
+            end_place: leg.end_place,
+            start_location: leg.start_location,
+            end_location: leg.end_location,
+            start_timezone: leg.start_timezone,
+            end_timezone: leg.end_timezone,
+            operator: leg.operator,
+            booking_reference: leg.booking_reference,
+            details: leg.details,
+            position: self.position,
+            visibility: self.visibility,
+            notes: leg.notes,
+        })
+    }
+}
+
+impl From<TravelLegEntry> for TravelLegRecord {
+    fn from(value: TravelLegEntry) -> Self {
+        Self {
+            id: Some(value.id),
+            name: value.name,
+            travel_leg_type: value.travel_leg_type,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            start_place: value.start_place,
+            end_place: value.end_place,
+            start_location: value.start_location,
+            end_location: value.end_location,
+            start_timezone: value.start_timezone,
+            end_timezone: value.end_timezone,
+            operator: value.operator,
+            booking_reference: value.booking_reference,
+            details: *value.details,
+            position: value.item.position,
+            visibility: value.item.visibility,
+            notes: value.notes,
+        }
+    }
+}
+
+/// Users are matched by email, since their ids differ between environments.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareRecord {
+    pub email: String,
+    pub share_type: ItineraryShareType,
+    pub share_message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stay(summary: &str) -> StayRecord {
+        StayRecord {
+            id: None,
+            summary: summary.to_string(),
+            start_date: NaiveDate::from_ymd_opt(2026, 5, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2026, 5, 3).unwrap(),
+            check_in_time: None,
+            check_out_time: None,
+            address: Some("  Rua Augusta 1  ".to_string()),
+            location: None,
+            confirmation_number: None,
+            position: Some(2),
+            visibility: ItemVisibility::Editors,
+            notes: String::new(),
+        }
+    }
+
+    #[test]
+    fn records_are_checked_like_new_bookings() {
+        let validated = stay("Hotel").validated().unwrap();
+        assert_eq!(validated.address.as_deref(), Some("Rua Augusta 1"));
+        assert_eq!(validated.position, Some(2));
+        assert_eq!(validated.visibility, ItemVisibility::Editors);
+
+        let error = stay(&"x".repeat(256)).validated().unwrap_err();
+        assert_eq!(error.field, "summary");
+    }
+}
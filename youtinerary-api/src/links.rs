@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use sqlx::types::Uuid;
+
+/// Where the itinerary routes are mounted.
+pub const API_PREFIX: &str = "/api/v0";
+
+/// The address clients reach the API at, for links handed out in responses.
+#[derive(Clone)]
+pub struct PublicUrl {
+    base_url: Arc<str>,
+}
+
+impl PublicUrl {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').into(),
+        }
+    }
+
+    /// The secret calendar feed of an itinerary, which calendar apps fetch without signing in.
+    pub fn feed_calendar(&self, token: Uuid) -> String {
+        format!(
+            "{}{}/feeds/{}/calendar.ics",
+            self.base_url,
+            API_PREFIX,
+            token.simple()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_urls_are_absolute_and_under_the_api_prefix() {
+        let token: Uuid = "5f0c8d1e-2b7a-4c3e-9d61-0a4b2c8e7f13".parse().unwrap();
+        let expected =
+            "https://trips.example.com/api/v0/feeds/5f0c8d1e2b7a4c3e9d610a4b2c8e7f13/calendar.ics";
+
+        assert_eq!(
+            PublicUrl::new("https://trips.example.com").feed_calendar(token),
+            expected
+        );
+        assert_eq!(
+            PublicUrl::new("https://trips.example.com/").feed_calendar(token),
+            expected
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Wall clock time of `instant` in `timezone`, in UTC when the zone is unknown.
+pub fn to_local(instant: DateTime<Utc>, timezone: Option<Tz>) -> NaiveDateTime {
+    instant
+        .with_timezone(&timezone.unwrap_or(Tz::UTC))
+        .naive_local()
+}
+
+/// Parses a zone read back from the database. Zones are validated before they are stored, so a
+/// name chrono-tz doesn't know only shows up for rows written before that and is dropped.
+pub fn parse_stored_timezone(timezone: Option<String>) -> Option<Tz> {
+    timezone.and_then(|timezone| timezone.parse().ok())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeInputError {
+    Missing,
+    Conflicting,
+    MissingTimezone,
+    Skipped(NaiveDateTime, Tz),
+}
+
+impl fmt::Display for TimeInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(
+                f,
+                "either a time with an offset or a local time is required"
+            ),
+            Self::Conflicting => write!(
+                f,
+                "give either a time with an offset or a local time, not both"
+            ),
+            Self::MissingTimezone => write!(f, "a local time needs a timezone"),
+            Self::Skipped(local, timezone) => write!(
+                f,
+                "{} does not exist in {}, the clocks skip it",
+                local,
+                timezone.name()
+            ),
+        }
+    }
+}
+
+/// Resolves a time given either as an instant, or as the wall clock time printed on a ticket
+/// together with the zone it is in.
+///
+/// A wall clock time repeated when the clocks go back is read as the first of the two, which
+/// is how timetables print departures in that hour.
+pub fn resolve_instant(
+    instant: Option<DateTime<Utc>>,
+    local: Option<NaiveDateTime>,
+    timezone: Option<Tz>,
+) -> Result<DateTime<Utc>, TimeInputError> {
+    match (instant, local) {
+        (Some(instant), None) => Ok(instant),
+        (Some(_), Some(_)) => Err(TimeInputError::Conflicting),
+        (None, None) => Err(TimeInputError::Missing),
+        (None, Some(local)) => {
+            let timezone = timezone.ok_or(TimeInputError::MissingTimezone)?;
+            match timezone.from_local_datetime(&local) {
+                LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => {
+                    Ok(time.with_timezone(&Utc))
+                }
+                LocalResult::None => Err(TimeInputError::Skipped(local, timezone)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn instant(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn instants_are_taken_as_given() {
+        let departure = instant("2026-07-01T08:30:00Z");
+        assert_eq!(
+            resolve_instant(Some(departure), None, Some(Tz::Asia__Tokyo)),
+            Ok(departure)
+        );
+    }
+
+    #[test]
+    fn local_times_resolve_in_their_zone() {
+        assert_eq!(
+            resolve_instant(
+                None,
+                Some(local("2026-07-01 09:30")),
+                Some(Tz::Europe__Lisbon)
+            ),
+            Ok(instant("2026-07-01T08:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn departure_and_arrival_resolve_in_their_own_zones() {
+        // TP 209 leaves Lisbon at 10:30 local time and lands in New York at 13:00 local time.
+        let departure = resolve_instant(
+            None,
+            Some(local("2026-07-01 10:30")),
+            Some(Tz::Europe__Lisbon),
+        );
+        let arrival = resolve_instant(
+            None,
+            Some(local("2026-07-01 13:00")),
+            Some(Tz::America__New_York),
+        );
+        assert_eq!(departure, Ok(instant("2026-07-01T09:30:00Z")));
+        assert_eq!(arrival, Ok(instant("2026-07-01T17:00:00Z")));
+        assert_eq!(
+            arrival.unwrap() - departure.unwrap(),
+            chrono::Duration::minutes(450)
+        );
+    }
+
+    #[test]
+    fn local_times_skipped_by_the_clocks_are_rejected() {
+        let skipped = local("2026-03-29 01:30");
+        assert_eq!(
+            resolve_instant(None, Some(skipped), Some(Tz::Europe__London)),
+            Err(TimeInputError::Skipped(skipped, Tz::Europe__London))
+        );
+    }
+
+    #[test]
+    fn repeated_local_times_are_read_as_the_first() {
+        assert_eq!(
+            resolve_instant(
+                None,
+                Some(local("2026-10-25 01:30")),
+                Some(Tz::Europe__London)
+            ),
+            Ok(instant("2026-10-25T00:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn local_times_need_exactly_one_source_and_a_zone() {
+        let departure = instant("2026-07-01T08:30:00Z");
+        let wall_clock = local("2026-07-01 09:30");
+        assert_eq!(
+            resolve_instant(None, None, Some(Tz::UTC)),
+            Err(TimeInputError::Missing)
+        );
+        assert_eq!(
+            resolve_instant(Some(departure), Some(wall_clock), Some(Tz::UTC)),
+            Err(TimeInputError::Conflicting)
+        );
+        assert_eq!(
+            resolve_instant(None, Some(wall_clock), None),
+            Err(TimeInputError::MissingTimezone)
+        );
+    }
+}
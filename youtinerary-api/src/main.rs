@@ -1,9 +1,28 @@
+mod access;
+mod activities;
+mod airports;
 pub mod error_handling;
+mod etag;
 mod features;
+mod fields;
+mod flights;
 mod health_check;
+mod ical;
+mod ingest;
+mod items;
+mod itinerary_document;
+mod links;
+mod local_time;
+mod map;
 mod models;
 mod middlewares;
+mod smtp;
+mod stays;
+mod timeline;
+mod travel_legs;
+mod validation;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -14,6 +33,7 @@ pub use models::*;
 use oauth2::basic::BasicClient;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use tokio::sync::Notify;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry};
@@ -25,7 +45,9 @@ use youtinerary_auth::authorize;
 use youtinerary_auth::login_authorized;
 use youtinerary_auth::protected;
 
-use self::features::itineraries_router;
+use self::features::{itineraries_router, process_inbox};
+use self::links::{PublicUrl, API_PREFIX};
+use self::smtp::Inbox;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -33,6 +55,8 @@ pub struct AppState {
     redis: redis::Client,
     oauth_client: BasicClient,
     reqwest_client: reqwest::Client,
+    inbox: Inbox,
+    public_url: PublicUrl,
 }
 
 
@@ -50,6 +74,18 @@ impl FromRef<AppState> for reqwest::Client {
     }
 }
 
+impl FromRef<AppState> for Inbox {
+    fn from_ref(state: &AppState) -> Self {
+        state.inbox.clone()
+    }
+}
+
+impl FromRef<AppState> for PublicUrl {
+    fn from_ref(state: &AppState) -> Self {
+        state.public_url.clone()
+    }
+}
+
 impl FromRef<AppState> for PgPool {
     fn from_ref(state: &AppState) -> Self {
         state.pool.clone()
@@ -86,11 +122,32 @@ async fn main() -> Result<()> {
     let redis = redis::Client::open(std::env::var("REDIS_URL")?).unwrap();
     sqlx::migrate!().run(&pool).await?;
 
+    let smtp_settings = settings.smtp_settings;
+    let inbox = Inbox::new(smtp_settings.as_ref().map(|x| x.domain.as_str()));
+    if let Some(smtp_settings) = smtp_settings {
+        let smtp_listener = tokio::net::TcpListener::bind(SocketAddr::from((
+            smtp_settings.addr,
+            smtp_settings.port,
+        )))
+        .await
+        .context("failed to bind the SMTP TcpListener")?;
+        let queued = Arc::new(Notify::new());
+        tokio::spawn(smtp::serve(
+            smtp_listener,
+            pool.clone(),
+            smtp_settings.domain,
+            queued.clone(),
+        ));
+        tokio::spawn(process_inbox(pool.clone(), queued));
+    }
+
     let state = AppState {
         pool,
         redis,
         oauth_client: settings.auth_settings.try_into()?,
         reqwest_client: reqwest::Client::new(),
+        inbox,
+        public_url: PublicUrl::new(&settings.app_settings.base_url),
     };
 
     let listener = tokio::net::TcpListener::bind(SocketAddr::from((
@@ -109,7 +166,7 @@ async fn main() -> Result<()> {
         .route("/protected", get(protected))
         .route("/authorize", get(authorize))
         .route("/authorized", get(login_authorized))
-        .nest("/api/v0", itineraries_router())
+        .nest(API_PREFIX, itineraries_router())
         // .route("", get(retrieve))
         .with_state(state);
 
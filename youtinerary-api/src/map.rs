@@ -0,0 +1,202 @@
+use serde_json::{json, Value};
+
+use crate::timeline::{Coordinates, TimelineEntry};
+
+/// A located part of an itinerary: stays and activities are places, travel legs and flights
+/// are lines from where they start to where they end. Flights without both airports are left
+/// out.
+enum Feature<'a> {
+    Place {
+        kind: &'static str,
+        entry: &'a TimelineEntry,
+        location: Coordinates,
+    },
+    Line {
+        entry: &'a TimelineEntry,
+        start: Coordinates,
+        end: Coordinates,
+    },
+}
+
+fn features(timeline: &[TimelineEntry]) -> impl Iterator<Item = Feature<'_>> {
+    timeline.iter().filter_map(|entry| match entry {
+        TimelineEntry::Stay(stay) => Some(Feature::Place {
+            kind: "stay",
+            entry,
+            location: stay.location?,
+        }),
+        TimelineEntry::Activity(activity) => Some(Feature::Place {
+            kind: "activity",
+            entry,
+            location: activity.location?,
+        }),
+        TimelineEntry::TravelLeg(leg) => Some(Feature::Line {
+            entry,
+            start: leg.start_location?,
+            end: leg.end_location?,
+        }),
+        TimelineEntry::Flight(flight) => Some(Feature::Line {
+            entry,
+            start: flight.departure_location?,
+            end: flight.arrival_location?,
+        }),
+    })
+}
+
+fn position(coordinates: Coordinates) -> Value {
+    json!([coordinates.longitude, coordinates.latitude])
+}
+
+/// Renders an RFC 7946 FeatureCollection. Every feature's properties are the timeline entry
+/// it comes from, so they carry the same fields as the itinerary's timeline.
+pub fn itinerary_geojson(name: &str, timeline: &[TimelineEntry]) -> Value {
+    let features: Vec<Value> = features(timeline)
+        .map(|feature| {
+            let (entry, geometry) = match feature {
+                Feature::Place {
+                    entry, location, ..
+                } => (
+                    entry,
+                    json!({ "type": "Point", "coordinates": position(location) }),
+                ),
+                Feature::Line { entry, start, end } => (
+                    entry,
+                    json!({
+                        "type": "LineString",
+                        "coordinates": [position(start), position(end)],
+                    }),
+                ),
+            };
+            let mut properties = serde_json::to_value(entry).unwrap_or_default();
+            if let Some(properties) = properties.as_object_mut() {
+                properties.retain(|key, _| !key.ends_with("location"));
+            }
+
+            json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "name": name,
+        "features": features,
+    })
+}
+
+/// Renders a GPX 1.1 document with a waypoint per place and a two point route per travel leg.
+pub fn itinerary_gpx(name: &str, timeline: &[TimelineEntry]) -> String {
+    let mut gpx = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<gpx version="1.1" creator="youtinerary" xmlns="http://www.topografix.com/GPX/1/1">"#,
+        "\n",
+    ));
+    gpx.push_str(&format!(
+        "  <metadata>\n    <name>{}</name>\n  </metadata>\n",
+        escape_xml(name)
+    ));
+
+    let features: Vec<Feature> = features(timeline).collect();
+    // GPX wants every waypoint ahead of the routes.
+    for feature in &features {
+        if let Feature::Place {
+            kind,
+            entry,
+            location,
+        } = feature
+        {
+            let (title, description) = describe(entry);
+            gpx.push_str(&format!(
+                "  <wpt lat=\"{}\" lon=\"{}\">\n    <name>{}</name>\n    <desc>{}</desc>\n    <type>{}</type>\n  </wpt>\n",
+                location.latitude,
+                location.longitude,
+                escape_xml(&title),
+                escape_xml(&description),
+                kind
+            ));
+        }
+    }
+    for feature in &features {
+        if let Feature::Line { entry, start, end } = feature {
+            let (title, description) = describe(entry);
+            let kind = match entry {
+                TimelineEntry::TravelLeg(leg) => {
+                    serde_json::to_value(leg.travel_leg_type).unwrap_or_default()
+                }
+                _ => Value::from("flight"),
+            };
+            gpx.push_str(&format!(
+                "  <rte>\n    <name>{}</name>\n    <desc>{}</desc>\n    <type>{}</type>\n",
+                escape_xml(&title),
+                escape_xml(&description),
+                kind.as_str().unwrap_or("other")
+            ));
+            for point in [start, end] {
+                gpx.push_str(&format!(
+                    "    <rtept lat=\"{}\" lon=\"{}\"/>\n",
+                    point.latitude, point.longitude
+                ));
+            }
+            gpx.push_str("  </rte>\n");
+        }
+    }
+
+    gpx.push_str("</gpx>\n");
+    gpx
+}
+
+/// Title and description of a located entry, the description holding its dates and notes.
+fn describe(entry: &TimelineEntry) -> (String, String) {
+    let (title, start_date, end_date, notes) = match entry {
+        TimelineEntry::Stay(stay) => (&stay.summary, stay.start_date, stay.end_date, &stay.notes),
+        TimelineEntry::Activity(activity) => (
+            &activity.summary,
+            activity.start_date,
+            activity.end_date,
+            &activity.notes,
+        ),
+        TimelineEntry::TravelLeg(leg) => (&leg.name, leg.start_date, leg.end_date, &leg.notes),
+        TimelineEntry::Flight(flight) => {
+            let title = match (&flight.departure_airport, &flight.arrival_airport) {
+                (Some(from), Some(to)) => format!("{} {} to {}", flight.title(), from, to),
+                _ => flight.title(),
+            };
+            let departs = flight.local_departure().format("%Y-%m-%d %H:%M").to_string();
+            return (title, join_notes(departs, &flight.notes));
+        }
+    };
+
+    let dates = if start_date == end_date {
+        start_date.to_string()
+    } else {
+        format!("{} to {}", start_date, end_date)
+    };
+    (title.clone(), join_notes(dates, notes))
+}
+
+fn join_notes(when: String, notes: &str) -> String {
+    if notes.is_empty() {
+        when
+    } else {
+        format!("{}\n{}", when, notes)
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -9,10 +9,10 @@ use axum::http::request::Parts;
 use crate::models::User;
 
 trait UserRepository {
-    async fn get_user<'a>(&self, email: &'a str) -> Result<Option<User>>;
+    async fn get_user(&self, email: &str) -> Result<Option<User>>;
 }
 impl UserRepository for PgPool {
-    async fn get_user<'a>(&self, email: &'a str) -> Result<Option<User>> {
+    async fn get_user(&self, email: &str) -> Result<Option<User>> {
         let user = sqlx::query_as!(
             User,
             r#"
@@ -1,25 +1,87 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{
-    types::chrono::{DateTime, NaiveDate, Utc},
+    types::chrono::{DateTime, NaiveDate, NaiveTime, Utc},
     FromRow,
 };
 
+use crate::activities::Cost;
+use crate::timeline::Coordinates;
+
 #[derive(Debug)]
 pub struct User {
     pub id: i32,
     pub email: String,
 }
 
-#[derive(sqlx::Type, Serialize, Deserialize)]
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "itinerary_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum ItineraryStatus {
     Draft,
     Published,
     Archived,
 }
 
-#[derive(sqlx::Type, Serialize, Deserialize)]
+impl ItineraryStatus {
+    /// Whether an itinerary may move from this status to `next`.
+    ///
+    /// Drafts and published itineraries can be archived, and publishing always starts from a
+    /// draft. An archived itinerary only goes back to draft through a status change, while
+    /// restoring it returns it to the status it was archived from, or draft when that is unknown.
+    pub fn can_transition_to(self, next: ItineraryStatus) -> bool {
+        use ItineraryStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft, Published)
+                | (Published, Draft)
+                | (Draft, Archived)
+                | (Published, Archived)
+                | (Archived, Draft)
+        )
+    }
+}
+
+/// Why an itinerary can't be published, or stay published after a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishError {
+    MissingName,
+    MissingDates,
+    EndBeforeStart,
+}
+
+impl PublishError {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::MissingName => "A published itinerary needs a name",
+            Self::MissingDates => "A published itinerary needs a start_date and an end_date",
+            Self::EndBeforeStart => "A published itinerary must not end before it starts",
+        }
+    }
+}
+
+/// Checks what a published itinerary must have: a name and both of its effective dates, in order.
+pub fn check_publishable(
+    name: &str,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<(), PublishError> {
+    if name.trim().is_empty() {
+        return Err(PublishError::MissingName);
+    }
+    let (Some(start_date), Some(end_date)) = (start_date, end_date) else {
+        return Err(PublishError::MissingDates);
+    };
+    if end_date < start_date {
+        return Err(PublishError::EndBeforeStart);
+    }
+
+    Ok(())
+}
+
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "itinerary_share_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum ItineraryShareType {
     Editor,
     Viewer,
@@ -34,41 +96,88 @@ pub struct ItineraryShare {
     pub share_message: String,
 }
 
+/// What every booking of an itinerary has in common. Each kind's row points at its item, and
+/// goes away with it.
 #[derive(FromRow, Serialize, Deserialize)]
 pub struct ItineraryItem {
     pub id: i32,
-    pub name: String,
     pub itinerary_id: i32,
+    pub item_type: ItineraryItemType,
+    pub position: Option<i32>,
+    pub visibility: ItemVisibility,
 }
 
-#[derive(sqlx::Type, Serialize, Deserialize)]
-pub struct ItineraryStay {
-    pub itinerary_id: i32,
-    pub stay_id: i32,
-}
-
-#[derive(FromRow, Serialize, Deserialize)]
+/// A row of `stays`. sqlx can't decode a `point`, so it isn't read with `FromRow` and its
+/// `location` is selected as coordinates instead.
+#[derive(Serialize, Deserialize)]
 pub struct Stay {
     pub id: i32,
-    pub summary: i32,
+    pub itinerary_item_id: i32,
+    pub summary: String,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
-    pub location: String,
+    pub check_in_time: Option<NaiveTime>,
+    pub check_out_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub confirmation_number: Option<String>,
     pub notes: String,
 }
 
-#[derive(FromRow, Serialize, Deserialize)]
+/// A row of `activities`, read like [`Stay`] since it has a `point` too.
+#[derive(Serialize, Deserialize)]
 pub struct Activity {
     pub id: i32,
-    pub summary: i32,
+    pub itinerary_item_id: i32,
+    pub summary: String,
+    pub category: ActivityCategory,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
-    pub location: String,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub booking_reference: Option<String>,
+    pub cost: Option<Cost>,
     pub notes: String,
 }
 
-#[derive(sqlx::Type, Serialize, Deserialize)]
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[sqlx(type_name = "activity_category", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityCategory {
+    Sightseeing,
+    Dining,
+    Event,
+    Tour,
+    Outdoors,
+    Shopping,
+    Nightlife,
+    Wellness,
+    #[default]
+    Other,
+}
+
+impl ActivityCategory {
+    /// Name of the category as shown to people, such as in calendar categories.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Sightseeing => "Sightseeing",
+            Self::Dining => "Dining",
+            Self::Event => "Event",
+            Self::Tour => "Tour",
+            Self::Outdoors => "Outdoors",
+            Self::Shopping => "Shopping",
+            Self::Nightlife => "Nightlife",
+            Self::Wellness => "Wellness",
+            Self::Other => "Other",
+        }
+    }
+}
+
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[sqlx(type_name = "travel_leg_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum TravelLegType {
     Flight,
     Train,
@@ -78,13 +187,122 @@ pub enum TravelLegType {
     Other,
 }
 
-#[derive(FromRow, Serialize, Deserialize)]
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "cabin_class", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CabinClass {
+    Economy,
+    PremiumEconomy,
+    Business,
+    First,
+}
+
+/// Kind of booking an itinerary item is, each stored in its own table.
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "itinerary_item_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ItineraryItemType {
+    Flight,
+    Stay,
+    Activity,
+    TravelLeg,
+}
+
+/// Who an itinerary item is shown to. Viewers and the calendar feed only get `Everyone` items.
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[sqlx(type_name = "itinerary_item_visibility", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ItemVisibility {
+    #[default]
+    Everyone,
+    Editors,
+}
+
+/// Kind of item an imported calendar event was turned into.
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "calendar_event_item_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarEventItemType {
+    Flight,
+    Stay,
+    Activity,
+}
+
+/// A row of `travel_legs`, read like [`Stay`].
+#[derive(Serialize, Deserialize)]
 pub struct TravelLeg {
     pub id: i32,
-    pub from: i32,
-    pub to: i32,
-    pub start: DateTime<Utc>,
-    pub end: DateTime<Utc>,
-    pub tz_start: String,
-    pub tz_end: String,
+    pub itinerary_item_id: i32,
+    pub name: String,
+    pub travel_leg_type: TravelLegType,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// IANA zones of both ends, checked against chrono-tz before they are stored.
+    pub start_timezone: Option<String>,
+    pub end_timezone: Option<String>,
+    pub start_place: Option<String>,
+    pub end_place: Option<String>,
+    pub start_location: Option<Coordinates>,
+    pub end_location: Option<Coordinates>,
+    pub operator: Option<String>,
+    pub booking_reference: Option<String>,
+    pub service_number: Option<String>,
+    pub coach: Option<String>,
+    pub seat: Option<String>,
+    pub platform: Option<String>,
+    pub vehicle: Option<String>,
+    pub cabin: Option<String>,
+    pub notes: String,
+}
+
+/// Where a message received at a user's inbox address is in parsing. Parsed messages may still
+/// have no bookings in them.
+#[derive(sqlx::Type, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "inbox_message_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum InboxMessageStatus {
+    Queued,
+    Parsed,
+    Failed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2026, 5, day)
+    }
+
+    #[test]
+    fn publishable_itineraries_have_a_name_and_ordered_dates() {
+        assert_eq!(check_publishable("Lisbon", date(1), date(4)), Ok(()));
+        assert_eq!(check_publishable("Lisbon", date(1), date(1)), Ok(()));
+    }
+
+    #[test]
+    fn blank_names_cannot_be_published() {
+        assert_eq!(
+            check_publishable("  ", date(1), date(4)),
+            Err(PublishError::MissingName)
+        );
+    }
+
+    #[test]
+    fn missing_or_inverted_dates_cannot_be_published() {
+        assert_eq!(
+            check_publishable("Lisbon", None, date(4)),
+            Err(PublishError::MissingDates)
+        );
+        assert_eq!(
+            check_publishable("Lisbon", date(1), None),
+            Err(PublishError::MissingDates)
+        );
+        assert_eq!(
+            check_publishable("Lisbon", date(4), date(1)),
+            Err(PublishError::EndBeforeStart)
+        );
+    }
 }
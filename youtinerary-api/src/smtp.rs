@@ -0,0 +1,601 @@
+//! Receives the confirmation emails users forward to their inbox address.
+//!
+//! Only mail for `plans+<token>@<domain>` is accepted, nothing is ever relayed. Messages are
+//! queued as they are received and parsed into bookings in the background. To try it locally,
+//! set `APP_SMTP_SETTINGS__PORT=2525` and `APP_SMTP_SETTINGS__DOMAIN=localhost`, then send a
+//! saved confirmation with e.g.
+//! `swaks --server localhost:2525 --to plans+<token>@localhost --data booking.eml`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+
+/// Local part of every inbox address, the user's token goes after the `+`.
+pub const MAILBOX: &str = "plans";
+
+const MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+/// More than the 1000 octets RFC 5321 allows, since some senders don't wrap their HTML.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+const MAX_RECIPIENTS: usize = 100;
+const MAX_ADDRESS_LENGTH: usize = 255;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Where users forward their confirmations to, when the service receives mail at all.
+#[derive(Clone, Default)]
+pub struct Inbox {
+    domain: Option<Arc<str>>,
+}
+
+impl Inbox {
+    pub fn new(domain: Option<&str>) -> Self {
+        Self {
+            domain: domain.map(Arc::from),
+        }
+    }
+
+    pub fn address(&self, token: Uuid) -> Option<String> {
+        self.domain
+            .as_ref()
+            .map(|domain| format!("{}+{}@{}", MAILBOX, token.simple(), domain))
+    }
+}
+
+/// Accepts connections until the service stops. `queued` is notified of every queued message.
+pub async fn serve(listener: TcpListener, db: PgPool, domain: String, queued: Arc<Notify>) {
+    let domain: Arc<str> = domain.into();
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                tracing::error!("failed to accept an SMTP connection: {:?}", error);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let session = Session {
+            db: db.clone(),
+            domain: domain.clone(),
+            queued: queued.clone(),
+        };
+        tokio::spawn(async move {
+            if let Err(error) = session.run(stream).await {
+                tracing::warn!("SMTP session with {} ended: {:?}", peer, error);
+            }
+        });
+    }
+}
+
+struct Session<R> {
+    db: R,
+    domain: Arc<str>,
+    queued: Arc<Notify>,
+}
+
+/// The sender and the users a message is for, as given by `MAIL` and `RCPT`.
+struct Envelope {
+    sender: String,
+    user_ids: Vec<i32>,
+    recipients: usize,
+}
+
+impl<R: MailRepository> Session<R> {
+    async fn run(self, stream: impl AsyncRead + AsyncWrite + Unpin) -> Result<()> {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        let mut envelope: Option<Envelope> = None;
+
+        reply(&mut writer, &format!("220 {} ESMTP ready", self.domain)).await?;
+        loop {
+            match read_line(&mut reader, &mut line).await? {
+                ReadLine::Line => {}
+                ReadLine::TooLong => {
+                    reply(&mut writer, "500 5.5.6 Line too long").await?;
+                    continue;
+                }
+                ReadLine::Closed => return Ok(()),
+            }
+            let command = String::from_utf8_lossy(&line);
+            let command = command.trim_end_matches(['\r', '\n']);
+            let (verb, argument) = command.split_once(' ').unwrap_or((command, ""));
+
+            let response = match verb.to_ascii_uppercase().as_str() {
+                "EHLO" => {
+                    envelope = None;
+                    format!(
+                        "250-{}\r\n250-8BITMIME\r\n250-PIPELINING\r\n250 SIZE {}",
+                        self.domain, MAX_MESSAGE_BYTES
+                    )
+                }
+                "HELO" => {
+                    envelope = None;
+                    format!("250 {}", self.domain)
+                }
+                "MAIL" => match (&envelope, path_argument(argument, "FROM:")) {
+                    (Some(_), _) => "503 5.5.1 Sender already given".into(),
+                    (None, None) => "501 5.5.4 Syntax: MAIL FROM:<address>".into(),
+                    (None, Some((sender, _))) if sender.len() > MAX_ADDRESS_LENGTH => {
+                        "501 5.1.7 Sender address too long".into()
+                    }
+                    (None, Some((_, parameters)))
+                        if declared_size(parameters) > MAX_MESSAGE_BYTES =>
+                    {
+                        "552 5.3.4 Message too big".into()
+                    }
+                    (None, Some((sender, _))) => {
+                        envelope = Some(Envelope {
+                            sender: sender.to_string(),
+                            user_ids: Vec::new(),
+                            recipients: 0,
+                        });
+                        "250 2.1.0 OK".into()
+                    }
+                },
+                "RCPT" => match (envelope.as_mut(), path_argument(argument, "TO:")) {
+                    (None, _) => "503 5.5.1 Need MAIL first".into(),
+                    (Some(_), None) => "501 5.5.4 Syntax: RCPT TO:<address>".into(),
+                    (Some(envelope), _) if envelope.recipients >= MAX_RECIPIENTS => {
+                        "452 4.5.3 Too many recipients".into()
+                    }
+                    (Some(envelope), Some((recipient, _))) => {
+                        match self.find_recipient(recipient).await {
+                            Ok(Some(user_id)) => {
+                                envelope.recipients += 1;
+                                if !envelope.user_ids.contains(&user_id) {
+                                    envelope.user_ids.push(user_id);
+                                }
+                                "250 2.1.5 OK".into()
+                            }
+                            Ok(None) => "550 5.1.1 No such mailbox".into(),
+                            Err(error) => {
+                                tracing::error!("failed to look up an inbox: {:?}", error);
+                                "451 4.3.0 Try again later".into()
+                            }
+                        }
+                    }
+                },
+                "DATA" => match envelope.take() {
+                    None => "503 5.5.1 Need MAIL first".into(),
+                    Some(envelope) if envelope.user_ids.is_empty() => {
+                        "554 5.5.1 No valid recipients".into()
+                    }
+                    Some(envelope) => {
+                        reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+                        match read_data(&mut reader, &mut line).await? {
+                            Err(rejection) => rejection.into(),
+                            Ok(data) => match self.queue(&envelope, &data).await {
+                                Ok(()) => "250 2.0.0 Queued".into(),
+                                Err(error) => {
+                                    tracing::error!("failed to queue a message: {:?}", error);
+                                    "451 4.3.0 Try again later".into()
+                                }
+                            },
+                        }
+                    }
+                },
+                "RSET" => {
+                    envelope = None;
+                    "250 2.0.0 OK".into()
+                }
+                "NOOP" => "250 2.0.0 OK".into(),
+                "VRFY" => "252 2.5.0 Cannot verify the mailbox".into(),
+                "QUIT" => {
+                    reply(&mut writer, "221 2.0.0 Bye").await?;
+                    return Ok(());
+                }
+                _ => "502 5.5.2 Command not recognized".into(),
+            };
+            reply(&mut writer, &response).await?;
+        }
+    }
+
+    /// The user an address is the inbox of. Other addresses, on this domain or not, are refused.
+    async fn find_recipient(&self, address: &str) -> Result<Option<i32>> {
+        match inbox_token(address, &self.domain) {
+            Some(token) => self.db.find_inbox_user(token).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn queue(&self, envelope: &Envelope, data: &[u8]) -> Result<()> {
+        // Postgres can't store NUL characters in text, and no confirmation needs them.
+        let raw = String::from_utf8_lossy(data).replace('\0', "");
+        self.db
+            .queue_message(&envelope.user_ids, &envelope.sender, &raw)
+            .await?;
+        self.queued.notify_one();
+
+        Ok(())
+    }
+}
+
+async fn reply(writer: &mut (impl AsyncWrite + Unpin), response: &str) -> Result<()> {
+    writer.write_all(response.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReadLine {
+    Line,
+    /// The line was longer than `MAX_LINE_BYTES`. It was read to its end and dropped.
+    TooLong,
+    Closed,
+}
+
+/// Reads the next line, newline included, into `line`.
+async fn read_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+) -> Result<ReadLine> {
+    line.clear();
+    let read = timeout(
+        COMMAND_TIMEOUT,
+        (&mut *reader).take(MAX_LINE_BYTES).read_until(b'\n', line),
+    )
+    .await??;
+
+    if read == 0 {
+        return Ok(ReadLine::Closed);
+    }
+    if line.ends_with(b"\n") || (read as u64) < MAX_LINE_BYTES {
+        return Ok(ReadLine::Line);
+    }
+    loop {
+        line.clear();
+        let read = timeout(
+            COMMAND_TIMEOUT,
+            (&mut *reader).take(MAX_LINE_BYTES).read_until(b'\n', line),
+        )
+        .await??;
+        if read == 0 || line.ends_with(b"\n") {
+            line.clear();
+            return Ok(ReadLine::TooLong);
+        }
+    }
+}
+
+/// Reads a message up to the line with a single dot, undoing the dot stuffing. A message that
+/// is too big or has a line that is too long is still read to its end, so the session can go
+/// on, but is rejected with the reply to send instead.
+async fn read_data(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+) -> Result<Result<Vec<u8>, &'static str>> {
+    let mut data = Vec::new();
+    let mut rejection = None;
+    loop {
+        match read_line(reader, line).await? {
+            ReadLine::Line => {}
+            ReadLine::TooLong => {
+                rejection.get_or_insert("500 5.5.6 Line too long");
+                continue;
+            }
+            ReadLine::Closed => bail!("connection closed during DATA"),
+        }
+        if line == b".\r\n" || line == b".\n" {
+            return Ok(match rejection {
+                Some(rejection) => Err(rejection),
+                None => Ok(data),
+            });
+        }
+        if rejection.is_some() {
+            continue;
+        }
+        let content = line.strip_prefix(b".").unwrap_or(line);
+        if data.len() + content.len() > MAX_MESSAGE_BYTES {
+            rejection = Some("552 5.3.4 Message too big");
+            data = Vec::new();
+        } else {
+            data.extend_from_slice(content);
+        }
+    }
+}
+
+/// Splits `FROM:<address> PARAMETERS` into the address and the parameters. The address is empty
+/// for the null sender of bounces.
+fn path_argument<'a>(argument: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    let prefix = argument.get(..keyword.len())?;
+    if !prefix.eq_ignore_ascii_case(keyword) {
+        return None;
+    }
+    let rest = argument[keyword.len()..].trim_start();
+    let (path, parameters) = match rest.strip_prefix('<') {
+        Some(rest) => rest.split_once('>')?,
+        None => rest.split_once(' ').unwrap_or((rest, "")),
+    };
+
+    Some((path, parameters.trim()))
+}
+
+/// The `SIZE=` a client declares with `MAIL`, zero when it doesn't.
+fn declared_size(parameters: &str) -> usize {
+    parameters
+        .split_whitespace()
+        .filter_map(|x| x.split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("SIZE"))
+        .and_then(|(_, size)| size.parse().ok())
+        .unwrap_or(0)
+}
+
+/// The token of an inbox address on `domain`, if it is one.
+fn inbox_token(address: &str, domain: &str) -> Option<Uuid> {
+    let (local, address_domain) = address.rsplit_once('@')?;
+    if !address_domain.eq_ignore_ascii_case(domain) {
+        return None;
+    }
+    let (mailbox, token) = local.split_once('+')?;
+    if !mailbox.eq_ignore_ascii_case(MAILBOX) {
+        return None;
+    }
+
+    Uuid::parse_str(token).ok()
+}
+
+trait MailRepository {
+    async fn find_inbox_user(&self, token: Uuid) -> Result<Option<i32>>;
+
+    /// Queues one copy of the message for each user.
+    async fn queue_message(&self, user_ids: &[i32], sender: &str, raw: &str) -> Result<()>;
+}
+
+impl MailRepository for PgPool {
+    async fn find_inbox_user(&self, token: Uuid) -> Result<Option<i32>> {
+        let user = sqlx::query!(
+            r#"
+                select user_id
+                from users
+                where inbox_token = $1
+            "#,
+            token
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(user.map(|x| x.user_id))
+    }
+
+    async fn queue_message(&self, user_ids: &[i32], sender: &str, raw: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+                insert into inbox_messages (user_id, sender, raw)
+                select user_id, $2, $3
+                from unnest($1::integer[]) as user_id
+            "#,
+            user_ids,
+            sender,
+            raw
+        )
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    const TOKEN: &str = "0b6f9d1c6a2e4f0f9a7d3c2b1e0f4a5d";
+    const USER_ID: i32 = 7;
+
+    /// One user with an inbox, and the messages queued for it.
+    #[derive(Default)]
+    struct FakeInbox {
+        queued: Mutex<Vec<(Vec<i32>, String, String)>>,
+    }
+
+    impl MailRepository for &FakeInbox {
+        async fn find_inbox_user(&self, token: Uuid) -> Result<Option<i32>> {
+            Ok((token == Uuid::parse_str(TOKEN).unwrap()).then_some(USER_ID))
+        }
+
+        async fn queue_message(&self, user_ids: &[i32], sender: &str, raw: &str) -> Result<()> {
+            self.queued.lock().unwrap().push((
+                user_ids.to_vec(),
+                sender.to_string(),
+                raw.to_string(),
+            ));
+
+            Ok(())
+        }
+    }
+
+    /// Plays `input` to a session and returns the replies, one per line, and what was queued.
+    async fn converse(input: &[u8]) -> (Vec<String>, Vec<(Vec<i32>, String, String)>) {
+        let inbox = FakeInbox::default();
+        let session = Session {
+            db: &inbox,
+            domain: "example.com".into(),
+            queued: Arc::new(Notify::new()),
+        };
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (mut client_reader, mut client_writer) = tokio::io::split(client);
+        let mut output = Vec::new();
+        let (ran, _, read) = tokio::join!(
+            session.run(server),
+            // The session may hang up before reading everything, after a `QUIT`.
+            client_writer.write_all(input),
+            client_reader.read_to_end(&mut output),
+        );
+        ran.unwrap();
+        read.unwrap();
+
+        let replies = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        (replies, inbox.queued.into_inner().unwrap())
+    }
+
+    fn rcpt(address: &str) -> String {
+        format!("RCPT TO:<{}>\r\n", address)
+    }
+
+    fn inbox_address() -> String {
+        format!("plans+{}@example.com", TOKEN)
+    }
+
+    #[tokio::test]
+    async fn messages_for_an_inbox_are_queued_without_dot_stuffing() {
+        let input = format!(
+            "EHLO client.test\r\nMAIL FROM:<airline@example.org>\r\n{}DATA\r\n\
+             Subject: Your flight\r\n\r\n..leading dot\r\n...\r\n.\r\nQUIT\r\n",
+            rcpt(&inbox_address())
+        );
+        let (replies, queued) = converse(input.as_bytes()).await;
+
+        assert_eq!(
+            replies,
+            [
+                "220 example.com ESMTP ready",
+                "250-example.com",
+                "250-8BITMIME",
+                "250-PIPELINING",
+                "250 SIZE 10485760",
+                "250 2.1.0 OK",
+                "250 2.1.5 OK",
+                "354 End data with <CR><LF>.<CR><LF>",
+                "250 2.0.0 Queued",
+                "221 2.0.0 Bye",
+            ]
+        );
+        assert_eq!(
+            queued,
+            [(
+                vec![USER_ID],
+                "airline@example.org".to_string(),
+                "Subject: Your flight\r\n\r\n.leading dot\r\n..\r\n".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn only_inbox_addresses_on_the_domain_have_a_token() {
+        let token = Uuid::parse_str(TOKEN).unwrap();
+        assert_eq!(inbox_token(&inbox_address(), "example.com"), Some(token));
+        assert_eq!(
+            inbox_token(
+                &format!("Plans+{}@EXAMPLE.com", token.hyphenated()),
+                "example.com"
+            ),
+            Some(token)
+        );
+        assert_eq!(inbox_token(&inbox_address(), "example.org"), None);
+        assert_eq!(
+            inbox_token(&format!("trips+{}@example.com", TOKEN), "example.com"),
+            None
+        );
+        assert_eq!(inbox_token("plans@example.com", "example.com"), None);
+        assert_eq!(inbox_token("plans+nope@example.com", "example.com"), None);
+        assert_eq!(inbox_token("plans+nope", "example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn other_recipients_are_refused() {
+        let input = format!(
+            "HELO client.test\r\nMAIL FROM:<>\r\n{}{}{}DATA\r\nQUIT\r\n",
+            rcpt("postmaster@example.com"),
+            rcpt(&format!("plans+{}@example.com", Uuid::nil().simple())),
+            rcpt(&inbox_address().replace("example.com", "example.org")),
+        );
+        let (replies, queued) = converse(input.as_bytes()).await;
+
+        assert_eq!(
+            replies[3..],
+            [
+                "550 5.1.1 No such mailbox",
+                "550 5.1.1 No such mailbox",
+                "550 5.1.1 No such mailbox",
+                "554 5.5.1 No valid recipients",
+                "221 2.0.0 Bye",
+            ]
+        );
+        assert!(queued.is_empty());
+    }
+
+    #[tokio::test]
+    async fn recipients_over_the_limit_are_deferred() {
+        let mut input = "HELO client.test\r\nMAIL FROM:<a@example.org>\r\n".to_string();
+        for _ in 0..=MAX_RECIPIENTS {
+            input.push_str(&rcpt(&inbox_address()));
+        }
+        input.push_str("QUIT\r\n");
+        let (replies, _) = converse(input.as_bytes()).await;
+
+        let accepted = replies.iter().filter(|x| *x == "250 2.1.5 OK").count();
+        assert_eq!(accepted, MAX_RECIPIENTS);
+        assert_eq!(
+            replies[replies.len() - 2..],
+            ["452 4.5.3 Too many recipients", "221 2.0.0 Bye"]
+        );
+    }
+
+    #[tokio::test]
+    async fn long_lines_are_rejected_whole() {
+        let long = "x".repeat(MAX_LINE_BYTES as usize + 10);
+        let input = format!(
+            "NOOP {long}\r\nHELO client.test\r\nMAIL FROM:<a@example.org>\r\n{}DATA\r\n\
+             {long}\r\nafter\r\n.\r\nNOOP\r\nQUIT\r\n",
+            rcpt(&inbox_address()),
+        );
+        let (replies, queued) = converse(input.as_bytes()).await;
+
+        assert_eq!(
+            replies[1..],
+            [
+                "500 5.5.6 Line too long",
+                "250 example.com",
+                "250 2.1.0 OK",
+                "250 2.1.5 OK",
+                "354 End data with <CR><LF>.<CR><LF>",
+                "500 5.5.6 Line too long",
+                "250 2.0.0 OK",
+                "221 2.0.0 Bye",
+            ]
+        );
+        assert!(queued.is_empty());
+    }
+
+    #[tokio::test]
+    async fn messages_over_the_size_limit_are_refused() {
+        let line = format!("{}\r\n", "x".repeat(998));
+        let body = line.repeat(MAX_MESSAGE_BYTES / line.len() + 1);
+        let input = format!(
+            "HELO client.test\r\nMAIL FROM:<a@example.org> SIZE={}\r\n\
+             MAIL FROM:<a@example.org> SIZE=1000\r\n{}DATA\r\n{body}.\r\nQUIT\r\n",
+            MAX_MESSAGE_BYTES + 1,
+            rcpt(&inbox_address()),
+        );
+        let (replies, queued) = converse(input.as_bytes()).await;
+
+        assert_eq!(
+            replies[1..],
+            [
+                "250 example.com",
+                "552 5.3.4 Message too big",
+                "250 2.1.0 OK",
+                "250 2.1.5 OK",
+                "354 End data with <CR><LF>.<CR><LF>",
+                "552 5.3.4 Message too big",
+                "221 2.0.0 Bye",
+            ]
+        );
+        assert!(queued.is_empty());
+    }
+}
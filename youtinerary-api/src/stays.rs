@@ -0,0 +1,132 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::fields::{coordinates, invalid, optional_text, required_text, InvalidField};
+use crate::items::Item;
+use crate::timeline::{Coordinates, StayEntry};
+use crate::ItemVisibility;
+
+/// A stay as clients write it. The dates are the local check-in and check-out days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StayFields {
+    pub summary: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Local times at the place, such as `15:00`.
+    pub check_in_time: Option<NaiveTime>,
+    pub check_out_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub confirmation_number: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl StayFields {
+    /// Trims the text fields and checks the stay ends after it starts.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        if self.end_date < self.start_date {
+            return Err(invalid("end_date", "must not be before start_date"));
+        }
+        if let (Some(check_in_time), Some(check_out_time)) =
+            (self.check_in_time, self.check_out_time)
+        {
+            if self.end_date == self.start_date && check_out_time < check_in_time {
+                return Err(invalid(
+                    "check_out_time",
+                    "must not be before check_in_time",
+                ));
+            }
+        }
+
+        Ok(StayFields {
+            summary: required_text("summary", self.summary, 255)?,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            check_in_time: self.check_in_time,
+            check_out_time: self.check_out_time,
+            address: optional_text("address", self.address, 255)?,
+            location: coordinates("location", self.location)?,
+            confirmation_number: optional_text(
+                "confirmation_number",
+                self.confirmation_number,
+                50,
+            )?,
+            notes: optional_text("notes", Some(self.notes), 255)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<StayEntry> for StayFields {
+    fn from(value: StayEntry) -> Self {
+        Self {
+            summary: value.summary,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            check_in_time: value.check_in_time,
+            check_out_time: value.check_out_time,
+            address: value.address,
+            location: value.location,
+            confirmation_number: value.confirmation_number,
+            notes: value.notes,
+        }
+    }
+}
+
+/// Loads the stays of an itinerary in check-in order, or only `stay_id` when given.
+pub async fn get_stays(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    stay_id: Option<i32>,
+) -> sqlx::Result<Vec<StayEntry>> {
+    let stays = sqlx::query!(
+        r#"
+            select
+                s.id,
+                s.summary,
+                s.start_date,
+                s.end_date,
+                s.check_in_time,
+                s.check_out_time,
+                s.address,
+                s.location[0] as longitude,
+                s.location[1] as latitude,
+                s.confirmation_number,
+                s.notes,
+                i.id as item_id,
+                i.position,
+                i.visibility as "visibility: ItemVisibility"
+            from stays s
+                join itinerary_items i on i.id = s.itinerary_item_id
+            where i.itinerary_id = $1
+                and ($2::integer is null or s.id = $2)
+            order by s.start_date, s.check_in_time, i.position nulls last, s.id
+        "#,
+        itinerary_id,
+        stay_id
+    )
+    .fetch_all(connection)
+    .await?;
+
+    Ok(stays
+        .into_iter()
+        .map(|stay| StayEntry {
+            id: stay.id,
+            summary: stay.summary,
+            start_date: stay.start_date,
+            end_date: stay.end_date,
+            check_in_time: stay.check_in_time,
+            check_out_time: stay.check_out_time,
+            address: stay.address,
+            location: Coordinates::from_point(stay.longitude, stay.latitude),
+            confirmation_number: stay.confirmation_number,
+            item: Item {
+                id: stay.item_id,
+                position: stay.position,
+                visibility: stay.visibility,
+            },
+            notes: stay.notes,
+        })
+        .collect())
+}
@@ -0,0 +1,315 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::PgPool;
+
+use crate::activities::{get_activities, Cost};
+use crate::flights::{get_flights, FlightDetails};
+use crate::items::Item;
+use crate::local_time::to_local;
+use crate::stays::get_stays;
+use crate::travel_legs::{get_travel_legs, LegDetails};
+use crate::{ActivityCategory, TravelLegType};
+
+/// A position stored in a Postgres `point`, where `x` is the longitude and `y` the latitude.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    pub fn from_point(x: Option<f64>, y: Option<f64>) -> Option<Self> {
+        Some(Self {
+            latitude: y?,
+            longitude: x?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FlightEntry {
+    pub id: i32,
+    pub airline: String,
+    pub confirmation_code: String,
+    pub departure_time: DateTime<Utc>,
+    pub arrival_time: DateTime<Utc>,
+    pub departure_timezone: Option<Tz>,
+    pub arrival_timezone: Option<Tz>,
+    /// IATA codes of the airports, whose positions are kept alongside them.
+    pub departure_airport: Option<String>,
+    pub arrival_airport: Option<String>,
+    pub departure_location: Option<Coordinates>,
+    pub arrival_location: Option<Coordinates>,
+    /// Boxed so flights don't make every timeline entry as large as a boarding pass.
+    pub details: Box<FlightDetails>,
+    pub item: Item,
+    pub notes: String,
+}
+
+impl FlightEntry {
+    /// Wall clock time the flight leaves at, in UTC when the departure zone is unknown.
+    pub fn local_departure(&self) -> NaiveDateTime {
+        to_local(self.departure_time, self.departure_timezone)
+    }
+
+    /// Wall clock time the flight lands at, in UTC when the arrival zone is unknown.
+    pub fn local_arrival(&self) -> NaiveDateTime {
+        to_local(self.arrival_time, self.arrival_timezone)
+    }
+
+    /// The airline followed by the flight number when there is one, as printed on the pass.
+    pub fn title(&self) -> String {
+        match &self.details.flight_number {
+            Some(flight_number) => format!("{} {}", self.airline, flight_number),
+            None => self.airline.clone(),
+        }
+    }
+}
+
+/// Flights are written with their times both in UTC and as the local wall clock times printed
+/// on the ticket.
+impl Serialize for FlightEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct FlightView<'a> {
+            id: i32,
+            airline: &'a str,
+            confirmation_code: &'a str,
+            departure_time: DateTime<Utc>,
+            local_departure_time: NaiveDateTime,
+            departure_timezone: Option<Tz>,
+            departure_airport: Option<&'a str>,
+            departure_location: Option<Coordinates>,
+            arrival_time: DateTime<Utc>,
+            local_arrival_time: NaiveDateTime,
+            arrival_timezone: Option<Tz>,
+            arrival_airport: Option<&'a str>,
+            arrival_location: Option<Coordinates>,
+            #[serde(flatten)]
+            details: &'a FlightDetails,
+            #[serde(flatten)]
+            item: Item,
+            notes: &'a str,
+        }
+
+        FlightView {
+            id: self.id,
+            airline: &self.airline,
+            confirmation_code: &self.confirmation_code,
+            departure_time: self.departure_time,
+            local_departure_time: self.local_departure(),
+            departure_timezone: self.departure_timezone,
+            departure_airport: self.departure_airport.as_deref(),
+            departure_location: self.departure_location,
+            arrival_time: self.arrival_time,
+            local_arrival_time: self.local_arrival(),
+            arrival_timezone: self.arrival_timezone,
+            arrival_airport: self.arrival_airport.as_deref(),
+            arrival_location: self.arrival_location,
+            details: &self.details,
+            item: self.item,
+            notes: &self.notes,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StayEntry {
+    pub id: i32,
+    pub summary: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Local times at the place, when the booking gives them.
+    pub check_in_time: Option<NaiveTime>,
+    pub check_out_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub confirmation_number: Option<String>,
+    #[serde(flatten)]
+    pub item: Item,
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub id: i32,
+    pub summary: String,
+    pub category: ActivityCategory,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Local times on the first and last day, when the activity doesn't take the whole day.
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub address: Option<String>,
+    pub location: Option<Coordinates>,
+    pub booking_reference: Option<String>,
+    pub cost: Option<Cost>,
+    #[serde(flatten)]
+    pub item: Item,
+    pub notes: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TravelLegEntry {
+    pub id: i32,
+    pub name: String,
+    pub travel_leg_type: TravelLegType,
+    /// Local dates at each end, set from the times when the leg has them.
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// Zone of the place the leg starts from, its dates are local to it.
+    pub start_timezone: Option<Tz>,
+    pub end_timezone: Option<Tz>,
+    /// Stations, ports or, for cars, the pickup and drop-off desks.
+    pub start_place: Option<String>,
+    pub end_place: Option<String>,
+    pub start_location: Option<Coordinates>,
+    pub end_location: Option<Coordinates>,
+    pub operator: Option<String>,
+    pub booking_reference: Option<String>,
+    /// Boxed like the details of a flight.
+    pub details: Box<LegDetails>,
+    pub item: Item,
+    pub notes: String,
+}
+
+impl TravelLegEntry {
+    /// Wall clock time the leg leaves at, in UTC when the start zone is unknown.
+    pub fn local_start(&self) -> Option<NaiveDateTime> {
+        self.start_time
+            .map(|time| to_local(time, self.start_timezone))
+    }
+
+    /// Wall clock time the leg arrives at, in UTC when the end zone is unknown.
+    pub fn local_end(&self) -> Option<NaiveDateTime> {
+        self.end_time.map(|time| to_local(time, self.end_timezone))
+    }
+}
+
+/// Legs are written with their local times next to the UTC ones, like flights.
+impl Serialize for TravelLegEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct TravelLegView<'a> {
+            id: i32,
+            name: &'a str,
+            travel_leg_type: TravelLegType,
+            start_date: NaiveDate,
+            start_time: Option<DateTime<Utc>>,
+            local_start_time: Option<NaiveDateTime>,
+            start_timezone: Option<Tz>,
+            start_place: Option<&'a str>,
+            start_location: Option<Coordinates>,
+            end_date: NaiveDate,
+            end_time: Option<DateTime<Utc>>,
+            local_end_time: Option<NaiveDateTime>,
+            end_timezone: Option<Tz>,
+            end_place: Option<&'a str>,
+            end_location: Option<Coordinates>,
+            operator: Option<&'a str>,
+            booking_reference: Option<&'a str>,
+            #[serde(flatten)]
+            details: &'a LegDetails,
+            #[serde(flatten)]
+            item: Item,
+            notes: &'a str,
+        }
+
+        TravelLegView {
+            id: self.id,
+            name: &self.name,
+            travel_leg_type: self.travel_leg_type,
+            start_date: self.start_date,
+            start_time: self.start_time,
+            local_start_time: self.local_start(),
+            start_timezone: self.start_timezone,
+            start_place: self.start_place.as_deref(),
+            start_location: self.start_location,
+            end_date: self.end_date,
+            end_time: self.end_time,
+            local_end_time: self.local_end(),
+            end_timezone: self.end_timezone,
+            end_place: self.end_place.as_deref(),
+            end_location: self.end_location,
+            operator: self.operator.as_deref(),
+            booking_reference: self.booking_reference.as_deref(),
+            details: &self.details,
+            item: self.item,
+            notes: &self.notes,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// One item of an itinerary, tagged with its kind so clients can tell them apart.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEntry {
+    Flight(FlightEntry),
+    Stay(StayEntry),
+    Activity(ActivityEntry),
+    TravelLeg(TravelLegEntry),
+}
+
+impl TimelineEntry {
+    /// Wall clock time the entry starts at where it starts. Stays and activities only know local
+    /// times, so flights and travel legs are compared by theirs too. Entries without a time
+    /// start at midnight of their first day.
+    pub fn starts_at(&self) -> NaiveDateTime {
+        let start_of =
+            |date: NaiveDate, time: Option<NaiveTime>| date.and_time(time.unwrap_or_default());
+
+        match self {
+            Self::Flight(flight) => flight.local_departure(),
+            Self::Stay(stay) => start_of(stay.start_date, stay.check_in_time),
+            Self::Activity(activity) => start_of(activity.start_date, activity.start_time),
+            Self::TravelLeg(leg) => leg
+                .local_start()
+                .unwrap_or_else(|| start_of(leg.start_date, None)),
+        }
+    }
+
+    pub fn item(&self) -> &Item {
+        match self {
+            Self::Flight(flight) => &flight.item,
+            Self::Stay(stay) => &stay.item,
+            Self::Activity(activity) => &activity.item,
+            Self::TravelLeg(leg) => &leg.item,
+        }
+    }
+}
+
+pub trait TimelineRepository {
+    /// Loads every item of the itinerary, ordered by the local time it starts at and then by the
+    /// item's position.
+    async fn get_timeline(&self, itinerary_id: i32) -> Result<Vec<TimelineEntry>>;
+}
+
+impl TimelineRepository for PgPool {
+    async fn get_timeline(&self, itinerary_id: i32) -> Result<Vec<TimelineEntry>> {
+        let flights = get_flights(&mut *self.acquire().await?, itinerary_id, None).await?;
+
+        let stays = get_stays(&mut *self.acquire().await?, itinerary_id, None).await?;
+
+        let activities = get_activities(&mut *self.acquire().await?, itinerary_id, None).await?;
+
+        let travel_legs = get_travel_legs(&mut *self.acquire().await?, itinerary_id, None).await?;
+
+        let mut timeline: Vec<TimelineEntry> = flights
+            .into_iter()
+            .map(TimelineEntry::Flight)
+            .chain(stays.into_iter().map(TimelineEntry::Stay))
+            .chain(activities.into_iter().map(TimelineEntry::Activity))
+            .chain(travel_legs.into_iter().map(TimelineEntry::TravelLeg))
+            .collect();
+
+        timeline.sort_by_key(|entry| (entry.starts_at(), entry.item().sort_key()));
+
+        Ok(timeline)
+    }
+}
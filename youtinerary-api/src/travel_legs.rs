@@ -0,0 +1,273 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+
+use crate::fields::{coordinates, invalid, optional_text, required_text, InvalidField};
+use crate::items::Item;
+use crate::local_time::{parse_stored_timezone, resolve_instant, to_local, TimeInputError};
+use crate::timeline::{Coordinates, TravelLegEntry};
+use crate::{ItemVisibility, TravelLegType};
+
+/// Fields that only some modes of transport use. For cars, the start and end places of the
+/// leg are the pickup and drop-off desks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LegDetails {
+    /// Train, bus or ferry number, such as `TGV 6201`.
+    pub service_number: Option<String>,
+    /// Train coach, printed next to the seat on the ticket.
+    pub coach: Option<String>,
+    pub seat: Option<String>,
+    pub platform: Option<String>,
+    /// Class or model of a rental car, or the vehicle taken on board a ferry.
+    pub vehicle: Option<String>,
+    /// Ferry cabin number.
+    pub cabin: Option<String>,
+}
+
+impl LegDetails {
+    /// Trims every field and rejects those that don't apply to `mode`, so a ferry cabin can't
+    /// end up on a train.
+    pub fn validated(self, mode: TravelLegType) -> Result<Self, InvalidField> {
+        use TravelLegType::*;
+
+        let field = |name: &'static str,
+                     value: Option<String>,
+                     max: usize,
+                     modes: &[TravelLegType]|
+         -> Result<Option<String>, InvalidField> {
+            let value = optional_text(name, value, max)?;
+            if value.is_some() && !modes.contains(&mode) {
+                return Err(invalid(name, "does not apply to this mode of transport"));
+            }
+            Ok(value)
+        };
+
+        Ok(LegDetails {
+            service_number: field(
+                "service_number",
+                self.service_number,
+                16,
+                &[Train, Bus, Ferry, Flight, Other],
+            )?,
+            coach: field("coach", self.coach, 8, &[Train])?,
+            seat: field("seat", self.seat, 8, &[Train, Bus, Ferry, Flight, Other])?,
+            platform: field("platform", self.platform, 8, &[Train, Bus])?,
+            vehicle: field("vehicle", self.vehicle, 64, &[Car, Ferry])?,
+            cabin: field("cabin", self.cabin, 16, &[Ferry])?,
+        })
+    }
+}
+
+/// A travel leg as it is stored, once its times are resolved.
+#[derive(Debug, Clone)]
+pub struct TravelLegFields {
+    pub name: String,
+    pub travel_leg_type: TravelLegType,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub start_timezone: Option<Tz>,
+    pub end_timezone: Option<Tz>,
+    pub start_place: Option<String>,
+    pub end_place: Option<String>,
+    pub start_location: Option<Coordinates>,
+    pub end_location: Option<Coordinates>,
+    pub operator: Option<String>,
+    pub booking_reference: Option<String>,
+    pub details: LegDetails,
+    pub notes: String,
+}
+
+impl TravelLegFields {
+    /// Trims the text fields and checks the leg ends after it starts.
+    pub fn validated(self) -> Result<Self, InvalidField> {
+        if self.end_date < self.start_date {
+            return Err(invalid("end_date", "must not be before start_date"));
+        }
+        if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+            if end_time < start_time {
+                return Err(invalid("end_time", "must not be before start_time"));
+            }
+        }
+
+        Ok(TravelLegFields {
+            name: required_text("name", self.name, 255)?,
+            travel_leg_type: self.travel_leg_type,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            start_timezone: self.start_timezone,
+            end_timezone: self.end_timezone,
+            start_place: optional_text("start_place", self.start_place, 255)?,
+            end_place: optional_text("end_place", self.end_place, 255)?,
+            start_location: coordinates("start_location", self.start_location)?,
+            end_location: coordinates("end_location", self.end_location)?,
+            operator: optional_text("operator", self.operator, 255)?,
+            booking_reference: optional_text("booking_reference", self.booking_reference, 50)?,
+            details: self.details.validated(self.travel_leg_type)?,
+            notes: optional_text("notes", Some(self.notes), 255)?.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<TravelLegEntry> for TravelLegFields {
+    fn from(value: TravelLegEntry) -> Self {
+        Self {
+            name: value.name,
+            travel_leg_type: value.travel_leg_type,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            start_timezone: value.start_timezone,
+            end_timezone: value.end_timezone,
+            start_place: value.start_place,
+            end_place: value.end_place,
+            start_location: value.start_location,
+            end_location: value.end_location,
+            operator: value.operator,
+            booking_reference: value.booking_reference,
+            details: *value.details,
+            notes: value.notes,
+        }
+    }
+}
+
+/// Why the fields of a travel leg being created or updated were rejected.
+#[derive(Debug)]
+pub enum TravelLegInputError {
+    InvalidField(InvalidField),
+    InvalidTime(&'static str, TimeInputError),
+}
+
+impl From<InvalidField> for TravelLegInputError {
+    fn from(value: InvalidField) -> Self {
+        Self::InvalidField(value)
+    }
+}
+
+impl IntoResponse for TravelLegInputError {
+    fn into_response(self) -> Response {
+        let message = match self {
+            Self::InvalidField(error) => error.to_string(),
+            Self::InvalidTime(end, error) => format!("Invalid {} time: {}", end, error),
+        };
+
+        (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
+    }
+}
+
+/// Works out when one end of a leg happens. A time, given as an instant or as the local time
+/// printed on the ticket, sets the local date in the end's timezone. Without a time the date
+/// has to be given, and with one a given date must match it.
+pub fn resolve_end(
+    end: &'static str,
+    date_field: &'static str,
+    date: Option<NaiveDate>,
+    instant: Option<DateTime<Utc>>,
+    local: Option<NaiveDateTime>,
+    timezone: Option<Tz>,
+) -> Result<(NaiveDate, Option<DateTime<Utc>>), TravelLegInputError> {
+    if instant.is_none() && local.is_none() {
+        let date = date.ok_or(invalid(date_field, "is required without a time"))?;
+        return Ok((date, None));
+    }
+
+    let instant = resolve_instant(instant, local, timezone)
+        .map_err(|error| TravelLegInputError::InvalidTime(end, error))?;
+    let local_date = to_local(instant, timezone).date();
+    if date.is_some_and(|date| date != local_date) {
+        return Err(invalid(date_field, "does not match the local date of its time").into());
+    }
+
+    Ok((local_date, Some(instant)))
+}
+
+/// Loads the travel legs of an itinerary in the order they start, or only `leg_id` when given.
+pub async fn get_travel_legs(
+    connection: &mut PgConnection,
+    itinerary_id: i32,
+    leg_id: Option<i32>,
+) -> sqlx::Result<Vec<TravelLegEntry>> {
+    let legs = sqlx::query!(
+        r#"
+            select
+                t.id,
+                t.name,
+                t.travel_leg_type as "travel_leg_type: TravelLegType",
+                t.start_date,
+                t.end_date,
+                t.start_time,
+                t.end_time,
+                t.start_timezone,
+                t.end_timezone,
+                t.start_place,
+                t.end_place,
+                t.start_location[0] as start_longitude,
+                t.start_location[1] as start_latitude,
+                t.end_location[0] as end_longitude,
+                t.end_location[1] as end_latitude,
+                t.operator,
+                t.booking_reference,
+                t.service_number,
+                t.coach,
+                t.seat,
+                t.platform,
+                t.vehicle,
+                t.cabin,
+                t.notes,
+                i.id as item_id,
+                i.position,
+                i.visibility as "visibility: ItemVisibility"
+            from travel_legs t
+                join itinerary_items i on i.id = t.itinerary_item_id
+            where i.itinerary_id = $1
+                and ($2::integer is null or t.id = $2)
+            order by t.start_date, t.start_time nulls first, i.position nulls last, t.id
+        "#,
+        itinerary_id,
+        leg_id
+    )
+    .fetch_all(connection)
+    .await?;
+
+    Ok(legs
+        .into_iter()
+        .map(|leg| TravelLegEntry {
+            id: leg.id,
+            name: leg.name,
+            travel_leg_type: leg.travel_leg_type,
+            start_date: leg.start_date,
+            end_date: leg.end_date,
+            start_time: leg.start_time,
+            end_time: leg.end_time,
+            start_timezone: parse_stored_timezone(leg.start_timezone),
+            end_timezone: parse_stored_timezone(leg.end_timezone),
+            start_place: leg.start_place,
+            end_place: leg.end_place,
+            start_location: Coordinates::from_point(leg.start_longitude, leg.start_latitude),
+            end_location: Coordinates::from_point(leg.end_longitude, leg.end_latitude),
+            operator: leg.operator,
+            booking_reference: leg.booking_reference,
+            details: Box::new(LegDetails {
+                service_number: leg.service_number,
+                coach: leg.coach,
+                seat: leg.seat,
+                platform: leg.platform,
+                vehicle: leg.vehicle,
+                cabin: leg.cabin,
+            }),
+            item: Item {
+                id: leg.item_id,
+                position: leg.position,
+                visibility: leg.visibility,
+            },
+            notes: leg.notes,
+        })
+        .collect())
+}
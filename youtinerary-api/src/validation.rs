@@ -0,0 +1,475 @@
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::timeline::{FlightEntry, TimelineEntry, TimelineRepository};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    ArrivalBeforeDeparture,
+    OverlappingFlights,
+    MissedConnection,
+    OverlappingStays,
+    NightsWithoutStay,
+}
+
+/// An item an issue is about, named the way the timeline tags its entries.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IssueItem {
+    #[serde(rename = "type")]
+    pub item_type: &'static str,
+    pub id: i32,
+}
+
+/// Something in an itinerary that is probably a mistake. Issues never block a write.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<IssueItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nights: Vec<NaiveDate>,
+}
+
+/// Body of responses to writes, so clients see the consequences of a change right away.
+#[derive(Debug, Serialize)]
+pub struct WriteResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub issues: Vec<Issue>,
+}
+
+fn flight_item(flight: &FlightEntry) -> IssueItem {
+    IssueItem {
+        item_type: "flight",
+        id: flight.id,
+    }
+}
+
+fn describe_flight(flight: &FlightEntry) -> String {
+    format!(
+        "The {} flight {} leaving {}",
+        flight.airline,
+        flight
+            .details
+            .flight_number
+            .as_ref()
+            .unwrap_or(&flight.confirmation_code),
+        flight.local_departure().format("%Y-%m-%d %H:%M")
+    )
+}
+
+/// Scans an itinerary for schedule conflicts.
+///
+/// `dates` is the itinerary's effective date range. Nights are only checked when it is known,
+/// a night counting as covered by a stay, an overnight flight or an overnight travel leg.
+pub fn find_issues(
+    dates: Option<(NaiveDate, NaiveDate)>,
+    timeline: &[TimelineEntry],
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let mut flights: Vec<&FlightEntry> = timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            TimelineEntry::Flight(flight) => Some(flight),
+            _ => None,
+        })
+        .collect();
+    flights.sort_by_key(|flight| flight.departure_time);
+
+    for flight in &flights {
+        if flight.arrival_time < flight.departure_time {
+            issues.push(Issue {
+                kind: IssueKind::ArrivalBeforeDeparture,
+                message: format!("{} arrives before it departs", describe_flight(flight)),
+                items: vec![flight_item(flight)],
+                nights: Vec::new(),
+            });
+        }
+    }
+
+    for (index, first) in flights.iter().enumerate() {
+        for second in &flights[index + 1..] {
+            if second.departure_time < first.arrival_time.max(first.departure_time) {
+                issues.push(Issue {
+                    kind: IssueKind::OverlappingFlights,
+                    message: format!(
+                        "{} departs before {} has landed",
+                        describe_flight(second),
+                        describe_flight(first).to_lowercase()
+                    ),
+                    items: vec![flight_item(first), flight_item(second)],
+                    nights: Vec::new(),
+                });
+            }
+        }
+    }
+
+    let mut legs: Vec<_> = timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            TimelineEntry::TravelLeg(leg) => Some(leg),
+            _ => None,
+        })
+        .collect();
+    legs.sort_by_key(|leg| (leg.start_date, leg.start_time, leg.id));
+    for flight in &flights {
+        let departure_date = flight.local_departure().date();
+        let arrival_date = flight.local_arrival().date();
+        // Legs with a start time are compared to the flight's instants, the others by date.
+        let next_leg = legs.iter().find(|leg| match leg.start_time {
+            Some(start_time) => start_time >= flight.departure_time,
+            None => leg.start_date >= departure_date,
+        });
+        let missed = next_leg.filter(|leg| match leg.start_time {
+            Some(start_time) => start_time < flight.arrival_time,
+            None => leg.start_date < arrival_date,
+        });
+        if let Some(leg) = missed {
+            let message = match leg.local_start() {
+                Some(local_start) => format!(
+                    "{} lands at {}, after {} leaves at {}",
+                    describe_flight(flight),
+                    flight.local_arrival().format("%Y-%m-%d %H:%M"),
+                    leg.name,
+                    local_start.format("%Y-%m-%d %H:%M")
+                ),
+                None => format!(
+                    "{} lands on {}, after {} starts on {}",
+                    describe_flight(flight),
+                    arrival_date,
+                    leg.name,
+                    leg.start_date
+                ),
+            };
+            issues.push(Issue {
+                kind: IssueKind::MissedConnection,
+                message,
+                items: vec![
+                    flight_item(flight),
+                    IssueItem {
+                        item_type: "travel_leg",
+                        id: leg.id,
+                    },
+                ],
+                nights: Vec::new(),
+            });
+        }
+    }
+
+    let mut stays: Vec<_> = timeline
+        .iter()
+        .filter_map(|entry| match entry {
+            TimelineEntry::Stay(stay) => Some(stay),
+            _ => None,
+        })
+        .collect();
+    stays.sort_by_key(|stay| (stay.start_date, stay.id));
+    for (index, first) in stays.iter().enumerate() {
+        for second in &stays[index + 1..] {
+            // Checking out and in on the same day is fine, sharing a night is not.
+            if second.start_date < first.end_date && first.start_date < second.end_date {
+                issues.push(Issue {
+                    kind: IssueKind::OverlappingStays,
+                    message: format!(
+                        "{} ({} to {}) and {} ({} to {}) share nights",
+                        first.summary,
+                        first.start_date,
+                        first.end_date,
+                        second.summary,
+                        second.start_date,
+                        second.end_date
+                    ),
+                    items: vec![
+                        IssueItem {
+                            item_type: "stay",
+                            id: first.id,
+                        },
+                        IssueItem {
+                            item_type: "stay",
+                            id: second.id,
+                        },
+                    ],
+                    nights: Vec::new(),
+                });
+            }
+        }
+    }
+
+    if let Some((start_date, end_date)) = dates {
+        let covered = |night: NaiveDate| {
+            timeline.iter().any(|entry| match entry {
+                TimelineEntry::Stay(stay) => stay.start_date <= night && night < stay.end_date,
+                TimelineEntry::Flight(flight) => {
+                    flight.local_departure().date() <= night
+                        && night < flight.local_arrival().date()
+                }
+                TimelineEntry::TravelLeg(leg) => leg.start_date <= night && night < leg.end_date,
+                TimelineEntry::Activity(_) => false,
+            })
+        };
+
+        // Consecutive uncovered nights are reported together.
+        let mut gaps: Vec<Vec<NaiveDate>> = Vec::new();
+        let mut previous: Option<NaiveDate> = None;
+        for night in start_date
+            .iter_days()
+            .take_while(|night| *night < end_date)
+            .filter(|night| !covered(*night))
+        {
+            match gaps.last_mut() {
+                Some(gap) if previous == Some(night - Duration::days(1)) => gap.push(night),
+                _ => gaps.push(vec![night]),
+            }
+            previous = Some(night);
+        }
+
+        for nights in gaps {
+            let message = match nights.as_slice() {
+                [night] => format!("Nothing is booked for the night of {}", night),
+                [first, .., last] => {
+                    format!("Nothing is booked for the nights of {} to {}", first, last)
+                }
+                [] => continue,
+            };
+            issues.push(Issue {
+                kind: IssueKind::NightsWithoutStay,
+                message,
+                items: Vec::new(),
+                nights,
+            });
+        }
+    }
+
+    issues
+}
+
+pub trait IssuesRepository {
+    /// Validates an itinerary as it is stored. Callers check that the user may see it.
+    async fn get_issues(&self, itinerary_id: i32) -> Result<Vec<Issue>>;
+}
+
+impl IssuesRepository for PgPool {
+    async fn get_issues(&self, itinerary_id: i32) -> Result<Vec<Issue>> {
+        let dates = sqlx::query!(
+            r#"
+                select start_date, end_date
+                from itinerary_dates
+                where itinerary_id = $1
+            "#,
+            itinerary_id
+        )
+        .fetch_optional(self)
+        .await?
+        .and_then(|x| Some((x.start_date?, x.end_date?)));
+        let timeline = self.get_timeline(itinerary_id).await?;
+
+        Ok(find_issues(dates, &timeline))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Tz;
+
+    use super::*;
+    use crate::items::Item;
+    use crate::timeline::{StayEntry, TravelLegEntry};
+    use crate::{ItemVisibility, TravelLegType};
+
+    fn item(id: i32) -> Item {
+        Item {
+            id,
+            position: None,
+            visibility: ItemVisibility::Everyone,
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn flight(id: i32, departure_time: &str, arrival_time: &str) -> TimelineEntry {
+        TimelineEntry::Flight(FlightEntry {
+            id,
+            airline: "KLM".into(),
+            confirmation_code: format!("CODE{}", id),
+            departure_time: departure_time.parse().unwrap(),
+            arrival_time: arrival_time.parse().unwrap(),
+            departure_timezone: Some(Tz::UTC),
+            arrival_timezone: Some(Tz::UTC),
+            departure_airport: None,
+            arrival_airport: None,
+            departure_location: None,
+            arrival_location: None,
+            details: Box::default(),
+            item: item(id),
+            notes: String::new(),
+        })
+    }
+
+    fn stay(id: i32, start_date: &str, end_date: &str) -> TimelineEntry {
+        TimelineEntry::Stay(StayEntry {
+            id,
+            summary: format!("Hotel {}", id),
+            start_date: date(start_date),
+            end_date: date(end_date),
+            check_in_time: None,
+            check_out_time: None,
+            address: None,
+            location: None,
+            confirmation_number: None,
+            item: item(id),
+            notes: String::new(),
+        })
+    }
+
+    fn leg(id: i32, start_date: &str, start_time: Option<&str>) -> TimelineEntry {
+        TimelineEntry::TravelLeg(TravelLegEntry {
+            id,
+            name: format!("Train {}", id),
+            travel_leg_type: TravelLegType::Train,
+            start_date: date(start_date),
+            end_date: date(start_date),
+            start_time: start_time.map(|x| x.parse().unwrap()),
+            end_time: None,
+            start_timezone: Some(Tz::UTC),
+            end_timezone: Some(Tz::UTC),
+            start_place: None,
+            end_place: None,
+            start_location: None,
+            end_location: None,
+            operator: None,
+            booking_reference: None,
+            details: Box::default(),
+            item: item(id),
+            notes: String::new(),
+        })
+    }
+
+    fn kinds(issues: &[Issue]) -> Vec<IssueKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn flights_in_the_air_at_the_same_time_overlap() {
+        let timeline = [
+            flight(1, "2026-05-01T08:00:00Z", "2026-05-01T12:00:00Z"),
+            flight(2, "2026-05-01T11:00:00Z", "2026-05-01T14:00:00Z"),
+            flight(3, "2026-05-01T14:00:00Z", "2026-05-01T16:00:00Z"),
+        ];
+
+        let issues = find_issues(None, &timeline);
+
+        assert_eq!(kinds(&issues), [IssueKind::OverlappingFlights]);
+        let ids: Vec<i32> = issues[0].items.iter().map(|item| item.id).collect();
+        assert_eq!(ids, [1, 2]);
+    }
+
+    #[test]
+    fn flights_landing_before_they_leave_are_reported() {
+        let timeline = [flight(1, "2026-05-01T12:00:00Z", "2026-05-01T08:00:00Z")];
+
+        let issues = find_issues(None, &timeline);
+
+        assert_eq!(kinds(&issues), [IssueKind::ArrivalBeforeDeparture]);
+    }
+
+    #[test]
+    fn timed_legs_leaving_before_the_flight_lands_are_missed() {
+        let timeline = [
+            flight(1, "2026-05-01T08:00:00Z", "2026-05-01T12:00:00Z"),
+            leg(2, "2026-05-01", Some("2026-05-01T11:30:00Z")),
+        ];
+
+        let issues = find_issues(None, &timeline);
+
+        assert_eq!(kinds(&issues), [IssueKind::MissedConnection]);
+        assert_eq!(
+            issues[0].message,
+            "The KLM flight CODE1 leaving 2026-05-01 08:00 lands at 2026-05-01 12:00, after \
+             Train 2 leaves at 2026-05-01 11:30"
+        );
+    }
+
+    #[test]
+    fn timed_legs_leaving_after_the_flight_lands_are_fine() {
+        let timeline = [
+            flight(1, "2026-05-01T08:00:00Z", "2026-05-01T12:00:00Z"),
+            leg(2, "2026-05-01", Some("2026-05-01T14:00:00Z")),
+        ];
+
+        assert!(find_issues(None, &timeline).is_empty());
+    }
+
+    #[test]
+    fn untimed_legs_fall_back_to_dates() {
+        let overnight = flight(1, "2026-05-01T22:00:00Z", "2026-05-02T06:00:00Z");
+
+        let missed = find_issues(None, &[overnight.clone(), leg(2, "2026-05-01", None)]);
+        let caught = find_issues(None, &[overnight, leg(2, "2026-05-02", None)]);
+
+        assert_eq!(kinds(&missed), [IssueKind::MissedConnection]);
+        assert!(caught.is_empty());
+    }
+
+    #[test]
+    fn stays_sharing_a_night_overlap() {
+        let timeline = [
+            stay(1, "2026-05-01", "2026-05-04"),
+            stay(2, "2026-05-03", "2026-05-05"),
+        ];
+
+        let issues = find_issues(None, &timeline);
+
+        assert_eq!(kinds(&issues), [IssueKind::OverlappingStays]);
+    }
+
+    #[test]
+    fn checking_out_and_in_on_the_same_day_is_fine() {
+        let timeline = [
+            stay(1, "2026-05-01", "2026-05-03"),
+            stay(2, "2026-05-03", "2026-05-05"),
+        ];
+
+        assert!(find_issues(None, &timeline).is_empty());
+    }
+
+    #[test]
+    fn consecutive_uncovered_nights_are_grouped() {
+        let timeline = [
+            stay(1, "2026-05-01", "2026-05-02"),
+            stay(2, "2026-05-04", "2026-05-05"),
+        ];
+
+        let issues = find_issues(Some((date("2026-05-01"), date("2026-05-07"))), &timeline);
+
+        assert_eq!(
+            kinds(&issues),
+            [IssueKind::NightsWithoutStay, IssueKind::NightsWithoutStay]
+        );
+        assert_eq!(issues[0].nights, [date("2026-05-02"), date("2026-05-03")]);
+        assert_eq!(
+            issues[0].message,
+            "Nothing is booked for the nights of 2026-05-02 to 2026-05-03"
+        );
+        assert_eq!(issues[1].nights, [date("2026-05-05"), date("2026-05-06")]);
+    }
+
+    #[test]
+    fn overnight_flights_cover_their_night() {
+        let timeline = [
+            stay(1, "2026-05-01", "2026-05-02"),
+            flight(2, "2026-05-02T22:00:00Z", "2026-05-03T06:00:00Z"),
+            stay(3, "2026-05-03", "2026-05-04"),
+        ];
+
+        let issues = find_issues(Some((date("2026-05-01"), date("2026-05-04"))), &timeline);
+
+        assert!(issues.is_empty());
+    }
+}
@@ -59,34 +59,34 @@ struct Oath2State {
 }
 
 trait SessionManager {
-    async fn get_session<'a>(&self, session_id: &'a str) -> Result<Option<Session>>;
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>>;
     async fn set_session(&self, session: &Session) -> Result<String>;
     async fn set_verifier(&self, csrf: &CsrfToken, state: &Oath2State) -> Result<()>;
     async fn get_verifier(&self, csrf: &CsrfToken) -> Result<Oath2State>;
 }
 
 impl SessionManager for redis::Client {
-    async fn get_session<'a>(&self, session_id: &'a str) -> Result<Option<Session>> {
+    async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
         let mut con = self.get_async_connection().await?;
         let session: String = con.get(session_id).await?;
         let session: Session = serde_json::from_str(&session)?;
-        con.expire(session.id(), 300).await?;
+        con.expire::<_, ()>(session.id(), 300).await?;
         Ok(Some(session))
     }
 
     async fn set_session(&self, session: &Session) -> Result<String> {
         let mut con = self.get_async_connection().await?;
-        con.set(session.id(), serde_json::to_string(session)?)
+        con.set::<_, _, ()>(session.id(), serde_json::to_string(session)?)
             .await?;
-        con.expire(session.id(), 300).await?;
+        con.expire::<_, ()>(session.id(), 300).await?;
         Ok(session.id().to_string())
     }
 
     async fn set_verifier(&self, csrf: &CsrfToken, state: &Oath2State) -> Result<()> {
         let mut con = self.get_async_connection().await?;
-        con.set(csrf.secret(), serde_json::to_string(state)?)
+        con.set::<_, _, ()>(csrf.secret(), serde_json::to_string(state)?)
             .await?;
-        con.expire(csrf.secret(), 300).await?;
+        con.expire::<_, ()>(csrf.secret(), 300).await?;
         Ok(())
     }
 